@@ -0,0 +1,14 @@
+-- Create note_revisions table
+CREATE TABLE note_revisions(
+    revision_id UUID NOT NULL PRIMARY KEY,
+    note_id UUID NOT NULL,
+    FOREIGN KEY (note_id) REFERENCES notes(note_id) ON DELETE CASCADE,
+    revision_number INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(note_id, revision_number)
+);
+
+-- Create indexes
+CREATE INDEX idx_note_revisions_note_id ON note_revisions(note_id);
@@ -0,0 +1,2 @@
+-- Version counter used for ETag / If-Match concurrency checks
+ALTER TABLE notes ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,5 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX idx_notes_title_trgm ON notes USING GIN(title gin_trgm_ops);
+
+CREATE INDEX idx_notes_content_trgm ON notes USING GIN(content gin_trgm_ops);
@@ -0,0 +1,30 @@
+-- Text search configuration used for each user's notes
+ALTER TABLE users ADD COLUMN search_language TEXT NOT NULL DEFAULT 'english';
+
+ALTER TABLE notes ADD COLUMN search_vector tsvector;
+
+-- Keeps search_vector in step with the note text, stemmed for its owner
+CREATE FUNCTION notes_search_vector_update() RETURNS trigger AS $$
+BEGIN
+    NEW.search_vector := to_tsvector(
+        (SELECT search_language FROM users WHERE user_id = NEW.user_id)::regconfig,
+        NEW.title || ' ' || NEW.content
+    );
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER notes_search_vector_update
+    BEFORE INSERT OR UPDATE OF title, content ON notes
+    FOR EACH ROW EXECUTE FUNCTION notes_search_vector_update();
+
+UPDATE notes n
+SET search_vector = to_tsvector(u.search_language::regconfig, n.title || ' ' || n.content)
+FROM users u
+WHERE u.user_id = n.user_id;
+
+ALTER TABLE notes ALTER COLUMN search_vector SET NOT NULL;
+
+DROP INDEX idx_notes_search;
+
+CREATE INDEX idx_notes_search_vector ON notes USING GIN(search_vector);
@@ -0,0 +1,22 @@
+-- Weight title matches (A) above content matches (B) when ranking
+CREATE FUNCTION note_search_vector(config regconfig, title TEXT, content TEXT)
+RETURNS tsvector AS $$
+    SELECT setweight(to_tsvector(config, title), 'A') || setweight(to_tsvector(config, content), 'B')
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION notes_search_vector_update() RETURNS trigger AS $$
+BEGIN
+    NEW.search_vector := note_search_vector(
+        (SELECT search_language FROM users WHERE user_id = NEW.user_id)::regconfig,
+        NEW.title,
+        NEW.content
+    );
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+-- Backfill existing notes with the weighted vector
+UPDATE notes n
+SET search_vector = note_search_vector(u.search_language::regconfig, n.title, n.content)
+FROM users u
+WHERE u.user_id = n.user_id;
@@ -0,0 +1,14 @@
+-- Create saved_searches table
+CREATE TABLE saved_searches(
+    search_id UUID NOT NULL PRIMARY KEY,
+    user_id UUID NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(user_id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    query JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(user_id, name)
+);
+
+-- Create indexes
+CREATE INDEX idx_saved_searches_user_id ON saved_searches(user_id);
@@ -0,0 +1,26 @@
+-- Create notebooks table
+CREATE TABLE notebooks(
+    notebook_id UUID NOT NULL PRIMARY KEY,
+    user_id UUID NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(user_id) ON DELETE CASCADE,
+    -- Deleting a notebook deletes its sub-notebooks too
+    parent_id UUID,
+    FOREIGN KEY (parent_id) REFERENCES notebooks(notebook_id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Names are unique among siblings, top-level notebooks included
+CREATE UNIQUE INDEX idx_notebooks_sibling_name ON notebooks(
+    user_id,
+    COALESCE(parent_id, '00000000-0000-0000-0000-000000000000'::uuid),
+    name
+);
+CREATE INDEX idx_notebooks_parent_id ON notebooks(parent_id);
+
+-- Notes in a deleted notebook become unfiled
+ALTER TABLE notes ADD COLUMN notebook_id UUID;
+ALTER TABLE notes ADD FOREIGN KEY (notebook_id) REFERENCES notebooks(notebook_id) ON DELETE SET NULL;
+
+CREATE INDEX idx_notes_notebook_id ON notes(notebook_id);
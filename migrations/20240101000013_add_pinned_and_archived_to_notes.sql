@@ -0,0 +1,3 @@
+-- Pinned notes are listed first, archived notes are hidden from listings by default
+ALTER TABLE notes ADD COLUMN pinned BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE notes ADD COLUMN archived BOOLEAN NOT NULL DEFAULT FALSE;
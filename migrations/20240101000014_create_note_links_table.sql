@@ -0,0 +1,29 @@
+-- Links written as [[Title]] or [[note:uuid]] in note content. Targets are
+-- resolved when read, so a link starts resolving once its target is created
+-- and becomes dangling again when the target is deleted.
+CREATE TABLE note_links(
+    source_note_id UUID NOT NULL,
+    FOREIGN KEY (source_note_id) REFERENCES notes(note_id) ON DELETE CASCADE,
+    target_note_id UUID,
+    target_title TEXT,
+    CHECK ((target_note_id IS NULL) <> (target_title IS NULL))
+);
+
+CREATE INDEX idx_note_links_source_note_id ON note_links(source_note_id);
+CREATE INDEX idx_note_links_target_note_id ON note_links(target_note_id);
+CREATE INDEX idx_note_links_target_title ON note_links(lower(target_title));
+
+-- Backfill links from existing content, mirroring NoteLink::parse_all
+INSERT INTO note_links (source_note_id, target_note_id, target_title)
+SELECT DISTINCT
+    n.note_id,
+    CASE WHEN l.target ~* '^note:[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$'
+        THEN substr(l.target, 6)::uuid END,
+    CASE WHEN l.target ~* '^note:[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$'
+        THEN NULL ELSE l.target END
+FROM notes n
+CROSS JOIN LATERAL (
+    SELECT btrim(m[1]) AS target
+    FROM regexp_matches(n.content, '\[\[([^][\n]+)\]\]', 'g') AS m
+) l
+WHERE l.target <> '';
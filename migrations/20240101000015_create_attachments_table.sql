@@ -0,0 +1,17 @@
+-- Files attached to notes. Their contents live in the configured storage
+-- backend under storage_key; deleting a row does not delete the file.
+CREATE TABLE attachments(
+    attachment_id UUID NOT NULL PRIMARY KEY,
+    note_id UUID NOT NULL,
+    FOREIGN KEY (note_id) REFERENCES notes(note_id) ON DELETE CASCADE,
+    user_id UUID NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(user_id) ON DELETE CASCADE,
+    filename TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL CHECK (size_bytes >= 0),
+    storage_key TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_attachments_note_id ON attachments(note_id);
+CREATE INDEX idx_attachments_user_id ON attachments(user_id);
@@ -1,6 +1,12 @@
+use crate::authentication::{validate_api_token, AuthError};
+use crate::domain::TokenScope;
 use actix_session::SessionExt;
 use actix_web::dev::Payload;
-use actix_web::{FromRequest, HttpRequest};
+use actix_web::http::{header, Method};
+use actix_web::{web, FromRequest, HttpRequest};
+use futures::future::LocalBoxFuture;
+use secrecy::SecretString;
+use sqlx::PgPool;
 use std::future::{ready, Ready};
 use uuid::Uuid;
 
@@ -40,19 +46,87 @@ pub struct AuthenticatedUser {
     pub user_id: Uuid,
 }
 
+/// Accepts either an `Authorization: Bearer` API token or the session cookie.
+/// A bearer token takes precedence when both are present.
 impl FromRequest for AuthenticatedUser {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        if let Some(token) = bearer_token(req) {
+            let pool = req.app_data::<web::Data<PgPool>>().cloned();
+            let is_read_only = matches!(*req.method(), Method::GET | Method::HEAD);
+
+            return Box::pin(async move {
+                let pool = pool.ok_or_else(|| {
+                    actix_web::error::ErrorInternalServerError("Database pool is not configured")
+                })?;
+                let owner = validate_api_token(token, &pool)
+                    .await
+                    .map_err(|e| match e {
+                        AuthError::InvalidCredentials(_) => actix_web::error::ErrorUnauthorized(e),
+                        AuthError::UnexpectedError(_) => {
+                            actix_web::error::ErrorInternalServerError(e)
+                        }
+                    })?;
+
+                let is_allowed = owner.scopes.contains(&TokenScope::Write)
+                    || (is_read_only && owner.scopes.contains(&TokenScope::Read));
+                if !is_allowed {
+                    return Err(actix_web::error::ErrorForbidden(
+                        "This API token does not have the `write` scope",
+                    ));
+                }
+
+                Ok(AuthenticatedUser {
+                    user_id: owner.user_id,
+                })
+            });
+        }
+
+        let result = session_user_id(req).map(|user_id| AuthenticatedUser { user_id });
+        Box::pin(ready(result))
+    }
+}
+
+/// A user authenticated by the session cookie only. Guards the routes that
+/// manage API tokens, so a leaked token can't mint or revoke others.
+#[derive(Debug)]
+pub struct SessionUser {
+    pub user_id: Uuid,
+}
+
+impl FromRequest for SessionUser {
     type Error = actix_web::Error;
     type Future = Ready<Result<Self, Self::Error>>;
 
     fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
-        let session = req.get_session();
-        match session.get::<Uuid>(TypedSession::USER_ID_KEY) {
-            Ok(Some(user_id)) => ready(Ok(AuthenticatedUser { user_id })),
-            Ok(None) => ready(Err(actix_web::error::ErrorUnauthorized(
-                "
-            You are not logged in. Please log in and try again",
-            ))),
-            Err(e) => ready(Err(actix_web::error::ErrorInternalServerError(e))),
+        if bearer_token(req).is_some() {
+            return ready(Err(actix_web::error::ErrorForbidden(
+                "API tokens can only be managed from a logged in session",
+            )));
         }
+        ready(session_user_id(req).map(|user_id| SessionUser { user_id }))
+    }
+}
+
+fn session_user_id(req: &HttpRequest) -> Result<Uuid, actix_web::Error> {
+    match req.get_session().get::<Uuid>(TypedSession::USER_ID_KEY) {
+        Ok(Some(user_id)) => Ok(user_id),
+        Ok(None) => Err(actix_web::error::ErrorUnauthorized(
+            "
+            You are not logged in. Please log in and try again",
+        )),
+        Err(e) => Err(actix_web::error::ErrorInternalServerError(e)),
+    }
+}
+
+fn bearer_token(req: &HttpRequest) -> Option<SecretString> {
+    let value = req.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+    let token = value.strip_prefix("Bearer ")?.trim();
+    if token.is_empty() {
+        None
+    } else {
+        Some(SecretString::new(token.into()))
     }
 }
@@ -1,5 +1,7 @@
+mod api_token;
 mod middleware;
 mod password;
 
+pub use api_token::*;
 pub use middleware::*;
 pub use password::*;
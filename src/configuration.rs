@@ -9,6 +9,10 @@ pub struct Settings {
     pub database: DatabaseSettings,
     pub application: ApplicationSettings,
     pub redis_uri: SecretString,
+    #[serde(default)]
+    pub trash: TrashSettings,
+    #[serde(default)]
+    pub storage: StorageSettings,
 }
 
 #[derive(serde::Deserialize, Clone)]
@@ -30,6 +34,63 @@ pub struct ApplicationSettings {
     pub base_url: String,
     pub hmac_secret: SecretString,
 }
+#[derive(serde::Deserialize, Clone)]
+pub struct TrashSettings {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub retention_days: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub purge_interval_seconds: u64,
+}
+
+impl TrashSettings {
+    pub fn retention(&self) -> chrono::Duration {
+        chrono::Duration::days(self.retention_days)
+    }
+
+    pub fn purge_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.purge_interval_seconds)
+    }
+}
+
+impl Default for TrashSettings {
+    fn default() -> Self {
+        Self {
+            retention_days: 30,
+            purge_interval_seconds: 3600,
+        }
+    }
+}
+
+/// Where attachment contents are kept. Their metadata always lives in Postgres.
+#[derive(serde::Deserialize, Clone)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum StorageSettings {
+    Local { path: String },
+    S3(S3Settings),
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        Self::Local {
+            path: "attachments".to_string(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Clone)]
+pub struct S3Settings {
+    /// Base URL of the S3-compatible service, e.g. `https://s3.eu-west-1.amazonaws.com`.
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: SecretString,
+    /// Address the bucket as `{endpoint}/{bucket}` instead of through a
+    /// subdomain, as MinIO and most self-hosted services expect.
+    #[serde(default)]
+    pub path_style: bool,
+}
+
 impl ApplicationSettings {
     pub fn url(&self) -> Result<Url, String> {
         Url::parse(&self.base_url).map_err(|e| e.to_string())
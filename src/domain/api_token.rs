@@ -0,0 +1,160 @@
+use chrono::{DateTime, Utc};
+use unicode_segmentation::UnicodeSegmentation;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct ApiTokenName(String);
+
+impl ApiTokenName {
+    pub fn parse(s: String) -> Result<ApiTokenName, String> {
+        let is_empty_or_whitespace = s.trim().is_empty();
+        let is_too_long = s.graphemes(true).count() > 100;
+
+        if is_empty_or_whitespace {
+            Err("Token name cannot be empty".to_string())
+        } else if is_too_long {
+            Err("Token name is too long (max 100 characters)".to_string())
+        } else {
+            Ok(Self(s.trim().to_string()))
+        }
+    }
+}
+
+impl AsRef<str> for ApiTokenName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for ApiTokenName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenScope {
+    Read,
+    Write,
+}
+
+impl TokenScope {
+    pub fn parse(s: &str) -> Result<TokenScope, String> {
+        match s {
+            "read" => Ok(Self::Read),
+            "write" => Ok(Self::Write),
+            other => Err(format!(
+                "'{}' is not a valid scope. Use either `read` or `write`",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::Read => "read",
+            TokenScope::Write => "write",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NewApiToken {
+    pub user_id: Uuid,
+    pub name: ApiTokenName,
+    pub scopes: Vec<TokenScope>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl NewApiToken {
+    pub fn parse(
+        user_id: Uuid,
+        name: String,
+        scopes: Vec<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<NewApiToken, String> {
+        let name = ApiTokenName::parse(name)?;
+
+        if scopes.is_empty() {
+            return Err("At least one scope must be provided".to_string());
+        }
+        let mut parsed_scopes = Vec::new();
+        for scope in scopes {
+            let scope = TokenScope::parse(&scope)?;
+            if !parsed_scopes.contains(&scope) {
+                parsed_scopes.push(scope);
+            }
+        }
+
+        if let Some(expires_at) = expires_at {
+            if expires_at <= Utc::now() {
+                return Err("Expiry must be in the future".to_string());
+            }
+        }
+
+        Ok(Self {
+            user_id,
+            name,
+            scopes: parsed_scopes,
+            expires_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn empty_token_name_is_rejected() {
+        assert_err!(ApiTokenName::parse("".to_string()));
+        assert_err!(ApiTokenName::parse("   ".to_string()));
+    }
+
+    #[test]
+    fn token_name_too_long_is_rejected() {
+        assert_err!(ApiTokenName::parse("a".repeat(101)));
+    }
+
+    #[test]
+    fn unknown_scope_is_rejected() {
+        assert_err!(TokenScope::parse("admin"));
+    }
+
+    #[test]
+    fn token_without_scopes_is_rejected() {
+        assert_err!(NewApiToken::parse(
+            Uuid::new_v4(),
+            "cli".to_string(),
+            vec![],
+            None
+        ));
+    }
+
+    #[test]
+    fn token_expiring_in_the_past_is_rejected() {
+        let expires_at = Utc::now() - chrono::Duration::days(1);
+        assert_err!(NewApiToken::parse(
+            Uuid::new_v4(),
+            "cli".to_string(),
+            vec!["read".to_string()],
+            Some(expires_at)
+        ));
+    }
+
+    #[test]
+    fn duplicate_scopes_are_collapsed() {
+        let token = NewApiToken::parse(
+            Uuid::new_v4(),
+            "cli".to_string(),
+            vec!["read".to_string(), "read".to_string(), "write".to_string()],
+            None,
+        );
+        assert_ok!(&token);
+        assert_eq!(
+            token.unwrap().scopes,
+            vec![TokenScope::Read, TokenScope::Write]
+        );
+    }
+}
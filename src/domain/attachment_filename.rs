@@ -0,0 +1,74 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Clone)]
+pub struct AttachmentFilename(String);
+
+impl AttachmentFilename {
+    /// Keeps only the last path component, since some clients send the full
+    /// path of the uploaded file.
+    pub fn parse(s: String) -> Result<AttachmentFilename, String> {
+        let name = s.rsplit(['/', '\\']).next().unwrap_or_default().trim();
+        let is_empty = name.is_empty() || name == "." || name == "..";
+        let is_too_long = name.graphemes(true).count() > 255;
+        let has_control_characters = name.chars().any(char::is_control);
+
+        if is_empty {
+            Err("Attachment filename cannot be empty".to_string())
+        } else if is_too_long {
+            Err("Attachment filename is too long (max 255 characters)".to_string())
+        } else if has_control_characters {
+            Err("Attachment filename cannot contain control characters".to_string())
+        } else {
+            Ok(Self(name.to_string()))
+        }
+    }
+}
+
+impl AsRef<str> for AttachmentFilename {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for AttachmentFilename {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AttachmentFilename;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn empty_filename_is_rejected() {
+        assert_err!(AttachmentFilename::parse("".to_string()));
+        assert_err!(AttachmentFilename::parse("  ".to_string()));
+        assert_err!(AttachmentFilename::parse("uploads/".to_string()));
+        assert_err!(AttachmentFilename::parse("..".to_string()));
+    }
+
+    #[test]
+    fn filename_too_long_is_rejected() {
+        assert_err!(AttachmentFilename::parse(format!(
+            "{}.png",
+            "a".repeat(252)
+        )));
+    }
+
+    #[test]
+    fn control_characters_are_rejected() {
+        assert_err!(AttachmentFilename::parse("report\n.pdf".to_string()));
+    }
+
+    #[test]
+    fn directories_are_stripped() {
+        let name = assert_ok!(AttachmentFilename::parse(
+            "C:\\Users\\me\\screenshot.png".to_string()
+        ));
+        assert_eq!(name.as_ref(), "screenshot.png");
+        let name = assert_ok!(AttachmentFilename::parse("../../etc/passwd".to_string()));
+        assert_eq!(name.as_ref(), "passwd");
+    }
+}
@@ -1,14 +1,26 @@
+mod api_token;
+mod attachment_filename;
 mod note;
 mod note_content;
+mod note_link;
 mod note_title;
+mod notebook;
+mod saved_search;
+mod search_language;
 mod tag;
 mod user;
 mod user_email;
 mod user_password;
 
+pub use api_token::*;
+pub use attachment_filename::*;
 pub use note::*;
 pub use note_content::*;
+pub use note_link::*;
 pub use note_title::*;
+pub use notebook::*;
+pub use saved_search::*;
+pub use search_language::*;
 pub use tag::*;
 pub use user::*;
 pub use user_email::*;
@@ -1,5 +1,6 @@
 use crate::domain::note_content::NoteContent;
 use crate::domain::note_title::NoteTitle;
+use crate::domain::tag::TagName;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
@@ -18,29 +19,75 @@ pub struct NewNote {
     pub user_id: Uuid,
     pub title: NoteTitle,
     pub content: NoteContent,
+    pub notebook_id: Option<Uuid>,
+    /// Tags the user doesn't have yet are created along with the note.
+    pub tags: Vec<TagName>,
 }
 
 impl NewNote {
-    pub fn parse(user_id: Uuid, title: String, content: String) -> Result<NewNote, String> {
+    pub fn parse(
+        user_id: Uuid,
+        title: String,
+        content: String,
+        notebook_id: Option<String>,
+        tags: Vec<String>,
+    ) -> Result<NewNote, String> {
         let title = NoteTitle::parse(title)?;
         let content = NoteContent::parse(content)?;
+        let notebook_id = notebook_id.as_deref().map(parse_notebook_id).transpose()?;
+        let tags = parse_tag_names(tags)?;
 
         Ok(Self {
             user_id,
             title,
             content,
+            notebook_id,
+            tags,
         })
     }
 }
 
+/// How the tags of an update combine with the ones the note already has.
+#[derive(Debug, Clone)]
+pub enum NoteTagsUpdate {
+    /// The note ends up with exactly these tags.
+    Replace(Vec<TagName>),
+    /// These tags are added to the ones the note already has.
+    Merge(Vec<TagName>),
+}
+
+impl NoteTagsUpdate {
+    /// `mode` is either `replace` (the default) or `merge`.
+    pub fn parse(tags: Vec<String>, mode: Option<&str>) -> Result<NoteTagsUpdate, String> {
+        let tags = parse_tag_names(tags)?;
+        match mode.map(str::to_lowercase).as_deref() {
+            None | Some("replace") => Ok(Self::Replace(tags)),
+            Some("merge") => Ok(Self::Merge(tags)),
+            Some(other) => Err(format!(
+                "'{}' is not a valid tags mode. Use either `replace` or `merge`",
+                other
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct UpdateNote {
     pub title: Option<NoteTitle>,
     pub content: Option<NoteContent>,
+    /// `Some(None)` takes the note out of its notebook.
+    pub notebook_id: Option<Option<Uuid>>,
+    pub tags: Option<NoteTagsUpdate>,
 }
 
 impl UpdateNote {
-    pub fn parse(title: Option<String>, content: Option<String>) -> Result<UpdateNote, String> {
+    pub fn parse(
+        title: Option<String>,
+        content: Option<String>,
+        notebook_id: Option<Option<String>>,
+        tags: Option<Vec<String>>,
+        tags_mode: Option<String>,
+    ) -> Result<UpdateNote, String> {
         let title = match title {
             Some(t) => Some(NoteTitle::parse(t)?),
             None => None,
@@ -51,9 +98,120 @@ impl UpdateNote {
             None => None,
         };
 
-        if title.is_none() && content.is_none() {
-            return Err("At least one field (title or content) must be provided".to_string());
+        let notebook_id = match notebook_id {
+            Some(Some(id)) => Some(Some(parse_notebook_id(&id)?)),
+            Some(None) => Some(None),
+            None => None,
+        };
+
+        let tags = match (tags, tags_mode) {
+            (Some(tags), mode) => Some(NoteTagsUpdate::parse(tags, mode.as_deref())?),
+            (None, Some(_)) => return Err("`tags_mode` requires `tags`".to_string()),
+            (None, None) => None,
+        };
+
+        if title.is_none() && content.is_none() && notebook_id.is_none() && tags.is_none() {
+            return Err(
+                "At least one field (title, content, notebook_id or tags) must be provided"
+                    .to_string(),
+            );
         }
-        Ok(Self { title, content })
+        Ok(Self {
+            title,
+            content,
+            notebook_id,
+            tags,
+        })
+    }
+}
+
+fn parse_notebook_id(s: &str) -> Result<Uuid, String> {
+    Uuid::parse_str(s).map_err(|_| "Invalid notebook ID".to_string())
+}
+
+fn parse_tag_names(names: Vec<String>) -> Result<Vec<TagName>, String> {
+    names
+        .into_iter()
+        .map(|name| TagName::parse(name.clone()).map_err(|e| format!("Tag '{}': {}", name, e)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_matches, assert_ok};
+
+    fn tags(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn new_note_tags_are_validated() {
+        let note = assert_ok!(NewNote::parse(
+            Uuid::new_v4(),
+            "Title".to_string(),
+            "Content".to_string(),
+            None,
+            tags(&["Work", "ideas"]),
+        ));
+        let names: Vec<&str> = note.tags.iter().map(|tag| tag.as_ref()).collect();
+        assert_eq!(names, vec!["work", "ideas"]);
+
+        let error = NewNote::parse(
+            Uuid::new_v4(),
+            "Title".to_string(),
+            "Content".to_string(),
+            None,
+            tags(&["work", "not valid!"]),
+        )
+        .unwrap_err();
+        assert!(error.contains("not valid!"), "{}", error);
+    }
+
+    #[test]
+    fn tags_alone_are_a_valid_update() {
+        let update = assert_ok!(UpdateNote::parse(
+            None,
+            None,
+            None,
+            Some(tags(&["work"])),
+            None
+        ));
+        assert_matches!(update.tags, Some(NoteTagsUpdate::Replace(_)));
+
+        let update = assert_ok!(UpdateNote::parse(
+            None,
+            None,
+            None,
+            Some(Vec::new()),
+            Some("Merge".to_string())
+        ));
+        assert_matches!(update.tags, Some(NoteTagsUpdate::Merge(_)));
+    }
+
+    #[test]
+    fn invalid_tag_updates_are_rejected() {
+        assert_err!(UpdateNote::parse(None, None, None, None, None));
+        assert_err!(UpdateNote::parse(
+            None,
+            None,
+            None,
+            None,
+            Some("merge".to_string())
+        ));
+        assert_err!(UpdateNote::parse(
+            None,
+            None,
+            None,
+            Some(tags(&["work"])),
+            Some("append".to_string())
+        ));
+        assert_err!(UpdateNote::parse(
+            None,
+            None,
+            None,
+            Some(tags(&["a/b"])),
+            None
+        ));
     }
 }
@@ -1,3 +1,6 @@
+use super::NoteLink;
+use uuid::Uuid;
+
 #[derive(Debug, Clone)]
 pub struct NoteContent(String);
 
@@ -11,6 +14,39 @@ impl NoteContent {
             Ok(Self(s))
         }
     }
+
+    /// The `[[...]]` links to other notes in the content.
+    pub fn links(&self) -> Vec<NoteLink> {
+        NoteLink::parse_all(&self.0)
+    }
+
+    /// Note IDs written out anywhere in the content, e.g. pasted from a URL.
+    pub fn mentioned_note_ids(&self) -> Vec<Uuid> {
+        self.0
+            .split(|c: char| !(c.is_ascii_hexdigit() || c == '-'))
+            .filter(|word| word.len() == 36)
+            .filter_map(|word| Uuid::parse_str(word).ok())
+            .collect()
+    }
+
+    /// The `titles` that appear in the content as whole words, ignoring case.
+    pub fn mentioned_titles<'a>(&self, titles: &[&'a str]) -> Vec<&'a str> {
+        let content = self.0.to_lowercase();
+        titles
+            .iter()
+            .copied()
+            .filter(|title| {
+                let title = title.trim().to_lowercase();
+                !title.is_empty()
+                    && content.match_indices(&title).any(|(start, _)| {
+                        let before = content[..start].chars().next_back();
+                        let after = content[start + title.len()..].chars().next();
+                        !before.is_some_and(char::is_alphanumeric)
+                            && !after.is_some_and(char::is_alphanumeric)
+                    })
+            })
+            .collect()
+    }
 }
 
 impl AsRef<str> for NoteContent {
@@ -29,6 +65,7 @@ impl std::fmt::Display for NoteContent {
 mod tests {
     use super::NoteContent;
     use claims::{assert_err, assert_ok};
+    use uuid::Uuid;
 
     #[test]
     fn whitespace_only_content_is_rejected() {
@@ -47,4 +84,26 @@ mod tests {
         let content = "This is my note content with multiple lines.\nLine 2\nLine 3".to_string();
         assert_ok!(NoteContent::parse(content));
     }
+
+    #[test]
+    fn note_ids_are_found_anywhere_in_the_content() {
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+        let content = NoteContent::parse(format!(
+            "See {} and https://example.com/notes/{}.",
+            first, second
+        ))
+        .unwrap();
+        assert_eq!(content.mentioned_note_ids(), vec![first, second]);
+    }
+
+    #[test]
+    fn titles_are_only_mentioned_as_whole_words() {
+        let content =
+            NoteContent::parse("Follows up on the Q3 Plan, not the planner".to_string()).unwrap();
+        assert_eq!(
+            content.mentioned_titles(&["q3 plan", "Plan", "Planner", "Roadmap", "plann"]),
+            vec!["q3 plan", "Plan", "Planner"]
+        );
+    }
 }
@@ -0,0 +1,128 @@
+use uuid::Uuid;
+
+/// Target of a wiki-style link written in note content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoteLink {
+    /// `[[Other note title]]`, matched case-insensitively against titles.
+    Title(String),
+    /// `[[note:67e55044-10b1-426f-9247-bb680e5fe0c8]]`
+    Id(Uuid),
+}
+
+impl NoteLink {
+    /// Extracts every distinct link from `text`, in order of appearance.
+    ///
+    /// A link cannot span lines or contain brackets, so `[[a]b]]` and
+    /// `[[\n]]` are left as plain text.
+    pub fn parse_all(text: &str) -> Vec<NoteLink> {
+        let mut links = Vec::new();
+        let mut rest = text;
+
+        while let Some(start) = rest.find("[[") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("]]") else {
+                break;
+            };
+            let inner = &after[..end];
+            if inner.contains(['[', ']', '\n']) {
+                rest = &rest[start + 1..];
+                continue;
+            }
+
+            if let Some(link) = Self::parse(inner) {
+                if !links.contains(&link) {
+                    links.push(link);
+                }
+            }
+            rest = &after[end + 2..];
+        }
+
+        links
+    }
+
+    fn parse(inner: &str) -> Option<NoteLink> {
+        let inner = inner.trim();
+        if inner.is_empty() {
+            return None;
+        }
+
+        // Only the hyphenated form counts, anything else is taken as a title
+        if let Some(id) = inner.strip_prefix("note:") {
+            if id.len() == 36 {
+                if let Ok(id) = Uuid::parse_str(id) {
+                    return Some(Self::Id(id));
+                }
+            }
+        }
+
+        Some(Self::Title(inner.to_string()))
+    }
+}
+
+impl std::fmt::Display for NoteLink {
+    /// The link as written between the brackets.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NoteLink::Title(title) => title.fmt(f),
+            NoteLink::Id(id) => write!(f, "note:{}", id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NoteLink;
+    use uuid::Uuid;
+
+    #[test]
+    fn title_and_id_links_are_extracted_in_order() {
+        let id = Uuid::new_v4();
+        let text = format!("See [[Weekly sync]] and [[note:{}]].", id);
+        assert_eq!(
+            NoteLink::parse_all(&text),
+            vec![NoteLink::Title("Weekly sync".to_string()), NoteLink::Id(id)]
+        );
+    }
+
+    #[test]
+    fn duplicate_links_are_kept_once() {
+        let links = NoteLink::parse_all("[[Plan]] then [[ Plan ]] again");
+        assert_eq!(links, vec![NoteLink::Title("Plan".to_string())]);
+    }
+
+    #[test]
+    fn malformed_links_are_plain_text() {
+        for text in [
+            "[[]]",
+            "[[  ]]",
+            "[[a]b]]",
+            "[[multi\nline]]",
+            "[[open",
+            "[single]",
+        ] {
+            assert!(NoteLink::parse_all(text).is_empty(), "Parsed {:?}", text);
+        }
+    }
+
+    #[test]
+    fn extra_brackets_around_a_link_are_ignored() {
+        assert_eq!(
+            NoteLink::parse_all("[[[Plan]]]"),
+            vec![NoteLink::Title("Plan".to_string())]
+        );
+    }
+
+    #[test]
+    fn invalid_ids_are_treated_as_titles() {
+        assert_eq!(
+            NoteLink::parse_all("[[note:not-a-uuid]]"),
+            vec![NoteLink::Title("note:not-a-uuid".to_string())]
+        );
+    }
+
+    #[test]
+    fn links_display_as_written() {
+        let id = Uuid::new_v4();
+        assert_eq!(NoteLink::Id(id).to_string(), format!("note:{}", id));
+    }
+}
@@ -0,0 +1,53 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Clone)]
+pub struct NotebookName(String);
+
+impl NotebookName {
+    pub fn parse(s: String) -> Result<NotebookName, String> {
+        let is_empty_or_whitespace = s.trim().is_empty();
+        let is_too_long = s.graphemes(true).count() > 100;
+
+        if is_empty_or_whitespace {
+            Err("Notebook name cannot be empty".to_string())
+        } else if is_too_long {
+            Err("Notebook name is too long (max 100 characters)".to_string())
+        } else {
+            Ok(Self(s.trim().to_string()))
+        }
+    }
+}
+
+impl AsRef<str> for NotebookName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for NotebookName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NotebookName;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn empty_name_is_rejected() {
+        assert_err!(NotebookName::parse("".to_string()));
+        assert_err!(NotebookName::parse("   ".to_string()));
+    }
+
+    #[test]
+    fn name_too_long_is_rejected() {
+        assert_err!(NotebookName::parse("a".repeat(101)));
+    }
+
+    #[test]
+    fn name_with_spaces_is_accepted() {
+        assert_ok!(NotebookName::parse("Work / Projects".to_string()));
+    }
+}
@@ -0,0 +1,55 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Clone)]
+pub struct SavedSearchName(String);
+
+impl SavedSearchName {
+    pub fn parse(s: String) -> Result<SavedSearchName, String> {
+        let is_empty_or_whitespace = s.trim().is_empty();
+        let is_too_long = s.graphemes(true).count() > 100;
+
+        if is_empty_or_whitespace {
+            Err("Saved search name cannot be empty".to_string())
+        } else if is_too_long {
+            Err("Saved search name is too long (max 100 characters)".to_string())
+        } else {
+            Ok(Self(s.trim().to_string()))
+        }
+    }
+}
+
+impl AsRef<str> for SavedSearchName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for SavedSearchName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SavedSearchName;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn empty_name_is_rejected() {
+        assert_err!(SavedSearchName::parse("".to_string()));
+        assert_err!(SavedSearchName::parse("   ".to_string()));
+    }
+
+    #[test]
+    fn name_too_long_is_rejected() {
+        assert_err!(SavedSearchName::parse("a".repeat(101)));
+    }
+
+    #[test]
+    fn name_is_trimmed() {
+        let name = SavedSearchName::parse("  Work todos ".to_string());
+        assert_ok!(&name);
+        assert_eq!(name.unwrap().as_ref(), "Work todos");
+    }
+}
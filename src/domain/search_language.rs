@@ -0,0 +1,105 @@
+/// A built-in PostgreSQL text search configuration used to stem a user's notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchLanguage {
+    Simple,
+    Danish,
+    Dutch,
+    #[default]
+    English,
+    Finnish,
+    French,
+    German,
+    Hungarian,
+    Italian,
+    Norwegian,
+    Portuguese,
+    Romanian,
+    Russian,
+    Spanish,
+    Swedish,
+    Turkish,
+}
+
+impl SearchLanguage {
+    const ALL: [SearchLanguage; 16] = [
+        Self::Simple,
+        Self::Danish,
+        Self::Dutch,
+        Self::English,
+        Self::Finnish,
+        Self::French,
+        Self::German,
+        Self::Hungarian,
+        Self::Italian,
+        Self::Norwegian,
+        Self::Portuguese,
+        Self::Romanian,
+        Self::Russian,
+        Self::Spanish,
+        Self::Swedish,
+        Self::Turkish,
+    ];
+
+    pub fn parse(s: &str) -> Result<SearchLanguage, String> {
+        let s = s.trim().to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|language| language.as_str() == s)
+            .ok_or_else(|| format!("'{}' is not a supported search language", s))
+    }
+
+    /// The name of the text search configuration, safe to cast to `regconfig`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchLanguage::Simple => "simple",
+            SearchLanguage::Danish => "danish",
+            SearchLanguage::Dutch => "dutch",
+            SearchLanguage::English => "english",
+            SearchLanguage::Finnish => "finnish",
+            SearchLanguage::French => "french",
+            SearchLanguage::German => "german",
+            SearchLanguage::Hungarian => "hungarian",
+            SearchLanguage::Italian => "italian",
+            SearchLanguage::Norwegian => "norwegian",
+            SearchLanguage::Portuguese => "portuguese",
+            SearchLanguage::Romanian => "romanian",
+            SearchLanguage::Russian => "russian",
+            SearchLanguage::Spanish => "spanish",
+            SearchLanguage::Swedish => "swedish",
+            SearchLanguage::Turkish => "turkish",
+        }
+    }
+}
+
+impl std::fmt::Display for SearchLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SearchLanguage;
+    use claims::assert_err;
+
+    #[test]
+    fn every_language_round_trips() {
+        for language in SearchLanguage::ALL {
+            assert_eq!(SearchLanguage::parse(language.as_str()), Ok(language));
+        }
+    }
+
+    #[test]
+    fn parsing_ignores_case_and_whitespace() {
+        assert_eq!(
+            SearchLanguage::parse(" German "),
+            Ok(SearchLanguage::German)
+        );
+    }
+
+    #[test]
+    fn unknown_language_is_rejected() {
+        assert_err!(SearchLanguage::parse("klingon"));
+        assert_err!(SearchLanguage::parse("english'; DROP TABLE notes; --"));
+    }
+}
@@ -0,0 +1,46 @@
+use actix_web::{http::StatusCode, ResponseError};
+
+#[derive(thiserror::Error)]
+pub enum TagError {
+    #[error("Invalid input: {0}")]
+    Validation(String),
+    #[error("A tag with this name already exists")]
+    Duplicate,
+    #[error("Tag or note not found")]
+    NotFound,
+    #[error("Invalid ID")]
+    InvalidId,
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for TagError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for TagError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            TagError::Validation(_) => StatusCode::BAD_REQUEST,
+            TagError::Duplicate => StatusCode::CONFLICT,
+            TagError::NotFound => StatusCode::NOT_FOUND,
+            TagError::InvalidId => StatusCode::BAD_REQUEST,
+            TagError::Unexpected(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+fn error_chain_fmt(
+    e: &impl std::error::Error,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    writeln!(f, "{}\n", e)?;
+    let mut current = e.source();
+    while let Some(cause) = current {
+        writeln!(f, "Caused by:\n\t{}", cause)?;
+        current = cause.source();
+    }
+    Ok(())
+}
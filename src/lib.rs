@@ -1,9 +1,12 @@
 pub mod authentication;
 pub mod configuration;
 pub mod domain;
+pub mod errors;
 pub mod middleware;
 pub mod routes;
 pub mod session_state;
 pub mod startup;
+pub mod storage;
 pub mod telemetry;
+pub mod trash_purge_worker;
 pub mod utils;
@@ -9,8 +9,13 @@ pub fn configure_cors(allowed_origin: &str) -> Cors {
             header::AUTHORIZATION,
             header::ACCEPT,
             header::CONTENT_TYPE,
+            header::IF_MATCH,
+            header::IF_NONE_MATCH,
+        ])
+        .expose_headers(vec![
+            headers::HeaderName::from_static("x-request-id"),
+            header::ETAG,
         ])
-        .expose_headers(vec![headers::HeaderName::from_static("x-request-id")])
         .max_age(3600)
         .supports_credentials()
 }
@@ -0,0 +1,271 @@
+use super::ExportedNote;
+use std::collections::HashSet;
+use std::io::{Seek, SeekFrom, Write};
+use std::sync::{Arc, Mutex};
+use zip::result::ZipResult;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Renders a note as a Markdown document with YAML front matter. Strings are
+/// written as JSON, which YAML reads as double-quoted scalars.
+pub fn to_markdown(note: &ExportedNote) -> String {
+    let mut document = String::from("---\n");
+    document.push_str(&format!("note_id: {}\n", note.note_id));
+    document.push_str(&format!("title: {}\n", quote(&note.title)));
+    if note.tags.is_empty() {
+        document.push_str("tags: []\n");
+    } else {
+        document.push_str("tags:\n");
+        for tag in &note.tags {
+            document.push_str(&format!("  - {}\n", quote(tag)));
+        }
+    }
+    document.push_str(&format!("created_at: {}\n", note.created_at));
+    document.push_str(&format!("updated_at: {}\n", note.updated_at));
+    document.push_str("---\n\n");
+    document.push_str(&note.content);
+    if !note.content.ends_with('\n') {
+        document.push('\n');
+    }
+    document
+}
+
+fn quote(s: &str) -> String {
+    serde_json::to_string(s).expect("Strings always serialize")
+}
+
+/// Hands out one `.md` file name per note title, numbering repeats so that
+/// notes sharing a title do not overwrite each other when unpacked.
+#[derive(Default)]
+pub struct FileNames {
+    used: HashSet<String>,
+}
+
+impl FileNames {
+    pub fn claim(&mut self, title: &str) -> String {
+        let stem = file_stem(title);
+        let mut name = format!("{}.md", stem);
+        let mut copy = 1;
+        // Case-insensitive file systems treat `Plan.md` and `plan.md` as one file
+        while !self.used.insert(name.to_lowercase()) {
+            copy += 1;
+            name = format!("{} ({}).md", stem, copy);
+        }
+        name
+    }
+}
+
+/// The title with characters that are not allowed in file names on common
+/// platforms replaced.
+fn file_stem(title: &str) -> String {
+    let stem: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c if c.is_control() => '-',
+            c => c,
+        })
+        .collect();
+    let stem = stem.trim().trim_start_matches('.').trim();
+    if stem.is_empty() {
+        "Untitled".to_string()
+    } else {
+        stem.to_string()
+    }
+}
+
+/// Writes notes into a zip archive, handing out the finished bytes as it goes
+/// so the archive can be sent to the client while it is still being written.
+pub struct MarkdownZip {
+    zip: ZipWriter<SharedBuffer>,
+    buffer: SharedBuffer,
+    file_names: FileNames,
+}
+
+impl Default for MarkdownZip {
+    fn default() -> Self {
+        let buffer = SharedBuffer::default();
+        Self {
+            zip: ZipWriter::new(buffer.clone()),
+            buffer,
+            file_names: FileNames::default(),
+        }
+    }
+}
+
+impl MarkdownZip {
+    /// Adds the note, returning the bytes of the archive that are final now.
+    pub fn add(&mut self, note: &ExportedNote) -> ZipResult<Vec<u8>> {
+        // Starting a file finishes the previous one, so everything written
+        // before this point won't be touched again
+        let finished = self.buffer.position();
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        self.zip
+            .start_file(self.file_names.claim(&note.title), options)?;
+        self.zip.write_all(to_markdown(note).as_bytes())?;
+        Ok(self.buffer.take_until(finished))
+    }
+
+    /// Writes the central directory, returning the rest of the archive.
+    pub fn finish(self) -> ZipResult<Vec<u8>> {
+        self.zip.finish()?;
+        Ok(self.buffer.take_until(u64::MAX))
+    }
+}
+
+/// Collects what the zip writer produces. `ZipWriter` seeks back to fill in
+/// the header of each file once it is finished, so bytes can only be taken
+/// once nothing will seek to them any more.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<BufferState>>);
+
+#[derive(Default)]
+struct BufferState {
+    /// How many bytes have been taken from the start of the archive.
+    taken: u64,
+    /// The bytes written after those.
+    data: Vec<u8>,
+    /// Where the next write goes, from the start of the archive.
+    position: u64,
+}
+
+impl SharedBuffer {
+    fn position(&self) -> u64 {
+        self.0.lock().unwrap().position
+    }
+
+    /// Removes the bytes before `offset` that have not been taken yet.
+    fn take_until(&self, offset: u64) -> Vec<u8> {
+        let mut state = self.0.lock().unwrap();
+        let count = offset
+            .saturating_sub(state.taken)
+            .min(state.data.len() as u64);
+        state.taken += count;
+        state.data.drain(..count as usize).collect()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut state = self.0.lock().unwrap();
+        let start = (state.position - state.taken) as usize;
+        let overlap = buf.len().min(state.data.len().saturating_sub(start));
+        state.data[start..start + overlap].copy_from_slice(&buf[..overlap]);
+        state.data.extend_from_slice(&buf[overlap..]);
+        state.position += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for SharedBuffer {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let mut state = self.0.lock().unwrap();
+        let end = state.taken + state.data.len() as u64;
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => end.checked_add_signed(offset),
+            SeekFrom::Current(offset) => state.position.checked_add_signed(offset),
+        };
+        match position {
+            Some(position) if position >= state.taken && position <= end => {
+                state.position = position;
+                Ok(position)
+            }
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Cannot seek outside the part of the archive that has not been sent",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(title: &str, tags: &[&str], content: &str) -> ExportedNote {
+        ExportedNote {
+            note_id: "0b6bd2a4-8a40-4fb1-9a35-5f5d1c9d3e2a".to_string(),
+            title: title.to_string(),
+            content: content.to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            notebook_id: None,
+            pinned: false,
+            archived: false,
+            created_at: "2024-01-01T10:00:00+00:00".to_string(),
+            updated_at: "2024-01-02T10:00:00+00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn front_matter_carries_the_note_metadata() {
+        let markdown = to_markdown(&note("Plan: \"v2\"", &["work", "ideas"], "# Plan"));
+        assert_eq!(
+            markdown,
+            "---\n\
+             note_id: 0b6bd2a4-8a40-4fb1-9a35-5f5d1c9d3e2a\n\
+             title: \"Plan: \\\"v2\\\"\"\n\
+             tags:\n  - \"work\"\n  - \"ideas\"\n\
+             created_at: 2024-01-01T10:00:00+00:00\n\
+             updated_at: 2024-01-02T10:00:00+00:00\n\
+             ---\n\n\
+             # Plan\n"
+        );
+    }
+
+    #[test]
+    fn notes_without_tags_have_an_empty_list() {
+        let markdown = to_markdown(&note("Plan", &[], "Content\n"));
+        assert!(markdown.contains("\ntags: []\n"));
+        assert!(markdown.ends_with("---\n\nContent\n"));
+    }
+
+    #[test]
+    fn file_names_are_sanitized() {
+        let mut names = FileNames::default();
+        assert_eq!(names.claim("Q1/Q2: plans?"), "Q1-Q2- plans-.md");
+        assert_eq!(names.claim("../secrets"), "-secrets.md");
+        assert_eq!(names.claim("   "), "Untitled.md");
+    }
+
+    #[test]
+    fn repeated_titles_are_numbered() {
+        let mut names = FileNames::default();
+        assert_eq!(names.claim("Plan"), "Plan.md");
+        assert_eq!(names.claim("plan"), "plan (2).md");
+        assert_eq!(names.claim("Plan"), "Plan (3).md");
+    }
+
+    #[test]
+    fn streamed_chunks_make_up_a_valid_archive() {
+        let mut archive = MarkdownZip::default();
+        let mut bytes = Vec::new();
+        for (title, content) in [("Plan", "Ship it"), ("Plan", "Again"), ("Notes", "More")] {
+            let chunk = archive.add(&note(title, &["work"], content)).unwrap();
+            bytes.extend(chunk);
+        }
+        // The earlier files go out before the archive is finished
+        assert!(!bytes.is_empty());
+        bytes.extend(archive.finish().unwrap());
+
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        assert_eq!(names.len(), 3);
+        let mut contents = Vec::new();
+        for name in ["Plan.md", "Plan (2).md", "Notes.md"] {
+            let mut file = archive.by_name(name).unwrap();
+            let mut content = String::new();
+            std::io::Read::read_to_string(&mut file, &mut content).unwrap();
+            contents.push(content);
+        }
+        assert!(contents[0].starts_with("---\nnote_id: "));
+        assert!(contents[0].contains("title: \"Plan\"\ntags:\n  - \"work\"\n"));
+        assert!(contents[0].ends_with("---\n\nShip it\n"));
+        assert!(contents[1].ends_with("---\n\nAgain\n"));
+        assert!(contents[2].ends_with("---\n\nMore\n"));
+    }
+}
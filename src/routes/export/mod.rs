@@ -0,0 +1,262 @@
+mod markdown;
+
+use crate::authentication::AuthenticatedUser;
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use chrono::{DateTime, Utc};
+use futures::channel::mpsc;
+use futures::{SinkExt, Stream, TryStreamExt};
+use markdown::MarkdownZip;
+use sqlx::PgPool;
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Version of the JSON export document. Bump it when the shape changes.
+const JSON_EXPORT_VERSION: u32 = 1;
+
+#[derive(serde::Deserialize)]
+pub struct ExportQueryParams {
+    /// Either `markdown` (the default) or `json`.
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ExportFormat {
+    /// A zip with one Markdown file per note.
+    Markdown,
+    /// A single JSON document with every note.
+    Json,
+}
+
+impl ExportFormat {
+    fn parse(s: &str) -> Result<ExportFormat, String> {
+        match s.to_lowercase().as_str() {
+            "markdown" => Ok(Self::Markdown),
+            "json" => Ok(Self::Json),
+            other => Err(format!(
+                "'{}' is not a valid export format. Use either `markdown` or `json`",
+                other
+            )),
+        }
+    }
+
+    fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "application/zip",
+            ExportFormat::Json => "application/json",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "zip",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct ExportedNote {
+    pub note_id: String,
+    pub title: String,
+    pub content: String,
+    pub tags: Vec<String>,
+    pub notebook_id: Option<String>,
+    pub pinned: bool,
+    pub archived: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(thiserror::Error)]
+pub enum ExportError {
+    #[error("Invalid input: {0}")]
+    ValidationError(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for ExportError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ExportError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            ExportError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+type ExportSender = mpsc::Sender<Result<web::Bytes, anyhow::Error>>;
+
+/// Streams every note that is not in the trash. Notes are read from the
+/// database one at a time and sent as soon as they are encoded, so the whole
+/// export is never held in memory.
+#[tracing::instrument(name = "Export notes", skip(user, params, pool), fields(user_id = %user.user_id))]
+pub async fn export_notes(
+    user: AuthenticatedUser,
+    params: web::Query<ExportQueryParams>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ExportError> {
+    let format = params
+        .format
+        .as_deref()
+        .map(ExportFormat::parse)
+        .transpose()
+        .map_err(ExportError::ValidationError)?
+        .unwrap_or(ExportFormat::Markdown);
+
+    let exported_at = Utc::now();
+    // Backpressure: encoding pauses while the client is behind
+    let (sender, receiver) = mpsc::channel(16);
+    actix_web::rt::spawn(
+        write_export(
+            pool.get_ref().clone(),
+            user.user_id,
+            format,
+            exported_at,
+            sender,
+        )
+        .instrument(tracing::Span::current()),
+    );
+
+    let filename = format!(
+        "jot-export-{}.{}",
+        exported_at.format("%Y-%m-%d"),
+        format.extension()
+    );
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(filename)],
+        })
+        .streaming(receiver))
+}
+
+async fn write_export(
+    pool: PgPool,
+    user_id: Uuid,
+    format: ExportFormat,
+    exported_at: DateTime<Utc>,
+    mut sender: ExportSender,
+) {
+    let outcome = match format {
+        ExportFormat::Markdown => write_markdown_export(&pool, user_id, &mut sender).await,
+        ExportFormat::Json => write_json_export(&pool, user_id, exported_at, &mut sender).await,
+    };
+
+    if let Err(e) = outcome {
+        tracing::error!(
+            error.cause_chain = ?e,
+            error.message = %e,
+            "Failed to export notes"
+        );
+        // The response has already started, so the only way to tell the client
+        // the export is incomplete is to abort it
+        let _ = sender.send(Err(e)).await;
+    }
+}
+
+async fn write_markdown_export(
+    pool: &PgPool,
+    user_id: Uuid,
+    sender: &mut ExportSender,
+) -> Result<(), anyhow::Error> {
+    let mut archive = MarkdownZip::default();
+    let mut notes = fetch_notes(pool, user_id);
+    while let Some(note) = notes.try_next().await? {
+        let chunk = archive.add(&note)?;
+        send(sender, chunk).await?;
+    }
+
+    send(sender, archive.finish()?).await
+}
+
+/// Writes `{"version": 1, "exported_at": "...", "notes": [...]}`.
+async fn write_json_export(
+    pool: &PgPool,
+    user_id: Uuid,
+    exported_at: DateTime<Utc>,
+    sender: &mut ExportSender,
+) -> Result<(), anyhow::Error> {
+    let header = format!(
+        r#"{{"version":{},"exported_at":{},"notes":["#,
+        JSON_EXPORT_VERSION,
+        serde_json::to_string(&exported_at.to_rfc3339())?
+    );
+    send(sender, header.into_bytes()).await?;
+
+    let mut notes = fetch_notes(pool, user_id);
+    let mut is_first = true;
+    while let Some(note) = notes.try_next().await? {
+        let mut chunk = if is_first { Vec::new() } else { vec![b','] };
+        serde_json::to_writer(&mut chunk, &note)?;
+        send(sender, chunk).await?;
+        is_first = false;
+    }
+
+    send(sender, b"]}".to_vec()).await
+}
+
+async fn send(sender: &mut ExportSender, chunk: Vec<u8>) -> Result<(), anyhow::Error> {
+    if chunk.is_empty() {
+        return Ok(());
+    }
+    sender
+        .send(Ok(web::Bytes::from(chunk)))
+        .await
+        .map_err(|_| anyhow::anyhow!("The client stopped reading the export"))
+}
+
+fn fetch_notes(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> impl Stream<Item = Result<ExportedNote, sqlx::Error>> + '_ {
+    sqlx::query!(
+        r#"
+        SELECT n.note_id, n.title, n.content, n.notebook_id, n.pinned, n.archived,
+            n.created_at, n.updated_at,
+            ARRAY(
+                SELECT t.name
+                FROM note_tags nt
+                JOIN tags t ON nt.tag_id = t.tag_id
+                WHERE nt.note_id = n.note_id
+                ORDER BY t.name
+            ) AS "tags!"
+        FROM notes n
+        WHERE n.user_id = $1 AND n.deleted_at IS NULL
+        ORDER BY n.created_at, n.note_id
+        "#,
+        user_id
+    )
+    .fetch(pool)
+    .map_ok(|row| ExportedNote {
+        note_id: row.note_id.to_string(),
+        title: row.title,
+        content: row.content,
+        tags: row.tags,
+        notebook_id: row.notebook_id.map(|id| id.to_string()),
+        pinned: row.pinned,
+        archived: row.archived,
+        created_at: row.created_at.to_rfc3339(),
+        updated_at: row.updated_at.to_rfc3339(),
+    })
+}
+
+fn error_chain_fmt(
+    e: &impl std::error::Error,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    writeln!(f, "{}\n", e)?;
+    let mut current = e.source();
+    while let Some(cause) = current {
+        writeln!(f, "Caused by:\n\t{}", cause)?;
+        current = cause.source();
+    }
+    Ok(())
+}
@@ -0,0 +1,249 @@
+mod model;
+
+use crate::authentication::AuthenticatedUser;
+use crate::domain::{NoteContent, TagName};
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use model::GraphData;
+use sqlx::PgPool;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use uuid::Uuid;
+
+#[derive(serde::Deserialize)]
+pub struct GraphQueryParams {
+    /// Either `json` (the default) or `dot`.
+    pub format: Option<String>,
+    /// Comma-separated tag names. Only notes carrying at least one are included.
+    pub tags: Option<String>,
+    /// Restricts the graph to notes connected to this one by links.
+    pub start: Option<String>,
+    /// Maximum number of links between `start` and any included note.
+    pub depth: Option<u32>,
+}
+
+enum GraphFormat {
+    Json,
+    Dot,
+}
+
+impl GraphFormat {
+    fn parse(s: &str) -> Result<GraphFormat, String> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "dot" => Ok(Self::Dot),
+            other => Err(format!(
+                "'{}' is not a valid graph format. Use either `json` or `dot`",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum GraphError {
+    #[error("Invalid input: {0}")]
+    ValidationError(String),
+    #[error("Note not found")]
+    NotFound,
+    #[error("Invalid note ID")]
+    InvalidId,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for GraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for GraphError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            GraphError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            GraphError::NotFound => StatusCode::NOT_FOUND,
+            GraphError::InvalidId => StatusCode::BAD_REQUEST,
+            GraphError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(name = "Get note graph", skip(user, params, pool), fields(user_id = %user.user_id))]
+pub async fn get_graph(
+    user: AuthenticatedUser,
+    params: web::Query<GraphQueryParams>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, GraphError> {
+    let format = params
+        .format
+        .as_deref()
+        .map(GraphFormat::parse)
+        .transpose()
+        .map_err(GraphError::ValidationError)?
+        .unwrap_or(GraphFormat::Json);
+    let tags = params
+        .tags
+        .as_deref()
+        .map(parse_tag_list)
+        .transpose()
+        .map_err(GraphError::ValidationError)?;
+    let start = params
+        .start
+        .as_deref()
+        .map(|id| Uuid::parse_str(id).map_err(|_| GraphError::InvalidId))
+        .transpose()?;
+    if params.depth.is_some() && start.is_none() {
+        return Err(GraphError::ValidationError(
+            "`depth` requires a `start` note".to_string(),
+        ));
+    }
+
+    if let Some(start) = start {
+        verify_note_ownership(&pool, start, user.user_id).await?;
+    }
+
+    let data = load_graph_data(&pool, user.user_id, tags)
+        .await
+        .map_err(GraphError::UnexpectedError)?;
+    let graph = data.into_graph(start, params.depth);
+
+    Ok(match format {
+        GraphFormat::Json => HttpResponse::Ok().json(graph),
+        GraphFormat::Dot => HttpResponse::Ok()
+            .content_type("text/vnd.graphviz; charset=utf-8")
+            .body(graph.to_dot()),
+    })
+}
+
+/// Parses a comma-separated list of tag names, ignoring empty entries.
+fn parse_tag_list(s: &str) -> Result<Vec<String>, String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| TagName::parse(name.to_string()).map(|tag| tag.as_ref().to_string()))
+        .collect()
+}
+
+#[tracing::instrument(name = "Load note graph from database", skip(pool))]
+async fn load_graph_data(
+    pool: &PgPool,
+    user_id: Uuid,
+    tags: Option<Vec<String>>,
+) -> Result<GraphData, anyhow::Error> {
+    let notes = sqlx::query!(
+        r#"
+        SELECT n.note_id, n.title, n.content
+        FROM notes n
+        WHERE n.user_id = $1 AND n.deleted_at IS NULL
+            AND ($2::text[] IS NULL OR EXISTS (
+                SELECT 1 FROM note_tags ft JOIN tags tf ON ft.tag_id = tf.tag_id
+                WHERE ft.note_id = n.note_id AND tf.name = ANY($2)
+            ))
+        ORDER BY n.title, n.note_id
+        "#,
+        user_id,
+        tags.as_deref()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let note_tags = sqlx::query!(
+        r#"
+        SELECT nt.note_id, t.name
+        FROM note_tags nt
+        JOIN tags t ON nt.tag_id = t.tag_id
+        WHERE t.user_id = $1
+        ORDER BY t.name, nt.note_id
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // Resolved the same way as GET /notes/{id}/links
+    let links = sqlx::query!(
+        r#"
+        SELECT DISTINCT s.note_id AS source_id, t.note_id AS target_id
+        FROM note_links l
+        JOIN notes s ON s.note_id = l.source_note_id
+        JOIN notes t
+            ON t.user_id = $1 AND t.deleted_at IS NULL
+            AND (t.note_id = l.target_note_id OR lower(t.title) = lower(l.target_title))
+        WHERE s.user_id = $1 AND s.deleted_at IS NULL
+        ORDER BY s.note_id, t.note_id
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut references: BTreeSet<(Uuid, Uuid)> = links
+        .into_iter()
+        .map(|r| (r.source_id, r.target_id))
+        .collect();
+
+    // Notes also refer to each other by mentioning an ID or a title in the text
+    let note_ids: HashSet<Uuid> = notes.iter().map(|r| r.note_id).collect();
+    let mut notes_by_title: HashMap<String, Vec<Uuid>> = HashMap::new();
+    for note in &notes {
+        notes_by_title
+            .entry(note.title.to_lowercase())
+            .or_default()
+            .push(note.note_id);
+    }
+    let titles: Vec<&str> = notes_by_title.keys().map(String::as_str).collect();
+    for note in &notes {
+        let Ok(content) = NoteContent::parse(note.content.clone()) else {
+            continue;
+        };
+        let mentioned_ids = content
+            .mentioned_note_ids()
+            .into_iter()
+            .filter(|id| note_ids.contains(id));
+        let titled_ids = content
+            .mentioned_titles(&titles)
+            .into_iter()
+            .flat_map(|title| notes_by_title[title].iter().copied());
+        references.extend(
+            mentioned_ids
+                .chain(titled_ids)
+                .filter(|target| *target != note.note_id)
+                .map(|target| (note.note_id, target)),
+        );
+    }
+
+    Ok(GraphData {
+        notes: notes.into_iter().map(|r| (r.note_id, r.title)).collect(),
+        tags: note_tags.into_iter().map(|r| (r.note_id, r.name)).collect(),
+        references: references.into_iter().collect(),
+    })
+}
+
+async fn verify_note_ownership(
+    pool: &PgPool,
+    note_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), GraphError> {
+    sqlx::query!(
+        "SELECT note_id FROM notes WHERE note_id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        note_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| GraphError::UnexpectedError(anyhow::anyhow!(e)))?
+    .ok_or(GraphError::NotFound)?;
+    Ok(())
+}
+
+fn error_chain_fmt(
+    e: &impl std::error::Error,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    writeln!(f, "{}\n", e)?;
+    let mut current = e.source();
+    while let Some(cause) = current {
+        writeln!(f, "Caused by:\n\t{}", cause)?;
+        current = cause.source();
+    }
+    Ok(())
+}
@@ -0,0 +1,260 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use uuid::Uuid;
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct Node {
+    /// `note:<uuid>` or `tag:<name>`.
+    pub id: String,
+    pub kind: NodeKind,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Note,
+    Tag,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct Edge {
+    pub source: String,
+    pub target: String,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    /// The source note links to the target note.
+    Reference,
+    /// The source note carries the target tag.
+    Tag,
+}
+
+/// Everything the graph of one user is built from.
+#[derive(Debug, Default)]
+pub struct GraphData {
+    /// Note IDs and titles, in the order nodes should be listed.
+    pub notes: Vec<(Uuid, String)>,
+    /// Note IDs and the names of their tags.
+    pub tags: Vec<(Uuid, String)>,
+    /// Resolved links as source and target note IDs.
+    pub references: Vec<(Uuid, Uuid)>,
+}
+
+impl GraphData {
+    /// Builds the graph, keeping only notes within `depth` reference hops of
+    /// `start` when given. Links are followed in both directions and `None`
+    /// means no depth limit.
+    pub fn into_graph(self, start: Option<Uuid>, depth: Option<u32>) -> Graph {
+        let mut included: HashSet<Uuid> = self.notes.iter().map(|(id, _)| *id).collect();
+        let references: Vec<(Uuid, Uuid)> = self
+            .references
+            .into_iter()
+            .filter(|(source, target)| included.contains(source) && included.contains(target))
+            .collect();
+
+        if let Some(start) = start {
+            included = reachable(start, &references, depth, &included);
+        }
+
+        let mut nodes: Vec<Node> = self
+            .notes
+            .into_iter()
+            .filter(|(id, _)| included.contains(id))
+            .map(|(id, title)| Node {
+                id: note_node_id(id),
+                kind: NodeKind::Note,
+                label: title,
+            })
+            .collect();
+
+        let mut edges: Vec<Edge> = references
+            .into_iter()
+            .filter(|(source, target)| included.contains(source) && included.contains(target))
+            .map(|(source, target)| Edge {
+                source: note_node_id(source),
+                target: note_node_id(target),
+                kind: EdgeKind::Reference,
+            })
+            .collect();
+
+        let mut tag_names = BTreeSet::new();
+        for (note_id, name) in self.tags {
+            if included.contains(&note_id) {
+                edges.push(Edge {
+                    source: note_node_id(note_id),
+                    target: tag_node_id(&name),
+                    kind: EdgeKind::Tag,
+                });
+                tag_names.insert(name);
+            }
+        }
+        nodes.extend(tag_names.into_iter().map(|name| Node {
+            id: tag_node_id(&name),
+            kind: NodeKind::Tag,
+            label: name,
+        }));
+
+        Graph { nodes, edges }
+    }
+}
+
+impl Graph {
+    /// Renders the graph in the GraphViz DOT language. Notes are boxes, tags
+    /// are ellipses joined to their notes by dashed lines.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph notes {\n");
+        for node in &self.nodes {
+            let shape = match node.kind {
+                NodeKind::Note => "box",
+                NodeKind::Tag => "ellipse",
+            };
+            dot.push_str(&format!(
+                "  {} [label={}, shape={}];\n",
+                quote(&node.id),
+                quote(&node.label),
+                shape
+            ));
+        }
+        for edge in &self.edges {
+            let attributes = match edge.kind {
+                EdgeKind::Reference => "",
+                EdgeKind::Tag => " [style=dashed, arrowhead=none]",
+            };
+            dot.push_str(&format!(
+                "  {} -> {}{};\n",
+                quote(&edge.source),
+                quote(&edge.target),
+                attributes
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Notes within `depth` hops of `start`, among `notes`.
+fn reachable(
+    start: Uuid,
+    references: &[(Uuid, Uuid)],
+    depth: Option<u32>,
+    notes: &HashSet<Uuid>,
+) -> HashSet<Uuid> {
+    let mut seen = HashSet::new();
+    if !notes.contains(&start) {
+        return seen;
+    }
+
+    let mut neighbours: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for (source, target) in references {
+        neighbours.entry(*source).or_default().push(*target);
+        neighbours.entry(*target).or_default().push(*source);
+    }
+
+    seen.insert(start);
+    let mut queue = VecDeque::from([(start, 0)]);
+    while let Some((note_id, distance)) = queue.pop_front() {
+        if depth.is_some_and(|depth| distance >= depth) {
+            continue;
+        }
+        for next in neighbours.get(&note_id).into_iter().flatten() {
+            if seen.insert(*next) {
+                queue.push_back((*next, distance + 1));
+            }
+        }
+    }
+    seen
+}
+
+fn note_node_id(note_id: Uuid) -> String {
+    format!("note:{}", note_id)
+}
+
+fn tag_node_id(name: &str) -> String {
+    format!("tag:{}", name)
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a -> b -> c -> d, with `a` tagged `work`.
+    fn chain() -> (Vec<Uuid>, GraphData) {
+        let ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+        let data = GraphData {
+            notes: ids
+                .iter()
+                .zip(["a", "b", "c", "d"])
+                .map(|(id, title)| (*id, title.to_string()))
+                .collect(),
+            tags: vec![(ids[0], "work".to_string())],
+            references: ids.windows(2).map(|pair| (pair[0], pair[1])).collect(),
+        };
+        (ids, data)
+    }
+
+    fn labels(graph: &Graph) -> Vec<&str> {
+        graph.nodes.iter().map(|node| node.label.as_str()).collect()
+    }
+
+    #[test]
+    fn whole_graph_has_notes_tags_and_both_edge_kinds() {
+        let (_, data) = chain();
+        let graph = data.into_graph(None, None);
+        assert_eq!(labels(&graph), vec!["a", "b", "c", "d", "work"]);
+        assert_eq!(graph.edges.len(), 4);
+        assert_eq!(graph.edges[3].kind, EdgeKind::Tag);
+    }
+
+    #[test]
+    fn depth_is_counted_from_the_start_in_both_directions() {
+        let (ids, data) = chain();
+        let graph = data.into_graph(Some(ids[2]), Some(1));
+        assert_eq!(labels(&graph), vec!["b", "c", "d"]);
+        assert_eq!(graph.edges.len(), 2);
+    }
+
+    #[test]
+    fn depth_zero_keeps_only_the_start() {
+        let (ids, data) = chain();
+        let graph = data.into_graph(Some(ids[0]), Some(0));
+        assert_eq!(labels(&graph), vec!["a", "work"]);
+    }
+
+    #[test]
+    fn start_outside_the_notes_gives_an_empty_graph() {
+        let (_, data) = chain();
+        let graph = data.into_graph(Some(Uuid::new_v4()), None);
+        assert!(graph.nodes.is_empty());
+        assert!(graph.edges.is_empty());
+    }
+
+    #[test]
+    fn dot_output_quotes_labels() {
+        let id = Uuid::new_v4();
+        let data = GraphData {
+            notes: vec![(id, "Say \"hi\"".to_string())],
+            ..Default::default()
+        };
+        let dot = data.into_graph(None, None).to_dot();
+        assert_eq!(
+            dot,
+            format!(
+                "digraph notes {{\n  \"note:{}\" [label=\"Say \\\"hi\\\"\", shape=box];\n}}\n",
+                id
+            )
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use super::{ImportCandidate, ParsedNote};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use quick_xml::escape::resolve_html5_entity;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    Title,
+    Content,
+    Created,
+    Updated,
+    Tag,
+}
+
+impl Field {
+    fn from_element(name: &[u8]) -> Option<Field> {
+        match name {
+            b"title" => Some(Self::Title),
+            b"content" => Some(Self::Content),
+            b"created" => Some(Self::Created),
+            b"updated" => Some(Self::Updated),
+            b"tag" => Some(Self::Tag),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct EnexNote {
+    title: String,
+    content: String,
+    created: String,
+    updated: String,
+    tags: Vec<String>,
+}
+
+impl EnexNote {
+    fn set(&mut self, field: Field, value: String) {
+        match field {
+            Field::Title => self.title = value,
+            Field::Content => self.content = value,
+            Field::Created => self.created = value,
+            Field::Updated => self.updated = value,
+            Field::Tag => self.tags.push(value),
+        }
+    }
+
+    fn into_candidate(self, position: usize) -> ImportCandidate {
+        let source = if self.title.trim().is_empty() {
+            format!("Note {}", position)
+        } else {
+            self.title.clone()
+        };
+
+        let note = enml_to_markdown(&self.content).and_then(|content| {
+            Ok(ParsedNote {
+                title: self.title,
+                content,
+                tags: self.tags,
+                created_at: parse_timestamp(&self.created)?,
+                updated_at: parse_timestamp(&self.updated)?,
+            })
+        });
+        ImportCandidate { source, note }
+    }
+}
+
+/// One candidate per `<note>` of an Evernote export, in file order.
+pub fn parse_enex(xml: &str) -> Result<Vec<ImportCandidate>, String> {
+    let invalid_file = |e: quick_xml::Error| format!("Invalid ENEX file: {}", e);
+    let mut reader = Reader::from_str(xml);
+
+    let mut is_enex = false;
+    let mut candidates = Vec::new();
+    let mut note: Option<EnexNote> = None;
+    let mut field = None;
+    let mut text = String::new();
+    loop {
+        match reader.read_event().map_err(invalid_file)? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"en-export" => is_enex = true,
+                b"note" => note = Some(EnexNote::default()),
+                name if note.is_some() => {
+                    field = Field::from_element(name);
+                    text.clear();
+                }
+                _ => {}
+            },
+            Event::Text(e) if field.is_some() => {
+                text.push_str(&e.unescape().map_err(invalid_file)?);
+            }
+            Event::CData(e) if field.is_some() => {
+                text.push_str(
+                    std::str::from_utf8(&e).map_err(|e| format!("Invalid ENEX file: {}", e))?,
+                );
+            }
+            Event::End(e) => {
+                if e.local_name().as_ref() == b"note" {
+                    if let Some(finished) = note.take() {
+                        candidates.push(finished.into_candidate(candidates.len() + 1));
+                    }
+                } else if let (Some(field), Some(note)) = (field.take(), note.as_mut()) {
+                    note.set(field, std::mem::take(&mut text).trim().to_string());
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !is_enex {
+        return Err("Not an Evernote export: missing <en-export>".to_string());
+    }
+    Ok(candidates)
+}
+
+/// ENEX timestamps look like `20240101T120000Z`. An empty string means the
+/// note does not have one.
+fn parse_timestamp(s: &str) -> Result<Option<DateTime<Utc>>, String> {
+    if s.is_empty() {
+        return Ok(None);
+    }
+    NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%SZ")
+        .map(|timestamp| Some(timestamp.and_utc()))
+        .map_err(|_| format!("Invalid timestamp '{}'", s))
+}
+
+/// Converts the ENML (Evernote's XHTML dialect) of a note to Markdown,
+/// keeping headings, lists, checkboxes, emphasis and links. Embedded media
+/// is dropped.
+fn enml_to_markdown(enml: &str) -> Result<String, String> {
+    let invalid_content = |e: quick_xml::Error| format!("Invalid note content: {}", e);
+    let mut reader = Reader::from_str(enml);
+
+    let mut markdown = String::new();
+    let mut links = Vec::new();
+    let mut preformatted = 0;
+    loop {
+        match reader.read_event().map_err(invalid_content)? {
+            Event::Start(e) => {
+                if e.local_name().as_ref() == b"pre" {
+                    preformatted += 1;
+                }
+                open_element(&e, &mut markdown, &mut links)?;
+            }
+            Event::Empty(e) => {
+                open_element(&e, &mut markdown, &mut links)?;
+                close_element(e.local_name().as_ref(), &mut markdown, &mut links);
+            }
+            Event::End(e) => {
+                if e.local_name().as_ref() == b"pre" {
+                    preformatted -= 1;
+                }
+                close_element(e.local_name().as_ref(), &mut markdown, &mut links);
+            }
+            Event::Text(e) => {
+                let text = e
+                    .unescape_with(resolve_html5_entity)
+                    .map_err(invalid_content)?;
+                push_text(&mut markdown, &text, preformatted > 0);
+            }
+            Event::CData(e) => {
+                let text =
+                    std::str::from_utf8(&e).map_err(|e| format!("Invalid note content: {}", e))?;
+                push_text(&mut markdown, text, preformatted > 0);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(markdown.trim().to_string())
+}
+
+fn open_element(
+    e: &BytesStart,
+    markdown: &mut String,
+    links: &mut Vec<String>,
+) -> Result<(), String> {
+    match e.local_name().as_ref() {
+        b"div" | b"p" | b"ul" | b"ol" | b"table" | b"tr" | b"blockquote" | b"pre" => {
+            end_line(markdown)
+        }
+        heading @ (b"h1" | b"h2" | b"h3" | b"h4" | b"h5" | b"h6") => {
+            end_line(markdown);
+            let level = (heading[1] - b'0') as usize;
+            markdown.push_str(&format!("{} ", "#".repeat(level)));
+        }
+        b"li" => {
+            end_line(markdown);
+            markdown.push_str("- ");
+        }
+        b"br" => markdown.push('\n'),
+        b"hr" => {
+            end_line(markdown);
+            markdown.push_str("---\n");
+        }
+        b"b" | b"strong" => markdown.push_str("**"),
+        b"i" | b"em" => markdown.push('*'),
+        b"en-todo" => {
+            let checked = attribute(e, "checked")?.as_deref() == Some("true");
+            markdown.push_str(if checked { "[x] " } else { "[ ] " });
+        }
+        b"a" => {
+            markdown.push('[');
+            links.push(attribute(e, "href")?.unwrap_or_default());
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn close_element(name: &[u8], markdown: &mut String, links: &mut Vec<String>) {
+    match name {
+        b"p" => {
+            end_line(markdown);
+            markdown.push('\n');
+        }
+        b"div" | b"li" | b"tr" | b"blockquote" | b"pre" | b"ul" | b"ol" | b"table" | b"h1"
+        | b"h2" | b"h3" | b"h4" | b"h5" | b"h6" => end_line(markdown),
+        b"b" | b"strong" => markdown.push_str("**"),
+        b"i" | b"em" => markdown.push('*'),
+        b"a" => {
+            let href = links.pop().unwrap_or_default();
+            markdown.push_str(&format!("]({})", href));
+        }
+        _ => {}
+    }
+}
+
+fn attribute(e: &BytesStart, name: &str) -> Result<Option<String>, String> {
+    let invalid_attribute = |e: quick_xml::Error| format!("Invalid note content: {}", e);
+    match e
+        .try_get_attribute(name)
+        .map_err(|e| invalid_attribute(e.into()))?
+    {
+        Some(attribute) => Ok(Some(
+            attribute
+                .unescape_value()
+                .map_err(invalid_attribute)?
+                .into_owned(),
+        )),
+        None => Ok(None),
+    }
+}
+
+fn end_line(markdown: &mut String) {
+    if !markdown.is_empty() && !markdown.ends_with('\n') {
+        markdown.push('\n');
+    }
+}
+
+/// Appends text the way a browser would lay it out: runs of whitespace
+/// collapse to one space, except inside `<pre>`.
+fn push_text(markdown: &mut String, text: &str, preformatted: bool) {
+    if preformatted {
+        markdown.push_str(text);
+        return;
+    }
+    for c in text.chars() {
+        if c.is_whitespace() {
+            let at_break = markdown.is_empty() || markdown.ends_with([' ', '\n']);
+            if !at_break {
+                markdown.push(' ');
+            }
+        } else {
+            markdown.push(c);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_ok};
+
+    const ENEX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE en-export SYSTEM "http://xml.evernote.com/pub/evernote-export4.dtd">
+<en-export export-date="20240301T090000Z" application="Evernote">
+  <note>
+    <title>Trip &amp; packing</title>
+    <created>20240101T120000Z</created>
+    <updated>20240102T080000Z</updated>
+    <tag>travel</tag>
+    <tag>lists</tag>
+    <note-attributes><author>me</author></note-attributes>
+    <content><![CDATA[<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE en-note SYSTEM "http://xml.evernote.com/pub/enml2.dtd">
+<en-note><h1>Packing</h1><div><en-todo checked="true"/>Passport</div>
+<div><en-todo/>Charger&nbsp;cable</div><div>See <a href="https://example.com">the <b>guide</b></a></div></en-note>]]></content>
+  </note>
+  <note>
+    <title></title>
+    <content><![CDATA[<en-note><div>Untitled</div></en-note>]]></content>
+  </note>
+</en-export>"#;
+
+    #[test]
+    fn notes_are_read_with_their_metadata() {
+        let candidates = assert_ok!(parse_enex(ENEX));
+        assert_eq!(candidates.len(), 2);
+
+        let trip = &candidates[0];
+        assert_eq!(trip.source, "Trip & packing");
+        let note = trip.note.as_ref().unwrap();
+        assert_eq!(note.title, "Trip & packing");
+        assert_eq!(note.tags, vec!["travel", "lists"]);
+        assert_eq!(
+            note.created_at.unwrap().to_rfc3339(),
+            "2024-01-01T12:00:00+00:00"
+        );
+        assert_eq!(
+            note.updated_at.unwrap().to_rfc3339(),
+            "2024-01-02T08:00:00+00:00"
+        );
+        assert_eq!(
+            note.content,
+            "# Packing\n[x] Passport\n[ ] Charger cable\nSee [the **guide**](https://example.com)"
+        );
+
+        assert_eq!(candidates[1].source, "Note 2");
+    }
+
+    #[test]
+    fn lists_and_paragraphs_become_markdown() {
+        let markdown = assert_ok!(enml_to_markdown(
+            "<en-note><p>Intro   text</p><ul>\n  <li>One</li>\n  <li><i>Two</i></li>\n</ul></en-note>"
+        ));
+        assert_eq!(markdown, "Intro text\n\n- One\n- *Two*");
+    }
+
+    #[test]
+    fn invalid_timestamps_fail_only_their_note() {
+        let candidates = assert_ok!(parse_enex(
+            "<en-export><note><title>A</title><created>yesterday</created>\
+             <content><![CDATA[<en-note>x</en-note>]]></content></note></en-export>"
+        ));
+        assert_err!(&candidates[0].note);
+    }
+
+    #[test]
+    fn other_xml_documents_are_rejected() {
+        assert_err!(parse_enex("<rss><note><title>A</title></note></rss>"));
+        assert_err!(parse_enex("<en-export><note></title></en-export>"));
+    }
+}
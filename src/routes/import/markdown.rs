@@ -0,0 +1,199 @@
+use super::{ImportCandidate, ParsedNote};
+use chrono::{DateTime, Utc};
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+/// Guards against archives that decompress to far more than was uploaded.
+const MAX_UNPACKED_BYTES: u64 = 104_857_600;
+
+/// One candidate per Markdown file in the archive, in archive order. Other
+/// files, directories and hidden files are skipped.
+pub fn parse_archive(bytes: &[u8]) -> Result<Vec<ImportCandidate>, String> {
+    let invalid_archive = |e: zip::result::ZipError| format!("Invalid zip archive: {}", e);
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(invalid_archive)?;
+
+    let mut candidates = Vec::new();
+    let mut unpacked = 0;
+    for i in 0..archive.len() {
+        let file = archive.by_index(i).map_err(invalid_archive)?;
+        let path = file.name().to_string();
+        if !file.is_file() || !is_markdown_file(&path) {
+            continue;
+        }
+
+        let mut contents = Vec::new();
+        file.take(MAX_UNPACKED_BYTES - unpacked + 1)
+            .read_to_end(&mut contents)
+            .map_err(|e| format!("Failed to unpack {}: {}", path, e))?;
+        unpacked += contents.len() as u64;
+        if unpacked > MAX_UNPACKED_BYTES {
+            return Err(format!(
+                "The archive unpacks to more than {} bytes",
+                MAX_UNPACKED_BYTES
+            ));
+        }
+
+        let note = String::from_utf8(contents)
+            .map_err(|_| "The file is not valid UTF-8".to_string())
+            .and_then(|text| parse_markdown(&path, &text));
+        candidates.push(ImportCandidate { source: path, note });
+    }
+
+    Ok(candidates)
+}
+
+fn is_markdown_file(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or_default().to_lowercase();
+    let is_hidden = name.starts_with('.') || path.starts_with("__MACOSX/");
+    !is_hidden && (name.ends_with(".md") || name.ends_with(".markdown"))
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+struct FrontMatter {
+    title: Option<String>,
+    tags: Option<Tags>,
+    #[serde(alias = "created")]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(alias = "updated")]
+    updated_at: Option<DateTime<Utc>>,
+}
+
+/// Either a YAML list or a single comma-separated string.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum Tags {
+    List(Vec<String>),
+    Text(String),
+}
+
+impl Tags {
+    fn into_names(self) -> Vec<String> {
+        match self {
+            Tags::List(names) => names,
+            Tags::Text(text) => text
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+/// Reads YAML front matter for the title, tags and timestamps. Without a
+/// title in the front matter, the file name is used.
+fn parse_markdown(path: &str, text: &str) -> Result<ParsedNote, String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let (front_matter, content) = split_front_matter(text);
+    let front_matter: FrontMatter = match front_matter {
+        Some(yaml) if !yaml.trim().is_empty() => {
+            serde_yaml::from_str(yaml).map_err(|e| format!("Invalid front matter: {}", e))?
+        }
+        _ => FrontMatter::default(),
+    };
+
+    Ok(ParsedNote {
+        title: front_matter.title.unwrap_or_else(|| file_stem(path)),
+        content: content.to_string(),
+        tags: front_matter.tags.map(Tags::into_names).unwrap_or_default(),
+        created_at: front_matter.created_at,
+        updated_at: front_matter.updated_at,
+    })
+}
+
+/// Splits `---` delimited front matter from the rest of the document. The
+/// blank line that usually follows the front matter is not part of the body.
+fn split_front_matter(text: &str) -> (Option<&str>, &str) {
+    let Some(rest) = text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))
+    else {
+        return (None, text);
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let delimiter = line.trim_end();
+        if delimiter == "---" || delimiter == "..." {
+            let body = &rest[offset + line.len()..];
+            let body = body
+                .strip_prefix("\r\n")
+                .or_else(|| body.strip_prefix('\n'))
+                .unwrap_or(body);
+            return (Some(&rest[..offset]), body);
+        }
+        offset += line.len();
+    }
+
+    // Without a closing delimiter the dashes are a horizontal rule
+    (None, text)
+}
+
+fn file_stem(path: &str) -> String {
+    let name = path.rsplit('/').next().unwrap_or_default();
+    match name.rsplit_once('.') {
+        Some((stem, _)) => stem.to_string(),
+        None => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_none, assert_ok};
+
+    #[test]
+    fn front_matter_sets_title_tags_and_timestamps() {
+        let note = assert_ok!(parse_markdown(
+            "notes/plan.md",
+            "---\ntitle: \"Q3 plan\"\ntags:\n  - work\n  - ideas\ncreated_at: 2024-01-01T10:00:00+00:00\n---\n\n# Plan\n"
+        ));
+        assert_eq!(note.title, "Q3 plan");
+        assert_eq!(note.content, "# Plan\n");
+        assert_eq!(note.tags, vec!["work", "ideas"]);
+        assert_eq!(
+            note.created_at.unwrap().to_rfc3339(),
+            "2024-01-01T10:00:00+00:00"
+        );
+        assert_none!(note.updated_at);
+    }
+
+    #[test]
+    fn files_without_front_matter_are_titled_after_the_file() {
+        let note = assert_ok!(parse_markdown("notes/Grocery list.md", "- milk\n"));
+        assert_eq!(note.title, "Grocery list");
+        assert_eq!(note.content, "- milk\n");
+        assert!(note.tags.is_empty());
+    }
+
+    #[test]
+    fn tags_can_be_a_comma_separated_string() {
+        let note = assert_ok!(parse_markdown("a.md", "---\ntags: work, ideas\n---\nBody"));
+        assert_eq!(note.tags, vec!["work", "ideas"]);
+    }
+
+    #[test]
+    fn unterminated_front_matter_is_content() {
+        let note = assert_ok!(parse_markdown("a.md", "---\nBody"));
+        assert_eq!(note.content, "---\nBody");
+    }
+
+    #[test]
+    fn invalid_front_matter_is_rejected() {
+        assert_err!(parse_markdown("a.md", "---\ntags: [work\n---\nBody"));
+        assert_err!(parse_markdown(
+            "a.md",
+            "---\ncreated_at: yesterday\n---\nBody"
+        ));
+    }
+
+    #[test]
+    fn only_visible_markdown_files_are_imported() {
+        assert!(is_markdown_file("Plan.md"));
+        assert!(is_markdown_file("work/Plan.MARKDOWN"));
+        assert!(!is_markdown_file("work/photo.png"));
+        assert!(!is_markdown_file("work/.Plan.md"));
+        assert!(!is_markdown_file("__MACOSX/Plan.md"));
+    }
+}
@@ -0,0 +1,278 @@
+mod enex;
+mod markdown;
+
+use crate::authentication::AuthenticatedUser;
+use crate::domain::{NewNote, TagName};
+use crate::routes::notes::save_links;
+use crate::routes::tags::get_or_create_tags;
+use crate::startup::MAX_PAYLOAD_BYTES;
+use actix_multipart::Multipart;
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// A note read from an import file, before validation.
+#[derive(Debug)]
+pub struct ParsedNote {
+    pub title: String,
+    pub content: String,
+    pub tags: Vec<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// One item of an import file. `note` is an error when the item could not
+/// be read at all.
+#[derive(Debug)]
+pub struct ImportCandidate {
+    /// Where the item came from: a path in the archive or a note title.
+    pub source: String,
+    pub note: Result<ParsedNote, String>,
+}
+
+/// A note that passed validation and is ready to be saved.
+struct ValidNote {
+    new_note: NewNote,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ImportReport {
+    pub imported_count: usize,
+    pub failed_count: usize,
+    /// One entry per note found in the file, in file order.
+    pub items: Vec<ImportItemReport>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ImportItemReport {
+    pub source: String,
+    #[serde(flatten)]
+    pub outcome: ImportOutcome,
+}
+
+#[derive(serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ImportOutcome {
+    Imported { note_id: String },
+    Failed { error: String },
+}
+
+#[derive(thiserror::Error)]
+pub enum ImportError {
+    #[error("Invalid input: {0}")]
+    ValidationError(String),
+    #[error("Imports are limited to {} bytes", MAX_PAYLOAD_BYTES)]
+    TooLarge,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for ImportError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ImportError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            ImportError::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ImportError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Imports notes from the `file` field of a multipart upload: either a zip of
+/// Markdown files or an Evernote `.enex` export. Notes that fail validation
+/// are reported and skipped; the others are saved together.
+#[tracing::instrument(name = "Import notes", skip(user, payload, pool), fields(user_id = %user.user_id))]
+pub async fn import_notes(
+    user: AuthenticatedUser,
+    payload: Multipart,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ImportError> {
+    let contents = read_upload(payload).await?;
+    let candidates = parse_import_file(&contents).map_err(ImportError::ValidationError)?;
+
+    let report = save_import(&pool, user.user_id, candidates)
+        .await
+        .map_err(ImportError::UnexpectedError)?;
+    tracing::info!(
+        imported = report.imported_count,
+        failed = report.failed_count,
+        "Imported notes"
+    );
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Tells the two supported formats apart by their contents, so the file
+/// name does not matter.
+fn parse_import_file(contents: &[u8]) -> Result<Vec<ImportCandidate>, String> {
+    // Local file header, or the end of central directory of an empty archive
+    if contents.starts_with(b"PK\x03\x04") || contents.starts_with(b"PK\x05\x06") {
+        return markdown::parse_archive(contents);
+    }
+
+    let xml = std::str::from_utf8(contents).map_err(|_| unsupported_file())?;
+    if xml.contains("<en-export") {
+        enex::parse_enex(xml)
+    } else {
+        Err(unsupported_file())
+    }
+}
+
+fn unsupported_file() -> String {
+    "Upload either a zip of Markdown files or an Evernote .enex export".to_string()
+}
+
+fn validate(user_id: Uuid, note: ParsedNote) -> Result<ValidNote, String> {
+    Ok(ValidNote {
+        new_note: NewNote::parse(user_id, note.title, note.content, None, note.tags)?,
+        created_at: note.created_at,
+        updated_at: note.updated_at,
+    })
+}
+
+#[tracing::instrument(name = "Save imported notes", skip(pool, candidates))]
+async fn save_import(
+    pool: &PgPool,
+    user_id: Uuid,
+    candidates: Vec<ImportCandidate>,
+) -> Result<ImportReport, anyhow::Error> {
+    let validated: Vec<(String, Result<ValidNote, String>)> = candidates
+        .into_iter()
+        .map(|candidate| {
+            let note = candidate.note.and_then(|note| validate(user_id, note));
+            (candidate.source, note)
+        })
+        .collect();
+
+    let mut transaction = pool.begin().await?;
+
+    // Only tags of notes that are actually imported get created
+    let tag_names: Vec<TagName> = validated
+        .iter()
+        .filter_map(|(_, note)| note.as_ref().ok())
+        .flat_map(|note| note.new_note.tags.iter().cloned())
+        .collect();
+    let tag_ids = get_or_create_tags(&mut transaction, user_id, &tag_names).await?;
+
+    let mut report = ImportReport {
+        imported_count: 0,
+        failed_count: 0,
+        items: Vec::with_capacity(validated.len()),
+    };
+    for (source, note) in validated {
+        let outcome = match note {
+            Ok(note) => {
+                let note_id = insert_imported_note(&mut transaction, &note, &tag_ids).await?;
+                report.imported_count += 1;
+                ImportOutcome::Imported {
+                    note_id: note_id.to_string(),
+                }
+            }
+            Err(error) => {
+                report.failed_count += 1;
+                ImportOutcome::Failed { error }
+            }
+        };
+        report.items.push(ImportItemReport { source, outcome });
+    }
+
+    transaction.commit().await?;
+    Ok(report)
+}
+
+/// Saves a note with its original timestamps when the file had them.
+async fn insert_imported_note(
+    transaction: &mut Transaction<'_, Postgres>,
+    note: &ValidNote,
+    tag_ids: &HashMap<String, Uuid>,
+) -> Result<Uuid, anyhow::Error> {
+    let note_id = Uuid::new_v4();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO notes (note_id, user_id, title, content, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, COALESCE($5, NOW()), COALESCE($6, $5, NOW()))
+        "#,
+        note_id,
+        note.new_note.user_id,
+        note.new_note.title.as_ref(),
+        note.new_note.content.as_ref(),
+        note.created_at,
+        note.updated_at,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    let note_tag_ids: Vec<Uuid> = note
+        .new_note
+        .tags
+        .iter()
+        .filter_map(|name| tag_ids.get(name.as_ref()).copied())
+        .collect();
+    sqlx::query!(
+        r#"
+        INSERT INTO note_tags (note_id, tag_id)
+        SELECT $1, UNNEST($2::uuid[])
+        ON CONFLICT DO NOTHING
+        "#,
+        note_id,
+        &note_tag_ids
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    save_links(transaction, note_id, &note.new_note.content.links()).await?;
+
+    Ok(note_id)
+}
+
+/// Reads the `file` field of a multipart upload into memory, ignoring any
+/// other fields.
+async fn read_upload(mut payload: Multipart) -> Result<Vec<u8>, ImportError> {
+    let mut upload = None;
+
+    while let Some(mut field) = payload.try_next().await.map_err(invalid_upload)? {
+        if field.name() != Some("file") || upload.is_some() {
+            while field.try_next().await.map_err(invalid_upload)?.is_some() {}
+            continue;
+        }
+
+        let mut contents = Vec::new();
+        while let Some(chunk) = field.try_next().await.map_err(invalid_upload)? {
+            if contents.len() + chunk.len() > MAX_PAYLOAD_BYTES {
+                return Err(ImportError::TooLarge);
+            }
+            contents.extend_from_slice(&chunk);
+        }
+        upload = Some(contents);
+    }
+
+    upload.ok_or_else(|| ImportError::ValidationError("Missing multipart field `file`".to_string()))
+}
+
+fn invalid_upload(e: actix_multipart::MultipartError) -> ImportError {
+    ImportError::ValidationError(e.to_string())
+}
+
+fn error_chain_fmt(
+    e: &impl std::error::Error,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    writeln!(f, "{}\n", e)?;
+    let mut current = e.source();
+    while let Some(cause) = current {
+        writeln!(f, "Caused by:\n\t{}", cause)?;
+        current = cause.source();
+    }
+    Ok(())
+}
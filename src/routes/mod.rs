@@ -1,15 +1,27 @@
+mod export;
+mod graph;
 mod health_check;
 mod home;
+mod import;
 mod login;
 mod logout;
+mod notebooks;
 mod notes;
+mod searches;
 mod tags;
+mod trash;
 mod users;
 
+pub use export::*;
+pub use graph::*;
 pub use health_check::*;
 pub use home::*;
+pub use import::*;
 pub use login::*;
 pub use logout::*;
+pub use notebooks::*;
 pub use notes::*;
+pub use searches::*;
 pub use tags::*;
+pub use trash::*;
 pub use users::*;
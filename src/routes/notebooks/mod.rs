@@ -0,0 +1,343 @@
+use crate::authentication::AuthenticatedUser;
+use crate::domain::NotebookName;
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(serde::Deserialize)]
+pub struct NotebookRequest {
+    pub name: String,
+    /// Nests the notebook inside another one. Omit for a top-level notebook.
+    pub parent_id: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct NotebookResponse {
+    pub notebook_id: String,
+    pub name: String,
+    pub parent_id: Option<String>,
+    pub note_count: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(thiserror::Error)]
+pub enum NotebookError {
+    #[error("Invalid input: {0}")]
+    ValidationError(String),
+    #[error("A notebook with this name already exists here")]
+    Duplicate,
+    #[error("Notebook not found")]
+    NotFound,
+    #[error("Invalid notebook ID")]
+    InvalidId,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for NotebookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for NotebookError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            NotebookError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            NotebookError::Duplicate => StatusCode::CONFLICT,
+            NotebookError::NotFound => StatusCode::NOT_FOUND,
+            NotebookError::InvalidId => StatusCode::BAD_REQUEST,
+            NotebookError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(name = "Create notebook", skip(user, request, pool), fields(user_id = %user.user_id))]
+pub async fn create_notebook(
+    user: AuthenticatedUser,
+    request: web::Json<NotebookRequest>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, NotebookError> {
+    let (name, parent_id) = parse_request(request.into_inner())?;
+
+    if let Some(parent_id) = parent_id {
+        verify_notebook_ownership(pool.as_ref(), parent_id, user.user_id).await?;
+    }
+
+    let notebook_id = Uuid::new_v4();
+    sqlx::query!(
+        r#"
+        INSERT INTO notebooks (notebook_id, user_id, parent_id, name)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        notebook_id,
+        user.user_id,
+        parent_id,
+        name.as_ref(),
+    )
+    .execute(pool.as_ref())
+    .await
+    .map_err(map_write_error)?;
+
+    let notebook = fetch_notebook(&pool, notebook_id, user.user_id).await?;
+    Ok(HttpResponse::Created().json(notebook))
+}
+
+#[tracing::instrument(name = "List notebooks", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn list_notebooks(
+    user: AuthenticatedUser,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, NotebookError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT nb.notebook_id, nb.name, nb.parent_id, nb.created_at, nb.updated_at,
+               COUNT(n.note_id) AS "note_count!"
+        FROM notebooks nb
+        LEFT JOIN notes n ON n.notebook_id = nb.notebook_id AND n.deleted_at IS NULL
+        WHERE nb.user_id = $1
+        GROUP BY nb.notebook_id
+        ORDER BY nb.name
+        "#,
+        user.user_id
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| NotebookError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    let notebooks: Vec<NotebookResponse> = rows
+        .into_iter()
+        .map(|r| NotebookResponse {
+            notebook_id: r.notebook_id.to_string(),
+            name: r.name,
+            parent_id: r.parent_id.map(|id| id.to_string()),
+            note_count: r.note_count,
+            created_at: r.created_at.to_rfc3339(),
+            updated_at: r.updated_at.to_rfc3339(),
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(notebooks))
+}
+
+#[tracing::instrument(name = "Get notebook", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn get_notebook(
+    user: AuthenticatedUser,
+    notebook_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, NotebookError> {
+    let notebook_id = Uuid::parse_str(&notebook_id).map_err(|_| NotebookError::InvalidId)?;
+    let notebook = fetch_notebook(&pool, notebook_id, user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(notebook))
+}
+
+#[tracing::instrument(name = "Update notebook", skip(user, request, pool), fields(user_id = %user.user_id))]
+pub async fn update_notebook(
+    user: AuthenticatedUser,
+    notebook_id: web::Path<String>,
+    request: web::Json<NotebookRequest>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, NotebookError> {
+    let notebook_id = Uuid::parse_str(&notebook_id).map_err(|_| NotebookError::InvalidId)?;
+    let (name, parent_id) = parse_request(request.into_inner())?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .map_err(|e| NotebookError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    // Concurrent moves (A under B and B under A) could each pass the cycle
+    // check on their own, so moves of the user's notebooks take turns
+    sqlx::query!(
+        "SELECT notebook_id FROM notebooks WHERE user_id = $1 ORDER BY notebook_id FOR UPDATE",
+        user.user_id
+    )
+    .fetch_all(&mut *transaction)
+    .await
+    .map_err(|e| NotebookError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    verify_notebook_ownership(&mut *transaction, notebook_id, user.user_id).await?;
+    if let Some(parent_id) = parent_id {
+        verify_notebook_ownership(&mut *transaction, parent_id, user.user_id).await?;
+        if is_within(&mut *transaction, parent_id, notebook_id).await? {
+            return Err(NotebookError::ValidationError(
+                "A notebook cannot be moved into itself or one of its sub-notebooks".to_string(),
+            ));
+        }
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE notebooks
+        SET name = $1, parent_id = $2, updated_at = NOW()
+        WHERE notebook_id = $3 AND user_id = $4
+        "#,
+        name.as_ref(),
+        parent_id,
+        notebook_id,
+        user.user_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(map_write_error)?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| NotebookError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    let notebook = fetch_notebook(&pool, notebook_id, user.user_id).await?;
+    Ok(HttpResponse::Ok().json(notebook))
+}
+
+#[tracing::instrument(name = "Delete notebook", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn delete_notebook(
+    user: AuthenticatedUser,
+    notebook_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, NotebookError> {
+    let notebook_id = Uuid::parse_str(&notebook_id).map_err(|_| NotebookError::InvalidId)?;
+
+    // Sub-notebooks are deleted with it and their notes become unfiled
+    let result = sqlx::query!(
+        "DELETE FROM notebooks WHERE notebook_id = $1 AND user_id = $2",
+        notebook_id,
+        user.user_id
+    )
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| NotebookError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(NotebookError::NotFound);
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Returns whether `notebook_id` exists and belongs to `user_id`. Used before
+/// filing a note into a notebook.
+pub(crate) async fn notebook_belongs_to_user(
+    executor: impl sqlx::PgExecutor<'_>,
+    notebook_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query!(
+        "SELECT notebook_id FROM notebooks WHERE notebook_id = $1 AND user_id = $2",
+        notebook_id,
+        user_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(row.is_some())
+}
+
+fn parse_request(request: NotebookRequest) -> Result<(NotebookName, Option<Uuid>), NotebookError> {
+    let name = NotebookName::parse(request.name).map_err(NotebookError::ValidationError)?;
+    let parent_id = request
+        .parent_id
+        .map(|id| Uuid::parse_str(&id).map_err(|_| NotebookError::InvalidId))
+        .transpose()?;
+
+    Ok((name, parent_id))
+}
+
+async fn verify_notebook_ownership(
+    executor: impl sqlx::PgExecutor<'_>,
+    notebook_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), NotebookError> {
+    let owned = notebook_belongs_to_user(executor, notebook_id, user_id)
+        .await
+        .map_err(|e| NotebookError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    if owned {
+        Ok(())
+    } else {
+        Err(NotebookError::NotFound)
+    }
+}
+
+/// Returns whether `notebook_id` is `ancestor_id` or nested anywhere below it.
+async fn is_within(
+    executor: impl sqlx::PgExecutor<'_>,
+    notebook_id: Uuid,
+    ancestor_id: Uuid,
+) -> Result<bool, NotebookError> {
+    let row = sqlx::query!(
+        r#"
+        WITH RECURSIVE subtree AS (
+            SELECT notebook_id FROM notebooks WHERE notebook_id = $1
+            UNION
+            SELECT nb.notebook_id FROM notebooks nb
+            JOIN subtree s ON nb.parent_id = s.notebook_id
+        )
+        SELECT EXISTS(SELECT 1 FROM subtree WHERE notebook_id = $2) AS "within!"
+        "#,
+        ancestor_id,
+        notebook_id
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(|e| NotebookError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    Ok(row.within)
+}
+
+#[tracing::instrument(name = "Fetch notebook from database", skip(pool))]
+async fn fetch_notebook(
+    pool: &PgPool,
+    notebook_id: Uuid,
+    user_id: Uuid,
+) -> Result<NotebookResponse, NotebookError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT nb.notebook_id, nb.name, nb.parent_id, nb.created_at, nb.updated_at,
+               COUNT(n.note_id) AS "note_count!"
+        FROM notebooks nb
+        LEFT JOIN notes n ON n.notebook_id = nb.notebook_id AND n.deleted_at IS NULL
+        WHERE nb.notebook_id = $1 AND nb.user_id = $2
+        GROUP BY nb.notebook_id
+        "#,
+        notebook_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| NotebookError::UnexpectedError(anyhow::anyhow!(e)))?
+    .ok_or(NotebookError::NotFound)?;
+
+    Ok(NotebookResponse {
+        notebook_id: row.notebook_id.to_string(),
+        name: row.name,
+        parent_id: row.parent_id.map(|id| id.to_string()),
+        note_count: row.note_count,
+        created_at: row.created_at.to_rfc3339(),
+        updated_at: row.updated_at.to_rfc3339(),
+    })
+}
+
+fn map_write_error(e: sqlx::Error) -> NotebookError {
+    if let Some(db) = e.as_database_error() {
+        if db.is_unique_violation() {
+            return NotebookError::Duplicate;
+        }
+    }
+
+    NotebookError::UnexpectedError(anyhow::anyhow!(e))
+}
+
+fn error_chain_fmt(
+    e: &impl std::error::Error,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    writeln!(f, "{}\n", e)?;
+    let mut current = e.source();
+    while let Some(cause) = current {
+        writeln!(f, "Caused by:\n\t{}", cause)?;
+        current = cause.source();
+    }
+    Ok(())
+}
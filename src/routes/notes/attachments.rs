@@ -0,0 +1,383 @@
+use crate::authentication::AuthenticatedUser;
+use crate::domain::AttachmentFilename;
+use crate::startup::MAX_PAYLOAD_BYTES;
+use crate::storage::{delete_stored_files, FileStorage};
+use actix_multipart::Multipart;
+use actix_web::http::header::{
+    Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue,
+    X_CONTENT_TYPE_OPTIONS,
+};
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use futures::TryStreamExt;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(serde::Serialize)]
+pub struct AttachmentResponse {
+    pub attachment_id: String,
+    pub note_id: String,
+    pub filename: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub created_at: String,
+}
+
+#[derive(thiserror::Error)]
+pub enum AttachmentError {
+    #[error("Invalid input: {0}")]
+    ValidationError(String),
+    #[error("Attachments are limited to {} bytes", MAX_PAYLOAD_BYTES)]
+    TooLarge,
+    #[error("Note not found")]
+    NoteNotFound,
+    #[error("Attachment not found")]
+    NotFound,
+    #[error("Invalid ID")]
+    InvalidId,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for AttachmentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for AttachmentError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AttachmentError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            AttachmentError::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            AttachmentError::NoteNotFound => StatusCode::NOT_FOUND,
+            AttachmentError::NotFound => StatusCode::NOT_FOUND,
+            AttachmentError::InvalidId => StatusCode::BAD_REQUEST,
+            AttachmentError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// The `file` field of an upload.
+struct Upload {
+    filename: AttachmentFilename,
+    content_type: String,
+    contents: Vec<u8>,
+}
+
+#[tracing::instrument(
+    name = "Upload attachment",
+    skip(user, payload, pool, storage),
+    fields(user_id = %user.user_id)
+)]
+pub async fn upload_attachment(
+    user: AuthenticatedUser,
+    note_id: web::Path<String>,
+    payload: Multipart,
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn FileStorage>,
+) -> Result<HttpResponse, AttachmentError> {
+    let note_id = Uuid::parse_str(&note_id).map_err(|_| AttachmentError::InvalidId)?;
+    verify_note_ownership(&pool, note_id, user.user_id).await?;
+
+    let upload = read_upload(payload).await?;
+    let attachment_id = Uuid::new_v4();
+    let storage_key = format!("{}/{}", user.user_id, attachment_id);
+    let size_bytes = upload.contents.len() as i64;
+
+    storage
+        .put(&storage_key, upload.contents, &upload.content_type)
+        .await
+        .map_err(AttachmentError::UnexpectedError)?;
+
+    let inserted = insert_attachment(
+        &pool,
+        attachment_id,
+        note_id,
+        user.user_id,
+        &upload.filename,
+        &upload.content_type,
+        size_bytes,
+        &storage_key,
+    )
+    .await;
+    let created_at = match inserted {
+        Ok(Some(created_at)) => created_at,
+        Ok(None) => {
+            // The note was deleted while the upload was in progress
+            delete_stored_files(storage.get_ref(), &[storage_key]).await;
+            return Err(AttachmentError::NoteNotFound);
+        }
+        Err(e) => {
+            delete_stored_files(storage.get_ref(), &[storage_key]).await;
+            return Err(AttachmentError::UnexpectedError(anyhow::anyhow!(e)));
+        }
+    };
+
+    Ok(HttpResponse::Created().json(AttachmentResponse {
+        attachment_id: attachment_id.to_string(),
+        note_id: note_id.to_string(),
+        filename: upload.filename.as_ref().to_string(),
+        content_type: upload.content_type,
+        size_bytes,
+        created_at: created_at.to_rfc3339(),
+    }))
+}
+
+#[tracing::instrument(name = "List attachments", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn list_attachments(
+    user: AuthenticatedUser,
+    note_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, AttachmentError> {
+    let note_id = Uuid::parse_str(&note_id).map_err(|_| AttachmentError::InvalidId)?;
+    verify_note_ownership(&pool, note_id, user.user_id).await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT attachment_id, filename, content_type, size_bytes, created_at
+        FROM attachments
+        WHERE note_id = $1
+        ORDER BY created_at, attachment_id
+        "#,
+        note_id
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| AttachmentError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    let attachments: Vec<AttachmentResponse> = rows
+        .into_iter()
+        .map(|r| AttachmentResponse {
+            attachment_id: r.attachment_id.to_string(),
+            note_id: note_id.to_string(),
+            filename: r.filename,
+            content_type: r.content_type,
+            size_bytes: r.size_bytes,
+            created_at: r.created_at.to_rfc3339(),
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(attachments))
+}
+
+#[tracing::instrument(
+    name = "Download attachment",
+    skip(user, pool, storage),
+    fields(user_id = %user.user_id)
+)]
+pub async fn download_attachment(
+    user: AuthenticatedUser,
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn FileStorage>,
+) -> Result<HttpResponse, AttachmentError> {
+    let (note_id, attachment_id) = parse_attachment_path(path.into_inner())?;
+
+    let row = sqlx::query!(
+        r#"
+        SELECT a.filename, a.content_type, a.storage_key
+        FROM attachments a
+        JOIN notes n ON n.note_id = a.note_id
+        WHERE a.attachment_id = $1 AND a.note_id = $2
+            AND n.user_id = $3 AND n.deleted_at IS NULL
+        "#,
+        attachment_id,
+        note_id,
+        user.user_id
+    )
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| AttachmentError::UnexpectedError(anyhow::anyhow!(e)))?
+    .ok_or(AttachmentError::NotFound)?;
+
+    let contents = storage
+        .get(&row.storage_key)
+        .await
+        .map_err(AttachmentError::UnexpectedError)?
+        .ok_or_else(|| {
+            AttachmentError::UnexpectedError(anyhow::anyhow!(
+                "Stored file {} is missing",
+                row.storage_key
+            ))
+        })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(row.content_type)
+        .insert_header(content_disposition(row.filename))
+        // Uploaded HTML must never be rendered as part of the API's origin
+        .insert_header((X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        .body(contents))
+}
+
+#[tracing::instrument(
+    name = "Delete attachment",
+    skip(user, pool, storage),
+    fields(user_id = %user.user_id)
+)]
+pub async fn delete_attachment(
+    user: AuthenticatedUser,
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn FileStorage>,
+) -> Result<HttpResponse, AttachmentError> {
+    let (note_id, attachment_id) = parse_attachment_path(path.into_inner())?;
+
+    let storage_key = sqlx::query_scalar!(
+        r#"
+        DELETE FROM attachments a
+        USING notes n
+        WHERE a.attachment_id = $1 AND a.note_id = $2 AND n.note_id = a.note_id
+            AND n.user_id = $3 AND n.deleted_at IS NULL
+        RETURNING a.storage_key
+        "#,
+        attachment_id,
+        note_id,
+        user.user_id
+    )
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(|e| AttachmentError::UnexpectedError(anyhow::anyhow!(e)))?
+    .ok_or(AttachmentError::NotFound)?;
+
+    delete_stored_files(storage.get_ref(), &[storage_key]).await;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Reads the `file` field of a multipart upload into memory, ignoring any
+/// other fields.
+async fn read_upload(mut payload: Multipart) -> Result<Upload, AttachmentError> {
+    let mut upload = None;
+
+    while let Some(mut field) = payload.try_next().await.map_err(invalid_upload)? {
+        if field.name() != Some("file") || upload.is_some() {
+            while field.try_next().await.map_err(invalid_upload)?.is_some() {}
+            continue;
+        }
+
+        let filename = field
+            .content_disposition()
+            .and_then(|disposition| disposition.get_filename())
+            .ok_or_else(|| {
+                AttachmentError::ValidationError("The `file` field has no filename".to_string())
+            })?
+            .to_string();
+        let filename =
+            AttachmentFilename::parse(filename).map_err(AttachmentError::ValidationError)?;
+        let content_type = field
+            .content_type()
+            .map(|mime| mime.to_string())
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+
+        let mut contents = Vec::new();
+        while let Some(chunk) = field.try_next().await.map_err(invalid_upload)? {
+            if contents.len() + chunk.len() > MAX_PAYLOAD_BYTES {
+                return Err(AttachmentError::TooLarge);
+            }
+            contents.extend_from_slice(&chunk);
+        }
+
+        upload = Some(Upload {
+            filename,
+            content_type,
+            contents,
+        });
+    }
+
+    upload.ok_or_else(|| {
+        AttachmentError::ValidationError("Missing multipart field `file`".to_string())
+    })
+}
+
+fn invalid_upload(e: actix_multipart::MultipartError) -> AttachmentError {
+    AttachmentError::ValidationError(e.to_string())
+}
+
+/// Asks browsers to save the file rather than display it, keeping non-ASCII
+/// names intact through the RFC 5987 `filename*` parameter.
+fn content_disposition(filename: String) -> ContentDisposition {
+    let parameter = if filename.is_ascii() {
+        DispositionParam::Filename(filename)
+    } else {
+        DispositionParam::FilenameExt(ExtendedValue {
+            charset: Charset::Ext("UTF-8".to_string()),
+            language_tag: None,
+            value: filename.into_bytes(),
+        })
+    };
+
+    ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![parameter],
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(name = "Save attachment in database", skip(pool))]
+async fn insert_attachment(
+    pool: &PgPool,
+    attachment_id: Uuid,
+    note_id: Uuid,
+    user_id: Uuid,
+    filename: &AttachmentFilename,
+    content_type: &str,
+    size_bytes: i64,
+    storage_key: &str,
+) -> Result<Option<chrono::DateTime<chrono::Utc>>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        INSERT INTO attachments
+            (attachment_id, note_id, user_id, filename, content_type, size_bytes, storage_key)
+        SELECT $1, note_id, user_id, $4, $5, $6, $7
+        FROM notes
+        WHERE note_id = $2 AND user_id = $3 AND deleted_at IS NULL
+        RETURNING created_at
+        "#,
+        attachment_id,
+        note_id,
+        user_id,
+        filename.as_ref(),
+        content_type,
+        size_bytes,
+        storage_key
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+fn parse_attachment_path(path: (String, String)) -> Result<(Uuid, Uuid), AttachmentError> {
+    let (note_id, attachment_id) = path;
+    let note_id = Uuid::parse_str(&note_id).map_err(|_| AttachmentError::InvalidId)?;
+    let attachment_id = Uuid::parse_str(&attachment_id).map_err(|_| AttachmentError::InvalidId)?;
+    Ok((note_id, attachment_id))
+}
+
+async fn verify_note_ownership(
+    pool: &PgPool,
+    note_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), AttachmentError> {
+    sqlx::query!(
+        "SELECT note_id FROM notes WHERE note_id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        note_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| AttachmentError::UnexpectedError(anyhow::anyhow!(e)))?
+    .ok_or(AttachmentError::NoteNotFound)?;
+    Ok(())
+}
+
+fn error_chain_fmt(
+    e: &impl std::error::Error,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    writeln!(f, "{}\n", e)?;
+    let mut current = e.source();
+    while let Some(cause) = current {
+        writeln!(f, "Caused by:\n\t{}", cause)?;
+        current = cause.source();
+    }
+    Ok(())
+}
@@ -0,0 +1,349 @@
+use super::filter::NoteFilter;
+use super::list::{lock_matching_notes, NoteQueryParams};
+use crate::authentication::AuthenticatedUser;
+use crate::domain::TagName;
+use crate::routes::tags::get_or_create_tags;
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::{BTreeSet, HashSet};
+use uuid::Uuid;
+
+/// Most notes that can be listed by ID in one request.
+const MAX_BULK_NOTE_IDS: usize = 1000;
+
+#[derive(serde::Deserialize)]
+pub struct BulkNotesRequest {
+    /// Either `delete`, `add_tag`, `remove_tag` or `replace_tags`.
+    pub action: String,
+    /// The notes to change. Exactly one of `note_ids` and `filter` is required.
+    pub note_ids: Option<Vec<String>>,
+    /// Selects notes the same way as `GET /notes`, ignoring paging and sorting.
+    pub filter: Option<NoteQueryParams>,
+    /// The tag added or removed by `add_tag` and `remove_tag`.
+    pub tag: Option<String>,
+    /// The complete set of tags for `replace_tags`.
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug)]
+enum BulkAction {
+    /// Moves the notes to the trash.
+    Delete,
+    AddTag(TagName),
+    RemoveTag(TagName),
+    ReplaceTags(Vec<TagName>),
+}
+
+impl BulkAction {
+    fn parse(request: &BulkNotesRequest) -> Result<BulkAction, String> {
+        let tag = || {
+            let tag = request
+                .tag
+                .clone()
+                .ok_or_else(|| format!("`tag` is required for `{}`", request.action))?;
+            TagName::parse(tag)
+        };
+
+        match request.action.to_lowercase().as_str() {
+            "delete" => Ok(Self::Delete),
+            "add_tag" => tag().map(Self::AddTag),
+            "remove_tag" => tag().map(Self::RemoveTag),
+            "replace_tags" => request
+                .tags
+                .clone()
+                .ok_or_else(|| "`tags` is required for `replace_tags`".to_string())?
+                .into_iter()
+                .map(TagName::parse)
+                .collect::<Result<Vec<_>, _>>()
+                .map(Self::ReplaceTags),
+            other => Err(format!(
+                "'{}' is not a valid action. Use either `delete`, `add_tag`, `remove_tag` or `replace_tags`",
+                other
+            )),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            BulkAction::Delete => "delete",
+            BulkAction::AddTag(_) => "add_tag",
+            BulkAction::RemoveTag(_) => "remove_tag",
+            BulkAction::ReplaceTags(_) => "replace_tags",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum BulkSelection {
+    NoteIds(Vec<Uuid>),
+    Filter(NoteFilter),
+}
+
+impl BulkSelection {
+    fn parse(request: &BulkNotesRequest) -> Result<BulkSelection, String> {
+        match (&request.note_ids, &request.filter) {
+            (Some(note_ids), None) => {
+                if note_ids.len() > MAX_BULK_NOTE_IDS {
+                    return Err(format!(
+                        "At most {} notes can be changed at once",
+                        MAX_BULK_NOTE_IDS
+                    ));
+                }
+                let note_ids = note_ids
+                    .iter()
+                    .map(|id| {
+                        Uuid::parse_str(id).map_err(|_| format!("'{}' is not a valid note ID", id))
+                    })
+                    .collect::<Result<BTreeSet<_>, _>>()?;
+                Ok(Self::NoteIds(note_ids.into_iter().collect()))
+            }
+            (None, Some(params)) => NoteFilter::from_params(params).map(Self::Filter),
+            _ => Err("Select notes with either `note_ids` or `filter`".to_string()),
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct BulkNotesResponse {
+    pub action: String,
+    /// Notes the action was applied to.
+    pub matched_count: usize,
+    /// Notes the action actually changed, e.g. excluding notes that already
+    /// had the tag being added.
+    pub changed_count: usize,
+    /// Requested IDs that are not notes of the user or are in the trash.
+    pub not_found: Vec<String>,
+}
+
+#[derive(thiserror::Error)]
+pub enum BulkNotesError {
+    #[error("Invalid input: {0}")]
+    ValidationError(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for BulkNotesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for BulkNotesError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            BulkNotesError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            BulkNotesError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Applies one action to many notes at once. Either every selected note is
+/// changed or, on error, none is.
+#[tracing::instrument(
+    name = "Bulk change notes",
+    skip(user, request, pool),
+    fields(user_id = %user.user_id, action = %request.action)
+)]
+pub async fn bulk_notes(
+    user: AuthenticatedUser,
+    request: web::Json<BulkNotesRequest>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, BulkNotesError> {
+    let action = BulkAction::parse(&request).map_err(BulkNotesError::ValidationError)?;
+    let selection = BulkSelection::parse(&request).map_err(BulkNotesError::ValidationError)?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .map_err(|e| BulkNotesError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    let (note_ids, not_found) = lock_selected_notes(&mut transaction, user.user_id, &selection)
+        .await
+        .map_err(|e| BulkNotesError::UnexpectedError(anyhow::anyhow!(e)))?;
+    let changed_count = apply_action(&mut transaction, user.user_id, &action, &note_ids)
+        .await
+        .map_err(|e| BulkNotesError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| BulkNotesError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    Ok(HttpResponse::Ok().json(BulkNotesResponse {
+        action: action.name().to_string(),
+        matched_count: note_ids.len(),
+        changed_count,
+        not_found: not_found.iter().map(Uuid::to_string).collect(),
+    }))
+}
+
+/// Locks the selected notes of `user_id`, returning their IDs along with the
+/// requested IDs that do not belong to the user.
+async fn lock_selected_notes(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    selection: &BulkSelection,
+) -> Result<(Vec<Uuid>, Vec<Uuid>), sqlx::Error> {
+    match selection {
+        BulkSelection::Filter(filter) => {
+            let note_ids = lock_matching_notes(transaction, user_id, filter).await?;
+            Ok((note_ids, Vec::new()))
+        }
+        BulkSelection::NoteIds(requested) => {
+            let owned: HashSet<Uuid> = sqlx::query_scalar!(
+                r#"
+                SELECT note_id
+                FROM notes
+                WHERE note_id = ANY($1) AND user_id = $2 AND deleted_at IS NULL
+                ORDER BY note_id
+                FOR UPDATE
+                "#,
+                requested,
+                user_id
+            )
+            .fetch_all(&mut **transaction)
+            .await?
+            .into_iter()
+            .collect();
+
+            let (note_ids, not_found) =
+                requested.iter().copied().partition(|id| owned.contains(id));
+            Ok((note_ids, not_found))
+        }
+    }
+}
+
+/// Returns how many notes were changed.
+#[tracing::instrument(name = "Apply bulk action", skip(transaction, note_ids))]
+async fn apply_action(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    action: &BulkAction,
+    note_ids: &[Uuid],
+) -> Result<usize, anyhow::Error> {
+    if note_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let changed: HashSet<Uuid> = match action {
+        BulkAction::Delete => {
+            let deleted = sqlx::query!(
+                r#"
+                UPDATE notes
+                SET deleted_at = NOW()
+                WHERE note_id = ANY($1) AND user_id = $2
+                "#,
+                note_ids,
+                user_id
+            )
+            .execute(&mut **transaction)
+            .await?
+            .rows_affected();
+            return Ok(deleted as usize);
+        }
+        BulkAction::AddTag(name) => {
+            let tag_ids =
+                get_or_create_tags(transaction, user_id, std::slice::from_ref(name)).await?;
+            let tag_id = tag_ids
+                .get(name.as_ref())
+                .copied()
+                .ok_or_else(|| anyhow::anyhow!("Tag '{}' was not created", name.as_ref()))?;
+
+            sqlx::query_scalar!(
+                r#"
+                INSERT INTO note_tags (note_id, tag_id)
+                SELECT UNNEST($1::uuid[]), $2
+                ON CONFLICT DO NOTHING
+                RETURNING note_id
+                "#,
+                note_ids,
+                tag_id
+            )
+            .fetch_all(&mut **transaction)
+            .await?
+            .into_iter()
+            .collect()
+        }
+        BulkAction::RemoveTag(name) => sqlx::query_scalar!(
+            r#"
+            DELETE FROM note_tags nt
+            USING tags t
+            WHERE nt.tag_id = t.tag_id
+                AND t.user_id = $1
+                AND t.name = $2
+                AND nt.note_id = ANY($3)
+            RETURNING nt.note_id
+            "#,
+            user_id,
+            name.as_ref(),
+            note_ids
+        )
+        .fetch_all(&mut **transaction)
+        .await?
+        .into_iter()
+        .collect(),
+        BulkAction::ReplaceTags(names) => {
+            let tag_ids: Vec<Uuid> = get_or_create_tags(transaction, user_id, names)
+                .await?
+                .into_values()
+                .collect();
+
+            let mut changed: HashSet<Uuid> = sqlx::query_scalar!(
+                r#"
+                DELETE FROM note_tags
+                WHERE note_id = ANY($1) AND NOT (tag_id = ANY($2))
+                RETURNING note_id
+                "#,
+                note_ids,
+                &tag_ids
+            )
+            .fetch_all(&mut **transaction)
+            .await?
+            .into_iter()
+            .collect();
+            changed.extend(
+                sqlx::query_scalar!(
+                    r#"
+                    INSERT INTO note_tags (note_id, tag_id)
+                    SELECT n.note_id, t.tag_id
+                    FROM UNNEST($1::uuid[]) AS n(note_id)
+                    CROSS JOIN UNNEST($2::uuid[]) AS t(tag_id)
+                    ON CONFLICT DO NOTHING
+                    RETURNING note_id
+                    "#,
+                    note_ids,
+                    &tag_ids
+                )
+                .fetch_all(&mut **transaction)
+                .await?,
+            );
+            changed
+        }
+    };
+
+    // The tags are part of each note's representation, so the retagged notes
+    // need new ETags
+    let changed: Vec<Uuid> = changed.into_iter().collect();
+    sqlx::query!(
+        "UPDATE notes SET version = version + 1 WHERE note_id = ANY($1)",
+        &changed
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(changed.len())
+}
+
+fn error_chain_fmt(
+    e: &impl std::error::Error,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    writeln!(f, "{}\n", e)?;
+    let mut current = e.source();
+    while let Some(cause) = current {
+        writeln!(f, "Caused by:\n\t{}", cause)?;
+        current = cause.source();
+    }
+    Ok(())
+}
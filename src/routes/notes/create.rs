@@ -1,5 +1,10 @@
+use super::etag::note_etag;
+use super::links::save_links;
 use crate::authentication::AuthenticatedUser;
 use crate::domain::NewNote;
+use crate::routes::notebooks::notebook_belongs_to_user;
+use crate::routes::tags::set_note_tags;
+use actix_web::http::header::ETag;
 use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
 use sqlx::PgPool;
 use uuid::Uuid;
@@ -8,6 +13,10 @@ use uuid::Uuid;
 pub struct CreateNoteRequest {
     pub title: String,
     pub content: String,
+    pub notebook_id: Option<String>,
+    /// Tag names. Tags that don't exist yet are created.
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(serde::Serialize)]
@@ -15,6 +24,8 @@ pub struct CreateNoteResponse {
     pub note_id: String,
     pub title: String,
     pub content: String,
+    pub notebook_id: Option<String>,
+    pub tags: Vec<String>,
     pub created_at: String,
 }
 
@@ -22,6 +33,8 @@ pub struct CreateNoteResponse {
 pub enum CreateNoteError {
     #[error("Invalid input: {0}")]
     ValidationError(String),
+    #[error("Notebook not found")]
+    NotebookNotFound,
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -36,6 +49,7 @@ impl ResponseError for CreateNoteError {
     fn status_code(&self) -> StatusCode {
         match self {
             CreateNoteError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            CreateNoteError::NotebookNotFound => StatusCode::NOT_FOUND,
             CreateNoteError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -54,39 +68,76 @@ pub async fn create_note(
     request: web::Json<CreateNoteRequest>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, CreateNoteError> {
-    let new_note = NewNote::parse(user.user_id, request.0.title, request.0.content)
-        .map_err(CreateNoteError::ValidationError)?;
+    let request = request.into_inner();
+    let new_note = NewNote::parse(
+        user.user_id,
+        request.title,
+        request.content,
+        request.notebook_id,
+        request.tags,
+    )
+    .map_err(CreateNoteError::ValidationError)?;
 
-    let note_id = insert_note(&pool, &new_note).await?;
+    if let Some(notebook_id) = new_note.notebook_id {
+        let owned = notebook_belongs_to_user(pool.as_ref(), notebook_id, user.user_id)
+            .await
+            .map_err(|e| CreateNoteError::UnexpectedError(anyhow::anyhow!(e)))?;
+        if !owned {
+            return Err(CreateNoteError::NotebookNotFound);
+        }
+    }
+
+    let (note_id, version, tags) = insert_note(&pool, &new_note).await?;
 
     let response = CreateNoteResponse {
         note_id: note_id.to_string(),
         title: new_note.title.to_string(),
         content: new_note.content.to_string(),
+        notebook_id: new_note.notebook_id.map(|id| id.to_string()),
+        tags,
         created_at: chrono::Utc::now().to_rfc3339(),
     };
 
-    Ok(HttpResponse::Created().json(response))
+    Ok(HttpResponse::Created()
+        .insert_header(ETag(note_etag(version)))
+        .json(response))
 }
 
 #[tracing::instrument(name = "Saving new note to database", skip(pool, new_note))]
-async fn insert_note(pool: &PgPool, new_note: &NewNote) -> Result<Uuid, anyhow::Error> {
+async fn insert_note(
+    pool: &PgPool,
+    new_note: &NewNote,
+) -> Result<(Uuid, i32, Vec<String>), anyhow::Error> {
     let note_id = Uuid::new_v4();
+    let mut transaction = pool.begin().await?;
 
-    sqlx::query!(
+    let row = sqlx::query!(
         r#"
-        INSERT INTO notes (note_id, user_id, title, content)
-        VALUES ($1, $2, $3, $4)
+        INSERT INTO notes (note_id, user_id, title, content, notebook_id)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING version
         "#,
         note_id,
         new_note.user_id,
         new_note.title.as_ref(),
         new_note.content.as_ref(),
+        new_note.notebook_id,
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    save_links(&mut transaction, note_id, &new_note.content.links()).await?;
+    let tags = set_note_tags(
+        &mut transaction,
+        new_note.user_id,
+        note_id,
+        &new_note.tags,
+        true,
     )
-    .execute(pool)
     .await?;
+    transaction.commit().await?;
 
-    Ok(note_id)
+    Ok((note_id, row.version, tags))
 }
 
 fn error_chain_fmt(
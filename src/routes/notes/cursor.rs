@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Position of the last note on a page, used for keyset pagination.
+///
+/// The sort field and order are embedded so a cursor cannot be replayed
+/// against a differently ordered listing.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NoteCursor {
+    pub sort: String,
+    pub order: String,
+    /// Pinned notes are listed ahead of the rest.
+    pub pinned: bool,
+    pub key: String,
+    pub note_id: Uuid,
+}
+
+impl NoteCursor {
+    pub fn encode(&self) -> String {
+        hex::encode(serde_json::to_vec(self).expect("Failed to serialize cursor"))
+    }
+
+    pub fn decode(s: &str) -> Result<NoteCursor, String> {
+        let bytes = hex::decode(s).map_err(|_| "Invalid cursor".to_string())?;
+        let cursor: NoteCursor =
+            serde_json::from_slice(&bytes).map_err(|_| "Invalid cursor".to_string())?;
+        cursor.key()?;
+        Ok(cursor)
+    }
+
+    pub fn key(&self) -> Result<CursorKey, String> {
+        match self.sort.as_str() {
+            "title" => Ok(CursorKey::Title(self.key.clone())),
+            "created_at" | "updated_at" => DateTime::parse_from_rfc3339(&self.key)
+                .map(|t| CursorKey::Timestamp(t.with_timezone(&Utc)))
+                .map_err(|_| "Invalid cursor".to_string()),
+            _ => Err("Invalid cursor".to_string()),
+        }
+    }
+}
+
+/// The typed value of the sort column at the cursor position.
+pub enum CursorKey {
+    Title(String),
+    Timestamp(DateTime<Utc>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NoteCursor;
+    use claims::assert_err;
+    use uuid::Uuid;
+
+    #[test]
+    fn cursor_round_trips() {
+        let cursor = NoteCursor {
+            sort: "title".to_string(),
+            order: "ASC".to_string(),
+            pinned: true,
+            key: "Meeting notes".to_string(),
+            note_id: Uuid::new_v4(),
+        };
+        assert_eq!(NoteCursor::decode(&cursor.encode()), Ok(cursor));
+    }
+
+    #[test]
+    fn cursor_with_malformed_timestamp_is_rejected() {
+        let cursor = NoteCursor {
+            sort: "created_at".to_string(),
+            order: "DESC".to_string(),
+            pinned: false,
+            key: "yesterday".to_string(),
+            note_id: Uuid::new_v4(),
+        };
+        assert_err!(NoteCursor::decode(&cursor.encode()));
+    }
+
+    #[test]
+    fn garbage_cursor_is_rejected() {
+        assert_err!(NoteCursor::decode("not-a-cursor"));
+        assert_err!(NoteCursor::decode(&hex::encode("{}")));
+    }
+}
@@ -1,5 +1,7 @@
+use super::etag::if_match_satisfied;
 use crate::authentication::AuthenticatedUser;
-use actix_web::{web, HttpResponse, ResponseError};
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, ResponseError};
+use actix_web::http::header::IfMatch;
 use actix_web::http::StatusCode;
 use sqlx::PgPool;
 use uuid::Uuid;
@@ -10,6 +12,8 @@ pub enum DeleteNoteError {
     NotFound,
     #[error("Invalid note ID")]
     InvalidId,
+    #[error("Note has been modified since it was last fetched")]
+    PreconditionFailed,
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -25,45 +29,76 @@ impl ResponseError for DeleteNoteError {
         match self {
             DeleteNoteError::NotFound => StatusCode::NOT_FOUND,
             DeleteNoteError::InvalidId => StatusCode::BAD_REQUEST,
+            DeleteNoteError::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
             DeleteNoteError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
 
-#[tracing::instrument(name = "Delete note", skip(user, pool))]
+#[tracing::instrument(name = "Delete note", skip(user, request, pool))]
 pub async fn delete_note(
     user: AuthenticatedUser,
+    request: HttpRequest,
     note_id: web::Path<String>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, DeleteNoteError> {
     let note_id = Uuid::parse_str(&note_id).map_err(|_| DeleteNoteError::InvalidId)?;
 
-    delete_note_from_db(&pool, note_id, user.user_id).await?;
+    let if_match = request.get_header::<IfMatch>();
+    delete_note_from_db(&pool, note_id, user.user_id, if_match.as_ref()).await?;
 
     Ok(HttpResponse::NoContent().finish())
 }
 
-#[tracing::instrument(name = "Delete note from database", skip(pool))]
+#[tracing::instrument(name = "Move note to trash", skip(pool, if_match))]
 async fn delete_note_from_db(
     pool: &PgPool,
     note_id: Uuid,
     user_id: Uuid,
+    if_match: Option<&IfMatch>,
 ) -> Result<(), DeleteNoteError> {
-    let result = sqlx::query!(
+    let mut transaction = pool
+        .begin()
+        .await
+        .map_err(|e| DeleteNoteError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    let existing = sqlx::query!(
         r#"
-        DELETE FROM notes
-        WHERE note_id = $1 AND user_id = $2
+        SELECT version
+        FROM notes
+        WHERE note_id = $1 AND user_id = $2 AND deleted_at IS NULL
+        FOR UPDATE
         "#,
         note_id,
         user_id
     )
-    .execute(pool)
-    .await?;
+    .fetch_optional(&mut *transaction)
+    .await
+    .map_err(|e| DeleteNoteError::UnexpectedError(anyhow::anyhow!(e)))?
+    .ok_or(DeleteNoteError::NotFound)?;
 
-    if result.rows_affected() == 0 {
-        return Err(DeleteNoteError::NotFound);
+    if !if_match_satisfied(if_match, existing.version) {
+        return Err(DeleteNoteError::PreconditionFailed);
     }
 
+    sqlx::query!(
+        r#"
+        UPDATE notes
+        SET deleted_at = NOW()
+        WHERE note_id = $1 AND user_id = $2
+        "#,
+        note_id,
+        user_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| DeleteNoteError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| DeleteNoteError::UnexpectedError(anyhow::anyhow!(e)))?;
+
     Ok(())
 }
 
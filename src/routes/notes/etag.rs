@@ -0,0 +1,65 @@
+use actix_web::http::header::{EntityTag, IfMatch, IfNoneMatch};
+
+pub fn note_etag(version: i32) -> EntityTag {
+    EntityTag::new_strong(version.to_string())
+}
+
+/// A missing `If-Match` header places no precondition on the write.
+pub fn if_match_satisfied(if_match: Option<&IfMatch>, version: i32) -> bool {
+    match if_match {
+        None | Some(IfMatch::Any) => true,
+        Some(IfMatch::Items(tags)) => {
+            let current = note_etag(version);
+            tags.iter().any(|tag| tag.strong_eq(&current))
+        }
+    }
+}
+
+/// Returns `true` when the client's cached copy is still `current`.
+pub fn if_none_match_hit(if_none_match: Option<&IfNoneMatch>, current: &EntityTag) -> bool {
+    match if_none_match {
+        None => false,
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(current)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_if_match_is_satisfied() {
+        assert!(if_match_satisfied(None, 3));
+    }
+
+    #[test]
+    fn if_match_with_current_version_is_satisfied() {
+        let if_match = IfMatch::Items(vec![EntityTag::new_strong("3".to_string())]);
+        assert!(if_match_satisfied(Some(&if_match), 3));
+    }
+
+    #[test]
+    fn if_match_with_stale_version_is_not_satisfied() {
+        let if_match = IfMatch::Items(vec![EntityTag::new_strong("2".to_string())]);
+        assert!(!if_match_satisfied(Some(&if_match), 3));
+    }
+
+    #[test]
+    fn weak_etags_never_satisfy_if_match() {
+        let if_match = IfMatch::Items(vec![EntityTag::new_weak("3".to_string())]);
+        assert!(!if_match_satisfied(Some(&if_match), 3));
+    }
+
+    #[test]
+    fn if_none_match_with_current_version_is_a_hit() {
+        let if_none_match = IfNoneMatch::Items(vec![EntityTag::new_weak("3".to_string())]);
+        assert!(if_none_match_hit(Some(&if_none_match), &note_etag(3)));
+    }
+
+    #[test]
+    fn if_none_match_with_stale_version_is_a_miss() {
+        let if_none_match = IfNoneMatch::Items(vec![EntityTag::new_strong("2".to_string())]);
+        assert!(!if_none_match_hit(Some(&if_none_match), &note_etag(3)));
+    }
+}
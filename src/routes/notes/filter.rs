@@ -0,0 +1,276 @@
+use super::list::NoteQueryParams;
+use super::search_query::{MatchMode, SearchQuery};
+use crate::domain::TagName;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Filters shared by the count and page queries of a notes listing.
+#[derive(Debug, Default)]
+pub struct NoteFilter {
+    pub search: Option<SearchQuery>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub tags: TagFilter,
+    pub notebook: Option<NotebookFilter>,
+    pub archived: ArchivedFilter,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct TagFilter {
+    /// Tag names a note must carry, combined according to `mode`.
+    pub include: Vec<String>,
+    pub mode: TagMode,
+    /// Tag names a note must not carry.
+    pub exclude: Vec<String>,
+    /// Only match notes without any tag.
+    pub untagged: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TagMode {
+    #[default]
+    All,
+    Any,
+}
+
+/// Whether archived notes are listed.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ArchivedFilter {
+    Include,
+    #[default]
+    Exclude,
+    Only,
+}
+
+impl ArchivedFilter {
+    pub fn parse(s: &str) -> Result<ArchivedFilter, String> {
+        match s.to_lowercase().as_str() {
+            "include" => Ok(Self::Include),
+            "exclude" => Ok(Self::Exclude),
+            "only" => Ok(Self::Only),
+            other => Err(format!(
+                "'{}' is not a valid archived filter. Use either `include`, `exclude` or `only`",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotebookFilter {
+    /// Notes that are not filed in any notebook.
+    Unfiled,
+    /// Notes in this notebook or any notebook nested below it.
+    Within(Uuid),
+}
+
+impl NotebookFilter {
+    pub fn parse(s: &str) -> Result<NotebookFilter, String> {
+        match s.trim() {
+            "none" => Ok(Self::Unfiled),
+            id => Uuid::parse_str(id).map(Self::Within).map_err(|_| {
+                format!(
+                    "'{}' is not a valid notebook. Use a notebook ID or `none`",
+                    id
+                )
+            }),
+        }
+    }
+}
+
+impl TagMode {
+    pub fn parse(s: &str) -> Result<TagMode, String> {
+        match s.to_lowercase().as_str() {
+            "all" => Ok(Self::All),
+            "any" => Ok(Self::Any),
+            other => Err(format!(
+                "'{}' is not a valid tag mode. Use either `all` or `any`",
+                other
+            )),
+        }
+    }
+}
+
+impl NoteFilter {
+    pub fn from_params(params: &NoteQueryParams) -> Result<NoteFilter, String> {
+        let mode = params
+            .match_mode
+            .as_deref()
+            .map(MatchMode::parse)
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(Self {
+            search: params
+                .search
+                .as_deref()
+                .filter(|s| !s.trim().is_empty())
+                .map(|s| SearchQuery::parse(s, mode))
+                .transpose()?,
+            from: params.from,
+            to: params.to,
+            tags: TagFilter::parse(
+                params.tag.as_deref(),
+                params.tags.as_deref(),
+                params.tag_mode.as_deref(),
+                params.exclude_tags.as_deref(),
+                params.untagged.unwrap_or(false),
+            )?,
+            notebook: params
+                .notebook
+                .as_deref()
+                .map(NotebookFilter::parse)
+                .transpose()?,
+            archived: params
+                .archived
+                .as_deref()
+                .map(ArchivedFilter::parse)
+                .transpose()?
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl NoteFilter {
+    /// The free-text part of the search, if any, in `websearch_to_tsquery` syntax.
+    pub fn search_text(&self) -> Option<&String> {
+        self.search.as_ref().and_then(|search| search.text.as_ref())
+    }
+}
+
+impl TagFilter {
+    /// `tag` is the legacy single-tag filter. It names one tag exactly, as it
+    /// always has, and is added to the names from `tags`.
+    pub fn parse(
+        tag: Option<&str>,
+        tags: Option<&str>,
+        mode: Option<&str>,
+        exclude: Option<&str>,
+        untagged: bool,
+    ) -> Result<TagFilter, String> {
+        let mut include = parse_tag_list(tags.unwrap_or_default())?;
+        if let Some(tag) = tag {
+            include.push(tag.to_string());
+            include.sort();
+            include.dedup();
+        }
+        let exclude = parse_tag_list(exclude.unwrap_or_default())?;
+        let mode = mode.map(TagMode::parse).transpose()?.unwrap_or_default();
+
+        if untagged && !include.is_empty() {
+            return Err("`untagged` cannot be combined with `tags`".to_string());
+        }
+        if let Some(name) = include.iter().find(|name| exclude.contains(name)) {
+            return Err(format!("Tag '{}' is both included and excluded", name));
+        }
+
+        Ok(Self {
+            include,
+            mode,
+            exclude,
+            untagged,
+        })
+    }
+}
+
+/// Parses a comma-separated list of tag names, ignoring empty entries.
+fn parse_tag_list(s: &str) -> Result<Vec<String>, String> {
+    let mut names = s
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| TagName::parse(name.to_string()).map(|tag| tag.as_ref().to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn tag_lists_are_normalised() {
+        let filter = TagFilter::parse(None, Some("Work, home,,work"), None, None, false).unwrap();
+        assert_eq!(filter.include, vec!["home".to_string(), "work".to_string()]);
+        assert_eq!(filter.mode, TagMode::All);
+    }
+
+    #[test]
+    fn legacy_tag_is_folded_into_tags() {
+        let filter =
+            TagFilter::parse(Some("urgent"), Some("work"), Some("any"), None, false).unwrap();
+        assert_eq!(
+            filter.include,
+            vec!["urgent".to_string(), "work".to_string()]
+        );
+        assert_eq!(filter.mode, TagMode::Any);
+    }
+
+    #[test]
+    fn legacy_tag_is_matched_as_given() {
+        let filter = TagFilter::parse(Some("Work,home"), None, None, None, false).unwrap();
+        assert_eq!(filter.include, vec!["Work,home".to_string()]);
+    }
+
+    #[test]
+    fn invalid_tag_names_are_rejected() {
+        assert_err!(TagFilter::parse(
+            None,
+            Some("not valid!"),
+            None,
+            None,
+            false
+        ));
+        assert_err!(TagFilter::parse(None, None, None, Some("a/b"), false));
+    }
+
+    #[test]
+    fn unknown_tag_mode_is_rejected() {
+        assert_err!(TagFilter::parse(
+            None,
+            Some("work"),
+            Some("some"),
+            None,
+            false
+        ));
+    }
+
+    #[test]
+    fn untagged_with_tags_is_rejected() {
+        assert_err!(TagFilter::parse(None, Some("work"), None, None, true));
+        assert_ok!(TagFilter::parse(None, None, None, Some("work"), true));
+    }
+
+    #[test]
+    fn notebook_filter_accepts_ids_and_none() {
+        assert_eq!(NotebookFilter::parse("none"), Ok(NotebookFilter::Unfiled));
+        let id = Uuid::new_v4();
+        assert_eq!(
+            NotebookFilter::parse(&id.to_string()),
+            Ok(NotebookFilter::Within(id))
+        );
+        assert_err!(NotebookFilter::parse("inbox"));
+    }
+
+    #[test]
+    fn archived_notes_are_excluded_by_default() {
+        let filter = NoteFilter::from_params(&NoteQueryParams::default()).unwrap();
+        assert_eq!(filter.archived, ArchivedFilter::Exclude);
+        assert_eq!(ArchivedFilter::parse("Only"), Ok(ArchivedFilter::Only));
+        assert_err!(ArchivedFilter::parse("hide"));
+    }
+
+    #[test]
+    fn including_and_excluding_the_same_tag_is_rejected() {
+        assert_err!(TagFilter::parse(
+            None,
+            Some("work"),
+            None,
+            Some("work"),
+            false
+        ));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::authentication::AuthenticatedUser;
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy)]
+enum NoteFlag {
+    /// Listed ahead of every other note.
+    Pinned,
+    /// Hidden from listings unless asked for.
+    Archived,
+}
+
+#[derive(thiserror::Error)]
+pub enum NoteFlagError {
+    #[error("Note not found")]
+    NotFound,
+    #[error("Invalid note ID")]
+    InvalidId,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for NoteFlagError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for NoteFlagError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            NoteFlagError::NotFound => StatusCode::NOT_FOUND,
+            NoteFlagError::InvalidId => StatusCode::BAD_REQUEST,
+            NoteFlagError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(name = "Pin note", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn pin_note(
+    user: AuthenticatedUser,
+    note_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, NoteFlagError> {
+    set_note_flag(&pool, &note_id, user.user_id, NoteFlag::Pinned, true).await
+}
+
+#[tracing::instrument(name = "Unpin note", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn unpin_note(
+    user: AuthenticatedUser,
+    note_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, NoteFlagError> {
+    set_note_flag(&pool, &note_id, user.user_id, NoteFlag::Pinned, false).await
+}
+
+#[tracing::instrument(name = "Archive note", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn archive_note(
+    user: AuthenticatedUser,
+    note_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, NoteFlagError> {
+    set_note_flag(&pool, &note_id, user.user_id, NoteFlag::Archived, true).await
+}
+
+#[tracing::instrument(name = "Unarchive note", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn unarchive_note(
+    user: AuthenticatedUser,
+    note_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, NoteFlagError> {
+    set_note_flag(&pool, &note_id, user.user_id, NoteFlag::Archived, false).await
+}
+
+#[tracing::instrument(name = "Set note flag in database", skip(pool))]
+async fn set_note_flag(
+    pool: &PgPool,
+    note_id: &str,
+    user_id: Uuid,
+    flag: NoteFlag,
+    value: bool,
+) -> Result<HttpResponse, NoteFlagError> {
+    let note_id = Uuid::parse_str(note_id).map_err(|_| NoteFlagError::InvalidId)?;
+
+    // The flags are part of the note representation, so bump the version for
+    // ETags, but leave updated_at alone as the text did not change
+    let result = match flag {
+        NoteFlag::Pinned => {
+            sqlx::query!(
+                r#"
+                UPDATE notes
+                SET pinned = $1, version = version + 1
+                WHERE note_id = $2 AND user_id = $3 AND deleted_at IS NULL
+                "#,
+                value,
+                note_id,
+                user_id
+            )
+            .execute(pool)
+            .await
+        }
+        NoteFlag::Archived => {
+            sqlx::query!(
+                r#"
+                UPDATE notes
+                SET archived = $1, version = version + 1
+                WHERE note_id = $2 AND user_id = $3 AND deleted_at IS NULL
+                "#,
+                value,
+                note_id,
+                user_id
+            )
+            .execute(pool)
+            .await
+        }
+    }
+    .map_err(|e| NoteFlagError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(NoteFlagError::NotFound);
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+fn error_chain_fmt(
+    e: &impl std::error::Error,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    writeln!(f, "{}\n", e)?;
+    let mut current = e.source();
+    while let Some(cause) = current {
+        writeln!(f, "Caused by:\n\t{}", cause)?;
+        current = cause.source();
+    }
+    Ok(())
+}
@@ -1,5 +1,8 @@
+use super::etag::if_none_match_hit;
+use super::render::{render_html, render_plain_text, NoteRepresentation};
 use crate::authentication::AuthenticatedUser;
-use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use actix_web::http::header::{Accept, ETag, IfNoneMatch, VARY};
+use actix_web::{http::StatusCode, web, HttpMessage, HttpRequest, HttpResponse, ResponseError};
 use sqlx::PgPool;
 use uuid::Uuid;
 
@@ -8,8 +11,13 @@ pub struct NoteResponse {
     pub note_id: String,
     pub title: String,
     pub content: String,
+    pub notebook_id: Option<String>,
+    pub pinned: bool,
+    pub archived: bool,
     pub created_at: String,
     pub updated_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
 }
 
 #[derive(thiserror::Error)]
@@ -18,7 +26,12 @@ pub enum GetNoteError {
     NotFound,
     #[error("Invalid note ID")]
     InvalidId,
-    #[error("transparent")]
+    #[error(
+        "None of the requested media types are available. \
+         Use application/json, text/html, text/markdown or text/plain"
+    )]
+    NotAcceptable,
+    #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
 
@@ -33,22 +46,50 @@ impl ResponseError for GetNoteError {
         match self {
             GetNoteError::NotFound => StatusCode::NOT_FOUND,
             GetNoteError::InvalidId => StatusCode::BAD_REQUEST,
+            GetNoteError::NotAcceptable => StatusCode::NOT_ACCEPTABLE,
             GetNoteError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
 
-#[tracing::instrument(name = "Get note", skip(user, pool))]
+#[tracing::instrument(name = "Get note", skip(user, request, pool))]
 pub async fn get_note(
     user: AuthenticatedUser,
+    request: HttpRequest,
     note_id: web::Path<String>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, GetNoteError> {
     let note_id = Uuid::parse_str(&note_id).map_err(|_| GetNoteError::InvalidId)?;
+    let representation = NoteRepresentation::negotiate(request.get_header::<Accept>().as_ref())
+        .ok_or(GetNoteError::NotAcceptable)?;
 
-    let note = fetch_note(&pool, note_id, user.user_id).await?;
+    let (note, version) = fetch_note(&pool, note_id, user.user_id).await?;
 
-    Ok(HttpResponse::Ok().json(note))
+    let etag = representation.etag(version);
+    if if_none_match_hit(request.get_header::<IfNoneMatch>().as_ref(), &etag) {
+        return Ok(HttpResponse::NotModified()
+            .insert_header(ETag(etag))
+            .insert_header((VARY, "Accept"))
+            .finish());
+    }
+
+    let mut response = HttpResponse::Ok();
+    response
+        .insert_header(ETag(etag))
+        .insert_header((VARY, "Accept"));
+
+    Ok(match representation {
+        NoteRepresentation::Json => response.json(note),
+        NoteRepresentation::Html => response
+            .content_type(representation.content_type())
+            .body(render_html(&note.content)),
+        NoteRepresentation::Markdown => response
+            .content_type(representation.content_type())
+            .body(note.content),
+        NoteRepresentation::PlainText => response
+            .content_type(representation.content_type())
+            .body(render_plain_text(&note.content)),
+    })
 }
 
 #[tracing::instrument(name = "Fetch note from database", skip(pool))]
@@ -56,12 +97,20 @@ async fn fetch_note(
     pool: &PgPool,
     note_id: Uuid,
     user_id: Uuid,
-) -> Result<NoteResponse, GetNoteError> {
+) -> Result<(NoteResponse, i32), GetNoteError> {
     let row = sqlx::query!(
         r#"
-        SELECT note_id, title, content, created_at, updated_at
-        FROM notes
-        WHERE note_id = $1 AND user_id = $2
+        SELECT n.note_id, n.title, n.content, n.notebook_id, n.pinned, n.archived,
+            n.created_at, n.updated_at, n.version,
+            ARRAY(
+                SELECT t.name
+                FROM note_tags nt
+                JOIN tags t ON nt.tag_id = t.tag_id
+                WHERE nt.note_id = n.note_id
+                ORDER BY t.name
+            ) AS "tags!"
+        FROM notes n
+        WHERE n.note_id = $1 AND n.user_id = $2 AND n.deleted_at IS NULL
         "#,
         note_id,
         user_id
@@ -70,13 +119,24 @@ async fn fetch_note(
     .await?
     .ok_or(GetNoteError::NotFound)?;
 
-    Ok(NoteResponse {
-        note_id: row.note_id.to_string(),
-        title: row.title,
-        content: row.content,
-        created_at: row.created_at.to_rfc3339(),
-        updated_at: row.updated_at.to_rfc3339(),
-    })
+    Ok((
+        NoteResponse {
+            note_id: row.note_id.to_string(),
+            title: row.title,
+            content: row.content,
+            notebook_id: row.notebook_id.map(|id| id.to_string()),
+            pinned: row.pinned,
+            archived: row.archived,
+            created_at: row.created_at.to_rfc3339(),
+            updated_at: row.updated_at.to_rfc3339(),
+            tags: if row.tags.is_empty() {
+                None
+            } else {
+                Some(row.tags)
+            },
+        },
+        row.version,
+    ))
 }
 
 fn error_chain_fmt(
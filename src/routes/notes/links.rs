@@ -0,0 +1,206 @@
+use crate::authentication::AuthenticatedUser;
+use crate::domain::NoteLink;
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+#[derive(serde::Serialize)]
+pub struct LinkedNote {
+    pub note_id: String,
+    pub title: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct NoteLinksResponse {
+    /// Notes the links resolve to. A title link resolves to every note with
+    /// that title.
+    pub links: Vec<LinkedNote>,
+    /// Links whose target does not exist, as written between the brackets.
+    pub dangling: Vec<String>,
+}
+
+#[derive(thiserror::Error)]
+pub enum NoteLinkError {
+    #[error("Note not found")]
+    NotFound,
+    #[error("Invalid note ID")]
+    InvalidId,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for NoteLinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for NoteLinkError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            NoteLinkError::NotFound => StatusCode::NOT_FOUND,
+            NoteLinkError::InvalidId => StatusCode::BAD_REQUEST,
+            NoteLinkError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(name = "List note links", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn list_note_links(
+    user: AuthenticatedUser,
+    note_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, NoteLinkError> {
+    let note_id = Uuid::parse_str(&note_id).map_err(|_| NoteLinkError::InvalidId)?;
+    fetch_note_title(&pool, note_id, user.user_id).await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT l.target_note_id, l.target_title,
+               t.note_id AS "resolved_id?", t.title AS "resolved_title?"
+        FROM note_links l
+        LEFT JOIN notes t
+            ON t.user_id = $2 AND t.deleted_at IS NULL
+            AND (t.note_id = l.target_note_id OR lower(t.title) = lower(l.target_title))
+        WHERE l.source_note_id = $1
+        ORDER BY t.title, t.note_id, l.target_title
+        "#,
+        note_id,
+        user.user_id
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| NoteLinkError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    let mut response = NoteLinksResponse {
+        links: Vec::new(),
+        dangling: Vec::new(),
+    };
+    for row in rows {
+        match (row.resolved_id, row.resolved_title) {
+            (Some(id), Some(title)) => {
+                let id = id.to_string();
+                if !response.links.iter().any(|link| link.note_id == id) {
+                    response.links.push(LinkedNote { note_id: id, title });
+                }
+            }
+            _ => {
+                let link = match (row.target_note_id, row.target_title) {
+                    (Some(id), _) => NoteLink::Id(id),
+                    (None, title) => NoteLink::Title(title.unwrap_or_default()),
+                };
+                response.dangling.push(link.to_string());
+            }
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[tracing::instrument(name = "List note backlinks", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn list_backlinks(
+    user: AuthenticatedUser,
+    note_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, NoteLinkError> {
+    let note_id = Uuid::parse_str(&note_id).map_err(|_| NoteLinkError::InvalidId)?;
+    let title = fetch_note_title(&pool, note_id, user.user_id).await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT DISTINCT s.note_id, s.title
+        FROM note_links l
+        JOIN notes s ON s.note_id = l.source_note_id
+        WHERE s.user_id = $2 AND s.deleted_at IS NULL
+            AND (l.target_note_id = $1 OR lower(l.target_title) = lower($3))
+        ORDER BY s.title, s.note_id
+        "#,
+        note_id,
+        user.user_id,
+        title
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| NoteLinkError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    let backlinks: Vec<LinkedNote> = rows
+        .into_iter()
+        .map(|r| LinkedNote {
+            note_id: r.note_id.to_string(),
+            title: r.title,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(backlinks))
+}
+
+/// Replaces the stored links of a note with `links`. Called whenever the
+/// content of a note is written.
+#[tracing::instrument(name = "Save note links", skip(transaction, links))]
+pub(crate) async fn save_links(
+    transaction: &mut Transaction<'_, Postgres>,
+    note_id: Uuid,
+    links: &[NoteLink],
+) -> Result<(), anyhow::Error> {
+    sqlx::query!("DELETE FROM note_links WHERE source_note_id = $1", note_id)
+        .execute(&mut **transaction)
+        .await?;
+
+    let (ids, titles): (Vec<Option<Uuid>>, Vec<Option<String>>) = links
+        .iter()
+        .map(|link| match link {
+            NoteLink::Id(id) => (Some(*id), None),
+            NoteLink::Title(title) => (None, Some(title.clone())),
+        })
+        .unzip();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO note_links (source_note_id, target_note_id, target_title)
+        SELECT $1, target_note_id, target_title
+        FROM UNNEST($2::uuid[], $3::text[]) AS l(target_note_id, target_title)
+        "#,
+        note_id,
+        &ids as &[Option<Uuid>],
+        &titles as &[Option<String>]
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+}
+
+async fn fetch_note_title(
+    pool: &PgPool,
+    note_id: Uuid,
+    user_id: Uuid,
+) -> Result<String, NoteLinkError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT title
+        FROM notes
+        WHERE note_id = $1 AND user_id = $2 AND deleted_at IS NULL
+        "#,
+        note_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| NoteLinkError::UnexpectedError(anyhow::anyhow!(e)))?
+    .ok_or(NoteLinkError::NotFound)?;
+
+    Ok(row.title)
+}
+
+fn error_chain_fmt(
+    e: &impl std::error::Error,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    writeln!(f, "{}\n", e)?;
+    let mut current = e.source();
+    while let Some(cause) = current {
+        writeln!(f, "Caused by:\n\t{}", cause)?;
+        current = cause.source();
+    }
+    Ok(())
+}
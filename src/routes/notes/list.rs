@@ -1,8 +1,13 @@
+use super::cursor::{CursorKey, NoteCursor};
+use super::filter::{ArchivedFilter, NoteFilter, NotebookFilter, TagMode};
+use super::search_query::{MatchMode, SearchQuery};
+use super::snippet::HighlightMarkers;
 use crate::authentication::AuthenticatedUser;
+use crate::utils::e400;
 use actix_web::{web, Error, HttpResponse};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{PgPool, QueryBuilder};
+use sqlx::{PgPool, Postgres, QueryBuilder, Transaction};
 
 #[derive(Deserialize)]
 pub struct NoteQueryParams {
@@ -10,7 +15,13 @@ pub struct NoteQueryParams {
     pub page: i64,
     #[serde(default = "default_page_size")]
     pub page_size: i64,
+    /// Free text plus the `title:`, `tag:`, `before:` and `after:` operators,
+    /// `-term` exclusions and `"quoted phrases"`.
     pub search: Option<String>,
+    /// How the free text of `search` is matched: `fulltext` (the default),
+    /// `fuzzy` or `prefix`.
+    #[serde(rename = "match")]
+    pub match_mode: Option<String>,
     pub from: Option<DateTime<Utc>>,
     pub to: Option<DateTime<Utc>>,
     #[serde(default = "default_sort")]
@@ -18,6 +29,55 @@ pub struct NoteQueryParams {
     #[serde(default = "default_order")]
     pub order: String,
     pub tag: Option<String>,
+    /// Comma-separated tag names, combined according to `tag_mode`.
+    pub tags: Option<String>,
+    /// Either `all` (the default) or `any`.
+    pub tag_mode: Option<String>,
+    /// Comma-separated tag names a note must not carry.
+    pub exclude_tags: Option<String>,
+    pub untagged: Option<bool>,
+    /// A notebook ID, including its sub-notebooks, or `none` for unfiled notes.
+    pub notebook: Option<String>,
+    /// Either `exclude` (the default), `include` or `only`.
+    pub archived: Option<String>,
+    /// Markers wrapped around matches in `snippet`, `<mark>` and `</mark>` by default.
+    pub highlight_start: Option<String>,
+    pub highlight_end: Option<String>,
+    /// Switches to keyset pagination. Pass an empty value for the first page,
+    /// then the `next_cursor` of the previous response.
+    pub cursor: Option<String>,
+}
+
+impl Default for NoteQueryParams {
+    fn default() -> Self {
+        Self {
+            page: default_page(),
+            page_size: default_page_size(),
+            search: None,
+            match_mode: None,
+            from: None,
+            to: None,
+            sort: default_sort(),
+            order: default_order(),
+            tag: None,
+            tags: None,
+            tag_mode: None,
+            exclude_tags: None,
+            untagged: None,
+            notebook: None,
+            archived: None,
+            highlight_start: None,
+            highlight_end: None,
+            cursor: None,
+        }
+    }
+}
+
+impl NoteQueryParams {
+    /// Checks the filters without running a query.
+    pub fn validate_filters(&self) -> Result<(), String> {
+        NoteFilter::from_params(self).map(|_| ())
+    }
 }
 
 fn default_page() -> i64 {
@@ -41,10 +101,19 @@ pub struct NoteListItem {
     pub note_id: String,
     pub title: String,
     pub content: String,
+    pub notebook_id: Option<String>,
+    pub pinned: bool,
+    pub archived: bool,
     pub created_at: String,
     pub updated_at: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<Vec<String>>,
+    /// Highlighted excerpt of the content, only present when searching.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    /// Trigram similarity between 0 and 1, only present for fuzzy matches.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
 }
 
 #[derive(Serialize)]
@@ -55,45 +124,125 @@ pub struct NoteListeResponse {
     pub total_count: i64,
 }
 
+#[derive(Serialize)]
+pub struct NoteCursorResponse {
+    pub notes: Vec<NoteListItem>,
+    pub page_size: i64,
+    pub next_cursor: Option<String>,
+}
+
+/// Tag names of `n`, aggregated in the page query itself so listing a page
+/// costs the same number of round trips regardless of its size.
+const NOTE_TAGS_COLUMN: &str = "ARRAY(SELECT ta.name FROM note_tags nta \
+    JOIN tags ta ON nta.tag_id = ta.tag_id \
+    WHERE nta.note_id = n.note_id ORDER BY ta.name)";
+
+/// Maintained by a trigger with the text search configuration of the owner,
+/// with the title weighted A and the content B.
+const SEARCH_VECTOR: &str = "n.search_vector";
+
+enum Pagination {
+    Offset(i64),
+    /// Keyset pagination, starting after the given cursor if any.
+    Cursor(Option<NoteCursor>),
+}
+
 #[tracing::instrument(name = "List user notes", skip(user, pool, params))]
 pub async fn list_notes(
     user: AuthenticatedUser,
     params: web::Query<NoteQueryParams>,
     pool: web::Data<PgPool>,
+) -> Result<HttpResponse, Error> {
+    query_notes(&pool, user.user_id, &params).await
+}
+
+/// Lists the notes of `user_id` matching `params`. Saved searches run through
+/// here as well, so they always agree with `GET /notes`.
+pub(crate) async fn query_notes(
+    pool: &PgPool,
+    user_id: uuid::Uuid,
+    params: &NoteQueryParams,
 ) -> Result<HttpResponse, Error> {
     let page = params.page.max(1);
     let page_size = params.page_size.clamp(1, 100);
-    let offset = (page - 1) * page_size;
 
-    let sorted_field = validate_sort_field(&params.sort);
+    let filter = NoteFilter::from_params(params).map_err(e400)?;
+    let highlight =
+        HighlightMarkers::parse(params.highlight_start.clone(), params.highlight_end.clone())
+            .map_err(e400)?;
+
+    // Ranking needs a search term, so fall back to the default sort without one
+    let sorted_field = match validate_sort_field(&params.sort) {
+        "relevance" if filter.search_text().is_none() => "created_at",
+        field => field,
+    };
     let order = if params.order.to_lowercase() == "asc" {
         "ASC"
     } else {
         "DESC"
     };
 
-    let total_count = get_notes_count(
-        &pool,
-        user.user_id,
-        &params.search,
-        &params.from,
-        &params.to,
-        &params.tag,
-    )
-    .await
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    let pagination = match params.cursor.as_deref() {
+        None => Pagination::Offset((page - 1) * page_size),
+        Some(_) if sorted_field == "relevance" => {
+            return Err(e400(
+                "Cursor pagination is not supported when sorting by relevance",
+            ));
+        }
+        Some("") => Pagination::Cursor(None),
+        Some(encoded) => {
+            let cursor = NoteCursor::decode(encoded).map_err(e400)?;
+            if cursor.sort != sorted_field || cursor.order != order {
+                return Err(e400("Cursor does not match the requested sort and order"));
+            }
+            Pagination::Cursor(Some(cursor))
+        }
+    };
+
+    if let Pagination::Cursor(_) = pagination {
+        // Fetch one extra row to learn whether another page follows
+        let mut notes = get_notes(
+            pool,
+            user_id,
+            page_size + 1,
+            &pagination,
+            &filter,
+            &highlight,
+            sorted_field,
+            order,
+        )
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+
+        let next_cursor = if notes.len() as i64 > page_size {
+            notes.truncate(page_size as usize);
+            notes
+                .last()
+                .map(|last| cursor_after(last, sorted_field, order).encode())
+        } else {
+            None
+        };
+
+        return Ok(HttpResponse::Ok().json(NoteCursorResponse {
+            notes,
+            page_size,
+            next_cursor,
+        }));
+    }
+
+    let total_count = get_notes_count(pool, user_id, &filter)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
 
     let notes = get_notes(
-        &pool,
-        user.user_id,
+        pool,
+        user_id,
         page_size,
-        offset,
-        &params.search,
-        &params.from,
-        &params.to,
+        &pagination,
+        &filter,
+        &highlight,
         sorted_field,
         order,
-        &params.tag,
     )
     .await
     .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
@@ -111,148 +260,386 @@ fn validate_sort_field(field: &str) -> &str {
         "title" => "title",
         "created_at" => "created_at",
         "updated_at" => "updated_at",
+        "relevance" => "relevance",
         _ => "created_at",
     }
 }
-#[tracing::instrument(name = "Get notes count from database", skip(pool))]
-async fn get_notes_count(
-    pool: &PgPool,
-    user_id: uuid::Uuid,
-    search: &Option<String>,
-    from: &Option<DateTime<Utc>>,
-    to: &Option<DateTime<Utc>>,
-    tag: &Option<String>,
-) -> Result<i64, anyhow::Error> {
-    let mut query = sqlx::QueryBuilder::new("SELECT COUNT(DISTINCT n.note_id) FROM notes n");
 
-    if tag.is_some() {
-        query.push(" LEFT JOIN note_tags nt ON n.note_id = nt.note_id");
-        query.push(" LEFT JOIN tags t ON nt.tag_id = t.tag_id");
+fn cursor_after(note: &NoteListItem, sort_field: &str, order: &str) -> NoteCursor {
+    let key = match sort_field {
+        "title" => note.title.clone(),
+        "updated_at" => note.updated_at.clone(),
+        _ => note.created_at.clone(),
+    };
+
+    NoteCursor {
+        sort: sort_field.to_string(),
+        order: order.to_string(),
+        pinned: note.pinned,
+        key,
+        note_id: note
+            .note_id
+            .parse()
+            .expect("Note IDs are always valid UUIDs"),
     }
+}
+
+/// Pushes the `WHERE` clause shared by the count and page queries.
+fn push_filters<'a>(
+    query: &mut QueryBuilder<'a, Postgres>,
+    user_id: uuid::Uuid,
+    filter: &'a NoteFilter,
+) {
     query.push(" WHERE n.user_id = ");
     query.push_bind(user_id);
+    query.push(" AND n.deleted_at IS NULL");
+
+    match filter.archived {
+        ArchivedFilter::Include => {}
+        ArchivedFilter::Exclude => {
+            query.push(" AND NOT n.archived");
+        }
+        ArchivedFilter::Only => {
+            query.push(" AND n.archived");
+        }
+    }
+
+    if let Some(search) = &filter.search {
+        if let Some(text) = &search.text {
+            match search.mode {
+                MatchMode::Fuzzy => {
+                    // Both operators can use the trigram indexes
+                    query.push(" AND (");
+                    query.push_bind(text);
+                    query.push(" <% n.title OR ");
+                    query.push_bind(text);
+                    query.push(" <% n.content)");
+                }
+                MatchMode::Fulltext | MatchMode::Prefix => {
+                    query.push(format!(" AND {} @@ ", SEARCH_VECTOR));
+                    push_tsquery(query, user_id, search);
+                }
+            }
+        }
 
-    if let Some(search_term) = search {
-        if !search_term.is_empty() {
-            query.push(" AND (");
-            query.push("to_tsvector('english', n.title || ' ' || n.content) @@ plainto_tsquery('english', ");
-            query.push_bind(search_term);
+        // Titles carry weight A in the search vector
+        for title in &search.titles {
+            query.push(format!(
+                " AND ts_filter({}, '{{a}}') @@ plainto_tsquery(",
+                SEARCH_VECTOR
+            ));
+            push_regconfig(query, user_id);
+            query.push(", ");
+            query.push_bind(title);
             query.push(")");
         }
+
+        for tag in &search.tags {
+            query.push(" AND EXISTS (");
+            push_matching_tags(query, "1", std::slice::from_ref(tag));
+            query.push(")");
+        }
+
+        if let Some(before) = &search.before {
+            query.push(" AND n.created_at < ");
+            query.push_bind(before);
+        }
+
+        if let Some(after) = &search.after {
+            query.push(" AND n.created_at > ");
+            query.push_bind(after);
+        }
     }
 
-    if let Some(from_date) = from {
+    if let Some(from_date) = &filter.from {
         query.push(" AND n.created_at >= ");
         query.push_bind(from_date);
     }
 
-    if let Some(to_date) = to {
+    if let Some(to_date) = &filter.to {
         query.push(" AND n.created_at <= ");
         query.push_bind(to_date);
     }
 
-    if let Some(tag_name) = tag {
-        query.push(" AND t.name = ");
-        query.push_bind(tag_name);
+    let tags = &filter.tags;
+    if !tags.include.is_empty() {
+        match tags.mode {
+            TagMode::Any => {
+                query.push(" AND EXISTS (");
+                push_matching_tags(query, "1", &tags.include);
+                query.push(")");
+            }
+            TagMode::All => {
+                query.push(" AND (");
+                push_matching_tags(query, "COUNT(DISTINCT tf.name)", &tags.include);
+                query.push(") = ");
+                query.push_bind(tags.include.len() as i64);
+            }
+        }
+    }
+
+    if !tags.exclude.is_empty() {
+        query.push(" AND NOT EXISTS (");
+        push_matching_tags(query, "1", &tags.exclude);
+        query.push(")");
+    }
+
+    if tags.untagged {
+        query.push(" AND NOT EXISTS (SELECT 1 FROM note_tags fu WHERE fu.note_id = n.note_id)");
+    }
+
+    match filter.notebook {
+        Some(NotebookFilter::Unfiled) => {
+            query.push(" AND n.notebook_id IS NULL");
+        }
+        Some(NotebookFilter::Within(notebook_id)) => {
+            query.push(
+                " AND n.notebook_id IN (WITH RECURSIVE subtree AS (\
+                 SELECT notebook_id FROM notebooks WHERE notebook_id = ",
+            );
+            query.push_bind(notebook_id);
+            query.push(" AND user_id = ");
+            query.push_bind(user_id);
+            query.push(
+                " UNION SELECT nb.notebook_id FROM notebooks nb \
+                 JOIN subtree s ON nb.parent_id = s.notebook_id) \
+                 SELECT notebook_id FROM subtree)",
+            );
+        }
+        None => {}
+    }
+}
+
+/// The `tsquery` for the free text of `search`, matched according to its mode.
+fn push_tsquery<'a>(
+    query: &mut QueryBuilder<'a, Postgres>,
+    user_id: uuid::Uuid,
+    search: &'a SearchQuery,
+) {
+    match search.mode {
+        MatchMode::Fulltext => {
+            query.push("websearch_to_tsquery(");
+            push_regconfig(query, user_id);
+            query.push(", ");
+            query.push_bind(&search.text);
+        }
+        MatchMode::Prefix => {
+            query.push("to_tsquery(");
+            push_regconfig(query, user_id);
+            query.push(", ");
+            query.push_bind(search.prefix_tsquery());
+        }
+        // Only used to highlight snippets, as fuzzy matching is trigram-based
+        MatchMode::Fuzzy => {
+            query.push("plainto_tsquery(");
+            push_regconfig(query, user_id);
+            query.push(", ");
+            query.push_bind(&search.text);
+        }
     }
+    query.push(")");
+}
+
+/// The text search configuration of `user_id`. The subquery does not depend
+/// on the row, so it runs once as part of the query rather than as a
+/// separate round trip.
+fn push_regconfig(query: &mut QueryBuilder<'_, Postgres>, user_id: uuid::Uuid) {
+    query.push("(SELECT search_language FROM users WHERE user_id = ");
+    query.push_bind(user_id);
+    query.push(")::regconfig");
+}
+
+/// Trigram similarity of the free text to the best matching run of words in
+/// the title or content of `n`.
+fn push_similarity<'a>(query: &mut QueryBuilder<'a, Postgres>, text: &'a str) {
+    query.push("GREATEST(word_similarity(");
+    query.push_bind(text);
+    query.push(", n.title), word_similarity(");
+    query.push_bind(text);
+    query.push(", n.content))");
+}
+
+/// Subquery over the tags of `n` whose name is one of `names`.
+fn push_matching_tags<'a>(
+    query: &mut QueryBuilder<'a, Postgres>,
+    select: &str,
+    names: &'a [String],
+) {
+    query.push(format!(
+        "SELECT {} FROM note_tags ft JOIN tags tf ON ft.tag_id = tf.tag_id \
+         WHERE ft.note_id = n.note_id AND tf.name = ANY(",
+        select
+    ));
+    query.push_bind(names);
+    query.push(")");
+}
+
+#[tracing::instrument(name = "Get notes count from database", skip(pool))]
+async fn get_notes_count(
+    pool: &PgPool,
+    user_id: uuid::Uuid,
+    filter: &NoteFilter,
+) -> Result<i64, anyhow::Error> {
+    let mut query = sqlx::QueryBuilder::new("SELECT COUNT(*) FROM notes n");
+
+    push_filters(&mut query, user_id, filter);
 
     let row: (i64,) = query.build_query_as().fetch_one(pool).await?;
     Ok(row.0)
 }
 
-#[tracing::instrument(name = "Get notes from database", skip(pool))]
+#[tracing::instrument(name = "Get notes from database", skip(pool, pagination, highlight))]
+#[allow(clippy::too_many_arguments)]
 async fn get_notes(
     pool: &PgPool,
     user_id: uuid::Uuid,
     limit: i64,
-    offset: i64,
-    search: &Option<String>,
-    from: &Option<DateTime<Utc>>,
-    to: &Option<DateTime<Utc>>,
+    pagination: &Pagination,
+    filter: &NoteFilter,
+    highlight: &HighlightMarkers,
     sort_field: &str,
     order: &str,
-    tag: &Option<String>,
 ) -> Result<Vec<NoteListItem>, anyhow::Error> {
-    let mut query = QueryBuilder::new(
-        "SELECT DISTINCT n.note_id, n.title n.content, n.created_at, n.updated_at FROM notes n",
-    );
-
-    if tag.is_some() {
-        query.push(" LEFT JOIN note_tags nt ON n.note_id = nt.note_id");
-        query.push(" LEFT JOIN tags t ON nt.tag_id = t.tag_id");
-    }
-
-    query.push(" WHERE n.user_id = ");
-    query.push_bind(user_id);
-
-    if let Some(search_term) = search {
-        if !search_term.is_empty() {
-            query.push(" AND (");
-            query.push("to_tsvector('english', n.title || ' '  || n.content) @@ plainto_tsquery('english', ");
-            query.push_bind(search_term);
+    let mut query = QueryBuilder::new(format!(
+        "SELECT n.note_id, n.title, n.content, n.notebook_id, n.pinned, n.archived, \
+         n.created_at, n.updated_at, {}, ",
+        NOTE_TAGS_COLUMN
+    ));
+
+    let search = filter.search.as_ref().filter(|s| s.text.is_some());
+
+    match search {
+        Some(search) => {
+            query.push("ts_headline(");
+            push_regconfig(&mut query, user_id);
+            query.push(", n.content, ");
+            push_tsquery(&mut query, user_id, search);
+            query.push(", ");
+            query.push_bind(highlight.headline_options());
             query.push(")");
         }
+        None => {
+            query.push("NULL::text");
+        }
     }
-
-    if let Some(from_date) = from {
-        query.push(" AND n.created_at >= ");
-        query.push_bind(from_date);
+    query.push(", ");
+    match search {
+        Some(SearchQuery {
+            mode: MatchMode::Fuzzy,
+            text: Some(text),
+            ..
+        }) => push_similarity(&mut query, text),
+        _ => {
+            query.push("NULL::real");
+        }
     }
-
-    if let Some(to_date) = to {
-        query.push(" AND n.created_at <= ");
-        query.push_bind(to_date);
+    query.push(" FROM notes n");
+
+    push_filters(&mut query, user_id, filter);
+
+    if let Pagination::Cursor(Some(cursor)) = pagination {
+        // Pinned notes come first whatever the order, so the flag is compared
+        // on its own. Row comparison keeps the note_id tiebreaker consistent
+        // with ORDER BY.
+        let comparison = if order == "ASC" { ">" } else { "<" };
+        query.push(" AND (n.pinned < ");
+        query.push_bind(cursor.pinned);
+        query.push(" OR (n.pinned = ");
+        query.push_bind(cursor.pinned);
+        query.push(format!(
+            " AND (n.{}, n.note_id) {} (",
+            sort_field, comparison
+        ));
+        match cursor.key().map_err(|e| anyhow::anyhow!(e))? {
+            CursorKey::Title(title) => query.push_bind(title),
+            CursorKey::Timestamp(timestamp) => query.push_bind(timestamp),
+        };
+        query.push(", ");
+        query.push_bind(cursor.note_id);
+        query.push(")))");
     }
 
-    if let Some(tag_name) = tag {
-        query.push(" AND t.name = ");
-        query.push_bind(tag_name);
+    match (sort_field, search) {
+        ("relevance", Some(search)) => {
+            query.push(" ORDER BY n.pinned DESC, ");
+            match (search.mode, &search.text) {
+                (MatchMode::Fuzzy, Some(text)) => push_similarity(&mut query, text),
+                _ => {
+                    query.push(format!("ts_rank_cd({}, ", SEARCH_VECTOR));
+                    push_tsquery(&mut query, user_id, search);
+                    query.push(")");
+                }
+            }
+            query.push(format!(" {}, n.note_id {}", order, order));
+        }
+        _ => {
+            query.push(format!(
+                " ORDER BY n.pinned DESC, n.{} {}, n.note_id {}",
+                sort_field, order, order
+            ));
+        }
     }
 
-    query.push(format!(" ORDER BY n.{} {}", sort_field, order));
-
     query.push(" LIMIT ");
     query.push_bind(limit);
-    query.push(" OFFSET ");
-    query.push_bind(offset);
+
+    if let Pagination::Offset(offset) = pagination {
+        query.push(" OFFSET ");
+        query.push_bind(*offset);
+    }
 
     let rows = query
-        .build_query_as::<(uuid::Uuid, String, String, DateTime<Utc>, DateTime<Utc>)>()
+        .build_query_as::<(
+            uuid::Uuid,
+            String,
+            String,
+            Option<uuid::Uuid>,
+            bool,
+            bool,
+            DateTime<Utc>,
+            DateTime<Utc>,
+            Vec<String>,
+            Option<String>,
+            Option<f32>,
+        )>()
         .fetch_all(pool)
         .await?;
 
-    let mut notes = Vec::new();
-
-    for row in rows {
-        let tags = get_note_tags(pool, row.0).await?;
-
-        notes.push(NoteListItem {
+    let notes = rows
+        .into_iter()
+        .map(|row| NoteListItem {
             note_id: row.0.to_string(),
             title: row.1,
             content: row.2,
-            created_at: row.3.to_rfc3339(),
-            updated_at: row.4.to_rfc3339(),
-            tags: if tags.is_empty() { None } else { Some(tags) },
-        });
-    }
+            notebook_id: row.3.map(|id| id.to_string()),
+            pinned: row.4,
+            archived: row.5,
+            created_at: row.6.to_rfc3339(),
+            updated_at: row.7.to_rfc3339(),
+            tags: if row.8.is_empty() { None } else { Some(row.8) },
+            snippet: row.9,
+            score: row.10,
+        })
+        .collect();
 
     Ok(notes)
 }
 
-#[tracing::instrument(name = "Get tags for note", skip(pool))]
-async fn get_note_tags(pool: &PgPool, note_id: uuid::Uuid) -> Result<Vec<String>, anyhow::Error> {
-    let rows = sqlx::query!(
-        r#"
-        SELECT t.name
-        FROM tags t
-        JOIN note_tags nt ON t.tag_id = nt.tag_id
-        WHERE nt.note_id = $1
-        ORDER BY t.name
-        "#,
-        note_id
-    )
-    .fetch_all(pool)
-    .await?;
+/// Locks every note of `user_id` matching `filter`, regardless of paging, and
+/// returns their IDs.
+#[tracing::instrument(name = "Lock matching notes", skip(transaction))]
+pub(super) async fn lock_matching_notes(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: uuid::Uuid,
+    filter: &NoteFilter,
+) -> Result<Vec<uuid::Uuid>, sqlx::Error> {
+    let mut query = QueryBuilder::new("SELECT n.note_id FROM notes n");
+
+    push_filters(&mut query, user_id, filter);
+    query.push(" ORDER BY n.note_id FOR UPDATE");
 
-    Ok(rows.into_iter().map(|r| r.name).collect())
+    query
+        .build_query_scalar()
+        .fetch_all(&mut **transaction)
+        .await
 }
@@ -1,11 +1,29 @@
+mod attachments;
+mod bulk;
 mod create;
+mod cursor;
 mod delete;
+mod etag;
+mod filter;
+mod flags;
 mod get;
+mod links;
 mod list;
+mod patch;
+mod render;
+mod revisions;
+mod search_query;
+mod snippet;
 mod update;
 
+pub use attachments::*;
+pub use bulk::*;
 pub use create::*;
 pub use delete::*;
+pub use flags::*;
 pub use get::*;
+pub use links::*;
 pub use list::*;
+pub use patch::*;
+pub use revisions::*;
 pub use update::*;
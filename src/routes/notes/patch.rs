@@ -0,0 +1,325 @@
+use super::etag::note_etag;
+use super::update::{apply_update, lock_note, LockedNote, UpdateNoteError};
+use crate::authentication::AuthenticatedUser;
+use crate::domain::UpdateNote;
+use crate::routes::tags::note_tag_names;
+use actix_web::http::header::{ETag, IfMatch};
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// Members of the patched document. Patches can't reach anything else.
+const PATCHABLE_FIELDS: [&str; 3] = ["title", "content", "tags"];
+
+#[derive(Debug)]
+enum NotePatchKind {
+    /// RFC 7396
+    Merge(Value),
+    /// RFC 6902
+    Json(json_patch::Patch),
+}
+
+/// A patch of `{"title": ..., "content": ..., "tags": [...]}`, with tags
+/// sorted by name.
+#[derive(Debug)]
+struct NotePatch {
+    kind: NotePatchKind,
+    /// Members the patch writes to. Only these end up in the update.
+    fields: BTreeSet<String>,
+}
+
+impl NotePatch {
+    /// `None` when the content type is not a supported patch format.
+    fn parse(content_type: &str, body: &[u8]) -> Option<Result<NotePatch, String>> {
+        match content_type {
+            "application/merge-patch+json" => Some(parse_json(body).and_then(Self::merge)),
+            "application/json-patch+json" => Some(parse_json(body).and_then(Self::json)),
+            _ => None,
+        }
+    }
+
+    fn merge(patch: Value) -> Result<NotePatch, String> {
+        let object = patch
+            .as_object()
+            .ok_or_else(|| "A merge patch must be a JSON object".to_string())?;
+        let fields = object
+            .keys()
+            .map(|key| patchable_field(key).map(str::to_string))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            kind: NotePatchKind::Merge(patch),
+            fields,
+        })
+    }
+
+    fn json(patch: Value) -> Result<NotePatch, String> {
+        let operations = patch
+            .as_array()
+            .ok_or_else(|| "A JSON Patch must be an array of operations".to_string())?;
+
+        let mut fields = BTreeSet::new();
+        for operation in operations {
+            let op = operation.get("op").and_then(Value::as_str);
+            let path = operation.get("path").and_then(Value::as_str);
+            let (Some(op), Some(path)) = (op, path) else {
+                return Err("Every operation needs an `op` and a `path`".to_string());
+            };
+            let field = pointer_field(path)?;
+            if op != "test" {
+                fields.insert(field.to_string());
+            }
+
+            if let Some(from) = operation.get("from").and_then(Value::as_str) {
+                let field = pointer_field(from)?;
+                // Moving a value away takes it out of its source
+                if op == "move" {
+                    fields.insert(field.to_string());
+                }
+            }
+        }
+
+        let patch =
+            serde_json::from_value(patch).map_err(|e| format!("Invalid JSON Patch: {}", e))?;
+        Ok(Self {
+            kind: NotePatchKind::Json(patch),
+            fields,
+        })
+    }
+
+    /// Applies the patch to the note, turning the result into an update of
+    /// the members the patch wrote to.
+    fn apply(&self, note: &LockedNote, tags: Vec<String>) -> Result<UpdateNote, UpdateNoteError> {
+        let mut document = serde_json::json!({
+            "title": note.title,
+            "content": note.content,
+            "tags": tags,
+        });
+        match &self.kind {
+            NotePatchKind::Merge(patch) => json_patch::merge(&mut document, patch),
+            NotePatchKind::Json(patch) => json_patch::patch(&mut document, &patch.0)
+                .map_err(|e| UpdateNoteError::UnprocessablePatch(e.to_string()))?,
+        }
+
+        let written = |field: &str| {
+            self.fields
+                .contains(field)
+                .then(|| document.get(field).cloned().unwrap_or(Value::Null))
+        };
+        let title = written("title")
+            .map(|v| string_field("title", v))
+            .transpose();
+        let content = written("content")
+            .map(|v| string_field("content", v))
+            .transpose();
+        let tags = written("tags").map(tags_field).transpose();
+
+        UpdateNote::parse(title?, content?, None, tags?, None)
+            .map_err(UpdateNoteError::ValidationError)
+    }
+}
+
+fn parse_json(body: &[u8]) -> Result<Value, String> {
+    serde_json::from_slice(body).map_err(|e| format!("Invalid JSON: {}", e))
+}
+
+fn patchable_field(name: &str) -> Result<&str, String> {
+    if PATCHABLE_FIELDS.contains(&name) {
+        Ok(name)
+    } else {
+        Err(format!(
+            "'{}' cannot be patched. Only `title`, `content` and `tags` can",
+            name
+        ))
+    }
+}
+
+/// The member of the document a JSON Pointer starts at, e.g. `tags` for
+/// `/tags/0`.
+fn pointer_field(pointer: &str) -> Result<&str, String> {
+    let field = pointer
+        .strip_prefix('/')
+        .map(|rest| rest.split('/').next().unwrap_or_default())
+        .ok_or_else(|| format!("'{}' does not point into the note", pointer))?;
+    patchable_field(field)
+}
+
+fn string_field(name: &str, value: Value) -> Result<String, UpdateNoteError> {
+    match value {
+        Value::String(s) => Ok(s),
+        _ => Err(UpdateNoteError::ValidationError(format!(
+            "`{}` must be a string",
+            name
+        ))),
+    }
+}
+
+/// Removing the tags, or setting them to `null`, leaves the note untagged.
+fn tags_field(value: Value) -> Result<Vec<String>, UpdateNoteError> {
+    let invalid = || UpdateNoteError::ValidationError("`tags` must be a list of names".to_string());
+    match value {
+        Value::Null => Ok(Vec::new()),
+        Value::Array(names) => names
+            .into_iter()
+            .map(|name| match name {
+                Value::String(name) => Ok(name),
+                _ => Err(invalid()),
+            })
+            .collect(),
+        _ => Err(invalid()),
+    }
+}
+
+/// Changes the title, content or tags of a note with either a JSON Merge
+/// Patch (`application/merge-patch+json`) or a JSON Patch
+/// (`application/json-patch+json`) of `{"title", "content", "tags"}`.
+#[tracing::instrument(
+    name = "Patch note",
+    skip(user, request, body, pool),
+    fields(user_id = %user.user_id)
+)]
+pub async fn patch_note(
+    user: AuthenticatedUser,
+    request: HttpRequest,
+    note_id: web::Path<String>,
+    body: web::Bytes,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, UpdateNoteError> {
+    let note_id = Uuid::parse_str(&note_id).map_err(|_| UpdateNoteError::InvalidId)?;
+
+    let patch = NotePatch::parse(request.content_type(), &body)
+        .ok_or(UpdateNoteError::UnsupportedPatchFormat)?
+        .map_err(UpdateNoteError::ValidationError)?;
+
+    let if_match = request.get_header::<IfMatch>();
+    let mut transaction = pool
+        .begin()
+        .await
+        .map_err(|e| UpdateNoteError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    // The patch applies to the note as it is under the lock, so concurrent
+    // writes can't slip in between reading and writing it
+    let existing = lock_note(&mut transaction, note_id, user.user_id, if_match.as_ref()).await?;
+    let tags = note_tag_names(&mut transaction, note_id)
+        .await
+        .map_err(|e| UpdateNoteError::UnexpectedError(anyhow::anyhow!(e)))?;
+    let update = patch.apply(&existing, tags)?;
+    let (updated_note, version) =
+        apply_update(&mut transaction, note_id, user.user_id, &existing, &update).await?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| UpdateNoteError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(note_etag(version)))
+        .json(updated_note))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::NoteTagsUpdate;
+    use claims::{assert_err, assert_matches, assert_none, assert_ok, assert_some};
+
+    fn note() -> LockedNote {
+        LockedNote {
+            title: "Plan".to_string(),
+            content: "Ship it".to_string(),
+            notebook_id: None,
+            version: 1,
+        }
+    }
+
+    fn patch(content_type: &str, body: Value) -> Result<NotePatch, String> {
+        assert_some!(NotePatch::parse(content_type, body.to_string().as_bytes()))
+    }
+
+    fn merge_patch(body: Value) -> Result<NotePatch, String> {
+        patch("application/merge-patch+json", body)
+    }
+
+    fn json_patch(body: Value) -> Result<NotePatch, String> {
+        patch("application/json-patch+json", body)
+    }
+
+    fn tag_names(update: &UpdateNote) -> Vec<&str> {
+        match &update.tags {
+            Some(NoteTagsUpdate::Replace(tags)) => tags.iter().map(|tag| tag.as_ref()).collect(),
+            other => panic!("Expected replaced tags, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn other_content_types_are_not_patches() {
+        assert_none!(NotePatch::parse("application/json", b"{}"));
+    }
+
+    #[test]
+    fn merge_patch_only_updates_the_members_it_sets() {
+        let patch = assert_ok!(merge_patch(serde_json::json!({"title": "New plan"})));
+        let update = assert_ok!(patch.apply(&note(), vec!["work".to_string()]));
+        assert_eq!(update.title.unwrap().as_ref(), "New plan");
+        assert_none!(update.content);
+        assert_none!(update.tags);
+    }
+
+    #[test]
+    fn merge_patch_null_removes_the_tags() {
+        let patch = assert_ok!(merge_patch(serde_json::json!({"tags": null})));
+        let update = assert_ok!(patch.apply(&note(), vec!["work".to_string()]));
+        assert!(tag_names(&update).is_empty());
+    }
+
+    #[test]
+    fn merge_patch_cannot_remove_the_title_or_reach_other_members() {
+        let patch = assert_ok!(merge_patch(serde_json::json!({"title": null})));
+        assert_matches!(
+            patch.apply(&note(), Vec::new()),
+            Err(UpdateNoteError::ValidationError(_))
+        );
+        assert_err!(merge_patch(serde_json::json!({"notebook_id": null})));
+        assert_err!(merge_patch(serde_json::json!(["title"])));
+    }
+
+    #[test]
+    fn json_patch_edits_the_tag_list() {
+        let patch = assert_ok!(json_patch(serde_json::json!([
+            {"op": "test", "path": "/title", "value": "Plan"},
+            {"op": "add", "path": "/tags/-", "value": "urgent"},
+            {"op": "remove", "path": "/tags/0"}
+        ])));
+        let update =
+            assert_ok!(patch.apply(&note(), vec!["ideas".to_string(), "work".to_string()]));
+        assert_none!(&update.title);
+        assert_eq!(tag_names(&update), vec!["work", "urgent"]);
+    }
+
+    #[test]
+    fn json_patch_that_cannot_apply_is_unprocessable() {
+        let patch = assert_ok!(json_patch(serde_json::json!([
+            {"op": "test", "path": "/title", "value": "Other"},
+            {"op": "replace", "path": "/title", "value": "New plan"}
+        ])));
+        assert_matches!(
+            patch.apply(&note(), Vec::new()),
+            Err(UpdateNoteError::UnprocessablePatch(_))
+        );
+    }
+
+    #[test]
+    fn json_patch_paths_must_stay_within_the_note() {
+        for operations in [
+            serde_json::json!([{"op": "remove", "path": "/notebook_id"}]),
+            serde_json::json!([{"op": "replace", "path": "", "value": {}}]),
+            serde_json::json!([{"op": "copy", "from": "/version", "path": "/title"}]),
+            serde_json::json!([{"path": "/title"}]),
+            serde_json::json!({"op": "remove", "path": "/title"}),
+        ] {
+            assert_err!(json_patch(operations));
+        }
+    }
+}
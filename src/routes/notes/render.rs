@@ -0,0 +1,158 @@
+use super::etag::note_etag;
+use actix_web::http::header::{Accept, EntityTag};
+use pulldown_cmark::{html, Event, Parser, TagEnd};
+
+/// Representations of a note that `GET /notes/{note_id}` can serve.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoteRepresentation {
+    Json,
+    /// The content rendered from CommonMark and sanitized.
+    Html,
+    /// The content as written.
+    Markdown,
+    /// The content with all markup stripped.
+    PlainText,
+}
+
+impl NoteRepresentation {
+    /// Picks the representation the client prefers most, defaulting to JSON
+    /// without an `Accept` header. `None` means nothing acceptable is offered.
+    pub fn negotiate(accept: Option<&Accept>) -> Option<NoteRepresentation> {
+        let Some(accept) = accept else {
+            return Some(Self::Json);
+        };
+
+        accept
+            .ranked()
+            .iter()
+            .find_map(|mime| match mime.essence_str() {
+                "application/json" | "application/*" | "*/*" => Some(Self::Json),
+                "text/html" => Some(Self::Html),
+                "text/markdown" => Some(Self::Markdown),
+                "text/plain" | "text/*" => Some(Self::PlainText),
+                _ => None,
+            })
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            NoteRepresentation::Json => "application/json",
+            NoteRepresentation::Html => "text/html; charset=utf-8",
+            NoteRepresentation::Markdown => "text/markdown; charset=utf-8",
+            NoteRepresentation::PlainText => "text/plain; charset=utf-8",
+        }
+    }
+
+    /// Each representation needs its own strong validator. JSON keeps the
+    /// plain version, which is what `If-Match` on writes compares against.
+    pub fn etag(&self, version: i32) -> EntityTag {
+        let suffix = match self {
+            NoteRepresentation::Json => return note_etag(version),
+            NoteRepresentation::Html => "html",
+            NoteRepresentation::Markdown => "markdown",
+            NoteRepresentation::PlainText => "text",
+        };
+        EntityTag::new_strong(format!("{}-{}", version, suffix))
+    }
+}
+
+/// Renders CommonMark to HTML, dropping scripts, event handlers and other
+/// markup that could run in the reader's browser.
+pub fn render_html(markdown: &str) -> String {
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, Parser::new(markdown));
+    ammonia::clean(&unsafe_html)
+}
+
+/// The text of a CommonMark document without any markup, one line per block.
+pub fn render_plain_text(markdown: &str) -> String {
+    let mut text = String::new();
+
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Text(s) | Event::Code(s) => text.push_str(&s),
+            Event::SoftBreak => text.push(' '),
+            Event::HardBreak => text.push('\n'),
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::Item
+                | TagEnd::CodeBlock
+                | TagEnd::TableRow,
+            ) if !text.is_empty() && !text.ends_with('\n') => text.push('\n'),
+            _ => {}
+        }
+    }
+
+    text.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::{Header, QualityItem};
+    use actix_web::test::TestRequest;
+
+    fn negotiate(accept: &str) -> Option<NoteRepresentation> {
+        let request = TestRequest::default()
+            .insert_header(("Accept", accept))
+            .to_http_request();
+        let accept = Accept::parse(&request).unwrap();
+        NoteRepresentation::negotiate(Some(&accept))
+    }
+
+    #[test]
+    fn missing_accept_header_gives_json() {
+        assert_eq!(
+            NoteRepresentation::negotiate(None),
+            Some(NoteRepresentation::Json)
+        );
+    }
+
+    #[test]
+    fn the_preferred_supported_type_wins() {
+        assert_eq!(negotiate("text/html"), Some(NoteRepresentation::Html));
+        assert_eq!(
+            negotiate("text/markdown;q=0.5, text/plain"),
+            Some(NoteRepresentation::PlainText)
+        );
+        assert_eq!(
+            negotiate("image/png, text/markdown;q=0.1"),
+            Some(NoteRepresentation::Markdown)
+        );
+        assert_eq!(negotiate("*/*"), Some(NoteRepresentation::Json));
+    }
+
+    #[test]
+    fn unsupported_types_are_not_acceptable() {
+        assert_eq!(negotiate("image/png"), None);
+        let accept = Accept(vec![QualityItem::max("application/xml".parse().unwrap())]);
+        assert_eq!(NoteRepresentation::negotiate(Some(&accept)), None);
+    }
+
+    #[test]
+    fn markdown_is_rendered_to_html() {
+        let html = render_html("# Plan\n\nShip **it**");
+        assert!(html.contains("<h1>Plan</h1>"), "{}", html);
+        assert!(html.contains("<p>Ship <strong>it</strong></p>"), "{}", html);
+    }
+
+    #[test]
+    fn scripts_and_event_handlers_are_stripped() {
+        let html = render_html(
+            "<script>alert(1)</script>\n\n<img src=\"x.png\" onerror=\"alert(1)\">\n\n[x](javascript:alert(1))",
+        );
+        assert!(!html.contains("script"), "{}", html);
+        assert!(!html.contains("onerror"), "{}", html);
+        assert!(!html.contains("javascript:"), "{}", html);
+        assert!(html.contains("<img src=\"x.png\">"), "{}", html);
+    }
+
+    #[test]
+    fn plain_text_drops_markup() {
+        assert_eq!(
+            render_plain_text("# Plan\n\n- Ship **it**\n- Use `cargo`\n\n<b>done</b>"),
+            "Plan\nShip it\nUse cargo\ndone"
+        );
+    }
+}
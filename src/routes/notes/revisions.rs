@@ -0,0 +1,302 @@
+use super::etag::note_etag;
+use super::links::save_links;
+use super::update::UpdateNoteResponse;
+use crate::authentication::AuthenticatedUser;
+use crate::domain::NoteLink;
+use crate::routes::tags::note_tag_names;
+use actix_web::http::header::ETag;
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+#[derive(serde::Serialize)]
+pub struct RevisionListItem {
+    pub revision_number: i32,
+    pub title: String,
+    pub created_at: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct RevisionResponse {
+    pub note_id: String,
+    pub revision_number: i32,
+    pub title: String,
+    pub content: String,
+    pub created_at: String,
+}
+
+#[derive(thiserror::Error)]
+pub enum RevisionError {
+    #[error("Note not found")]
+    NoteNotFound,
+    #[error("Revision not found")]
+    RevisionNotFound,
+    #[error("Invalid note ID")]
+    InvalidId,
+    #[error("Invalid revision number")]
+    InvalidRevision,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for RevisionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for RevisionError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            RevisionError::NoteNotFound => StatusCode::NOT_FOUND,
+            RevisionError::RevisionNotFound => StatusCode::NOT_FOUND,
+            RevisionError::InvalidId => StatusCode::BAD_REQUEST,
+            RevisionError::InvalidRevision => StatusCode::BAD_REQUEST,
+            RevisionError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(name = "List note revisions", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn list_revisions(
+    user: AuthenticatedUser,
+    note_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, RevisionError> {
+    let note_id = Uuid::parse_str(&note_id).map_err(|_| RevisionError::InvalidId)?;
+
+    verify_note_ownership(&pool, note_id, user.user_id).await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT revision_number, title, created_at
+        FROM note_revisions
+        WHERE note_id = $1
+        ORDER BY revision_number DESC
+        "#,
+        note_id
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| RevisionError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    let revisions: Vec<RevisionListItem> = rows
+        .into_iter()
+        .map(|r| RevisionListItem {
+            revision_number: r.revision_number,
+            title: r.title,
+            created_at: r.created_at.to_rfc3339(),
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(revisions))
+}
+
+#[tracing::instrument(name = "Get note revision", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn get_revision(
+    user: AuthenticatedUser,
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, RevisionError> {
+    let (note_id, revision_number) = parse_revision_path(path.into_inner())?;
+
+    verify_note_ownership(&pool, note_id, user.user_id).await?;
+
+    let revision = fetch_revision(&pool, note_id, revision_number).await?;
+
+    Ok(HttpResponse::Ok().json(revision))
+}
+
+#[tracing::instrument(name = "Restore note revision", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn restore_revision(
+    user: AuthenticatedUser,
+    path: web::Path<(String, String)>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, RevisionError> {
+    let (note_id, revision_number) = parse_revision_path(path.into_inner())?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .map_err(|e| RevisionError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    // Lock the note so concurrent updates cannot interleave with the snapshot
+    let existing = sqlx::query!(
+        r#"
+        SELECT title, content
+        FROM notes
+        WHERE note_id = $1 AND user_id = $2 AND deleted_at IS NULL
+        FOR UPDATE
+        "#,
+        note_id,
+        user.user_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .map_err(|e| RevisionError::UnexpectedError(anyhow::anyhow!(e)))?
+    .ok_or(RevisionError::NoteNotFound)?;
+
+    let revision = sqlx::query!(
+        r#"
+        SELECT title, content
+        FROM note_revisions
+        WHERE note_id = $1 AND revision_number = $2
+        "#,
+        note_id,
+        revision_number
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .map_err(|e| RevisionError::UnexpectedError(anyhow::anyhow!(e)))?
+    .ok_or(RevisionError::RevisionNotFound)?;
+
+    // Restoring is itself an update, so the current text is kept as a revision too
+    save_revision(
+        &mut transaction,
+        note_id,
+        &existing.title,
+        &existing.content,
+    )
+    .await?;
+
+    let row = sqlx::query!(
+        r#"
+        UPDATE notes
+        SET title = $1, content = $2, updated_at = NOW(), version = version + 1
+        WHERE note_id = $3 AND user_id = $4
+        RETURNING note_id, title, content, notebook_id, updated_at, version
+        "#,
+        revision.title,
+        revision.content,
+        note_id,
+        user.user_id
+    )
+    .fetch_one(&mut *transaction)
+    .await
+    .map_err(|e| RevisionError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    save_links(
+        &mut transaction,
+        note_id,
+        &NoteLink::parse_all(&row.content),
+    )
+    .await?;
+
+    let tags = note_tag_names(&mut transaction, note_id)
+        .await
+        .map_err(|e| RevisionError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| RevisionError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(note_etag(row.version)))
+        .json(UpdateNoteResponse {
+            note_id: row.note_id.to_string(),
+            title: row.title,
+            content: row.content,
+            notebook_id: row.notebook_id.map(|id| id.to_string()),
+            tags,
+            updated_at: row.updated_at.to_rfc3339(),
+        }))
+}
+
+/// Stores `title` and `content` as the next revision of the note.
+#[tracing::instrument(name = "Save note revision", skip(transaction, title, content))]
+pub(crate) async fn save_revision(
+    transaction: &mut Transaction<'_, Postgres>,
+    note_id: Uuid,
+    title: &str,
+    content: &str,
+) -> Result<i32, anyhow::Error> {
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO note_revisions (revision_id, note_id, revision_number, title, content)
+        SELECT $1, $2, COALESCE(MAX(revision_number), 0) + 1, $3, $4
+        FROM note_revisions
+        WHERE note_id = $2
+        RETURNING revision_number
+        "#,
+        Uuid::new_v4(),
+        note_id,
+        title,
+        content
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.revision_number)
+}
+
+fn parse_revision_path(
+    (note_id, revision_number): (String, String),
+) -> Result<(Uuid, i32), RevisionError> {
+    let note_id = Uuid::parse_str(&note_id).map_err(|_| RevisionError::InvalidId)?;
+    let revision_number = revision_number
+        .parse::<i32>()
+        .ok()
+        .filter(|n| *n > 0)
+        .ok_or(RevisionError::InvalidRevision)?;
+    Ok((note_id, revision_number))
+}
+
+#[tracing::instrument(name = "Fetch note revision from database", skip(pool))]
+async fn fetch_revision(
+    pool: &PgPool,
+    note_id: Uuid,
+    revision_number: i32,
+) -> Result<RevisionResponse, RevisionError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT note_id, revision_number, title, content, created_at
+        FROM note_revisions
+        WHERE note_id = $1 AND revision_number = $2
+        "#,
+        note_id,
+        revision_number
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| RevisionError::UnexpectedError(anyhow::anyhow!(e)))?
+    .ok_or(RevisionError::RevisionNotFound)?;
+
+    Ok(RevisionResponse {
+        note_id: row.note_id.to_string(),
+        revision_number: row.revision_number,
+        title: row.title,
+        content: row.content,
+        created_at: row.created_at.to_rfc3339(),
+    })
+}
+
+async fn verify_note_ownership(
+    pool: &PgPool,
+    note_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), RevisionError> {
+    sqlx::query!(
+        "SELECT note_id FROM notes WHERE note_id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        note_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| RevisionError::UnexpectedError(anyhow::anyhow!(e)))?
+    .ok_or(RevisionError::NoteNotFound)?;
+    Ok(())
+}
+
+fn error_chain_fmt(
+    e: &impl std::error::Error,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    writeln!(f, "{}\n", e)?;
+    let mut current = e.source();
+    while let Some(cause) = current {
+        writeln!(f, "Caused by:\n\t{}", cause)?;
+        current = cause.source();
+    }
+    Ok(())
+}
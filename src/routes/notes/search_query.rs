@@ -0,0 +1,365 @@
+use crate::domain::TagName;
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// A parsed `search` string such as
+/// `title:meeting tag:work -draft "exact phrase" before:2026-01-01`.
+#[derive(Debug, Default, PartialEq)]
+pub struct SearchQuery {
+    pub mode: MatchMode,
+    /// Free-text part. In `websearch_to_tsquery` syntax for full-text
+    /// matching, plain words otherwise.
+    pub text: Option<String>,
+    /// Terms that must appear in the title.
+    pub titles: Vec<String>,
+    /// Tag names a note must carry.
+    pub tags: Vec<String>,
+    /// Only match notes created strictly before this instant.
+    pub before: Option<DateTime<Utc>>,
+    /// Only match notes created strictly after this instant.
+    pub after: Option<DateTime<Utc>>,
+}
+
+/// How the free-text part of a search is matched against notes.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum MatchMode {
+    /// Stemmed full-text search.
+    #[default]
+    Fulltext,
+    /// Trigram similarity, tolerant of typos.
+    Fuzzy,
+    /// Words match any word they are a prefix of.
+    Prefix,
+}
+
+impl MatchMode {
+    pub fn parse(s: &str) -> Result<MatchMode, String> {
+        match s.to_lowercase().as_str() {
+            "fulltext" => Ok(Self::Fulltext),
+            "fuzzy" => Ok(Self::Fuzzy),
+            "prefix" => Ok(Self::Prefix),
+            other => Err(format!(
+                "'{}' is not a valid match mode. Use either `fulltext`, `fuzzy` or `prefix`",
+                other
+            )),
+        }
+    }
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str, mode: MatchMode) -> Result<SearchQuery, String> {
+        let mut query = SearchQuery {
+            mode,
+            ..Default::default()
+        };
+        let mut text = Vec::new();
+
+        for token in tokenize(input)? {
+            if token.starts_with('"') {
+                unquote(&token, &token)?;
+                require_fulltext(mode, &token)?;
+                text.push(token);
+                continue;
+            }
+
+            if let Some(negated) = token.strip_prefix('-') {
+                if operator(negated).is_some() {
+                    return Err(format!("Search operators cannot be negated in '{}'", token));
+                }
+                if !negated.is_empty() {
+                    if negated.starts_with('"') {
+                        unquote(negated, &token)?;
+                    }
+                    require_fulltext(mode, &token)?;
+                    text.push(token);
+                }
+                continue;
+            }
+
+            let Some((operator, value)) = operator(&token) else {
+                if mode == MatchMode::Prefix && !token.chars().any(char::is_alphanumeric) {
+                    return Err(format!("'{}' has no letters or digits to match", token));
+                }
+                text.push(token);
+                continue;
+            };
+            let value = unquote(value, &token)?;
+
+            match operator {
+                Operator::Title => query.titles.push(value.to_string()),
+                Operator::Tag => {
+                    let tag = TagName::parse(value.to_string())
+                        .map_err(|e| format!("Invalid tag in '{}': {}", token, e))?;
+                    query.tags.push(tag.as_ref().to_string());
+                }
+                Operator::Before => set_date(&mut query.before, "before", value, &token)?,
+                Operator::After => set_date(&mut query.after, "after", value, &token)?,
+            }
+        }
+
+        if !text.is_empty() {
+            query.text = Some(text.join(" "));
+        }
+        Ok(query)
+    }
+
+    /// The free text as a `to_tsquery` expression matching word prefixes,
+    /// e.g. `kube:* & dep:*`. Words are split on anything that is not a
+    /// letter or digit, so `kube-deploy` needs both `kube` and `deploy`.
+    pub fn prefix_tsquery(&self) -> Option<String> {
+        let text = self.text.as_ref()?;
+        let terms: Vec<String> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| format!("{}:*", word))
+            .collect();
+        Some(terms.join(" & "))
+    }
+}
+
+/// Phrases and exclusions only have a meaning for full-text matching.
+fn require_fulltext(mode: MatchMode, token: &str) -> Result<(), String> {
+    if mode == MatchMode::Fulltext {
+        Ok(())
+    } else {
+        Err(format!(
+            "'{}' is only supported when matching in full-text mode",
+            token
+        ))
+    }
+}
+
+/// Splits on whitespace, keeping double-quoted sections together.
+fn tokenize(input: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in input.chars() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        }
+        if c.is_whitespace() && !in_quotes {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(c);
+        }
+    }
+
+    if in_quotes {
+        return Err(format!("Unterminated quote in '{}'", current));
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+enum Operator {
+    Title,
+    Tag,
+    Before,
+    After,
+}
+
+/// Splits `name:value` tokens. Only the known operator names count, so URLs,
+/// times like `12:30` and words like `re:meeting` stay free text.
+fn operator(token: &str) -> Option<(Operator, &str)> {
+    let (name, value) = token.split_once(':')?;
+    let operator = match name.to_lowercase().as_str() {
+        "title" => Operator::Title,
+        "tag" => Operator::Tag,
+        "before" => Operator::Before,
+        "after" => Operator::After,
+        _ => return None,
+    };
+    Some((operator, value))
+}
+
+fn set_date(
+    date: &mut Option<DateTime<Utc>>,
+    name: &str,
+    value: &str,
+    token: &str,
+) -> Result<(), String> {
+    if date.is_some() {
+        return Err(format!(
+            "'{}:' is given more than once in '{}'",
+            name, token
+        ));
+    }
+    *date = Some(parse_date(value).ok_or_else(|| {
+        format!(
+            "Invalid date in '{}'. Use YYYY-MM-DD or an RFC 3339 timestamp",
+            token
+        )
+    })?);
+    Ok(())
+}
+
+/// Strips the surrounding quotes from `value`, rejecting empty values and
+/// quotes in the middle of a term.
+fn unquote<'a>(value: &'a str, token: &str) -> Result<&'a str, String> {
+    let inner = match value.strip_prefix('"') {
+        Some(rest) => rest
+            .strip_suffix('"')
+            .ok_or_else(|| format!("Unexpected quote in '{}'", token))?,
+        None => value,
+    };
+    if inner.contains('"') {
+        return Err(format!("Unexpected quote in '{}'", token));
+    }
+    if inner.trim().is_empty() {
+        return Err(format!("Missing value in '{}'", token));
+    }
+    Ok(inner)
+}
+
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(date.and_hms_opt(0, 0, 0)?.and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MatchMode, SearchQuery};
+    use chrono::{TimeZone, Utc};
+    use claims::assert_err;
+
+    #[test]
+    fn plain_words_are_free_text() {
+        let query = SearchQuery::parse("  weekly   sync ", MatchMode::Fulltext).unwrap();
+        assert_eq!(query.text, Some("weekly sync".to_string()));
+        assert!(query.titles.is_empty());
+    }
+
+    #[test]
+    fn full_grammar_is_parsed() {
+        let query = SearchQuery::parse(
+            "title:meeting tag:Work -draft \"exact phrase\" before:2026-01-01",
+            MatchMode::Fulltext,
+        )
+        .unwrap();
+        assert_eq!(
+            query,
+            SearchQuery {
+                mode: MatchMode::Fulltext,
+                text: Some("-draft \"exact phrase\"".to_string()),
+                titles: vec!["meeting".to_string()],
+                tags: vec!["work".to_string()],
+                before: Some(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()),
+                after: None,
+            }
+        );
+    }
+
+    #[test]
+    fn operator_values_can_be_quoted() {
+        let query = SearchQuery::parse("title:\"weekly sync\"", MatchMode::Fulltext).unwrap();
+        assert_eq!(query.titles, vec!["weekly sync".to_string()]);
+        assert_eq!(query.text, None);
+    }
+
+    #[test]
+    fn times_are_not_operators() {
+        let query = SearchQuery::parse("standup 09:30", MatchMode::Fulltext).unwrap();
+        assert_eq!(query.text, Some("standup 09:30".to_string()));
+    }
+
+    #[test]
+    fn urls_are_not_operators() {
+        let query =
+            SearchQuery::parse("see https://example.com/notes", MatchMode::Fulltext).unwrap();
+        assert_eq!(
+            query.text,
+            Some("see https://example.com/notes".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_prefixes_are_free_text() {
+        let query = SearchQuery::parse("re:meeting todo:later", MatchMode::Fulltext).unwrap();
+        assert_eq!(query.text, Some("re:meeting todo:later".to_string()));
+        assert!(query.titles.is_empty());
+    }
+
+    #[test]
+    fn after_bounds_the_creation_date() {
+        let query = SearchQuery::parse("after:2026-01-01", MatchMode::Fulltext).unwrap();
+        assert_eq!(
+            query.after,
+            Some(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(query.text, None);
+    }
+
+    #[test]
+    fn errors_name_the_offending_token() {
+        let cases = [
+            ("before:tomorrow", "before:tomorrow"),
+            ("after:soon", "after:soon"),
+            ("tag:not/valid", "tag:not/valid"),
+            ("title:", "title:"),
+            ("-tag:work", "-tag:work"),
+            ("\"open phrase", "\"open phrase"),
+        ];
+        for (input, token) in cases {
+            let error = SearchQuery::parse(input, MatchMode::Fulltext).unwrap_err();
+            assert!(
+                error.contains(token),
+                "Error '{}' does not mention '{}'",
+                error,
+                token
+            );
+        }
+    }
+
+    #[test]
+    fn repeated_before_is_rejected() {
+        assert_err!(SearchQuery::parse(
+            "before:2026-01-01 before:2026-02-01",
+            MatchMode::Fulltext
+        ));
+    }
+
+    #[test]
+    fn prefix_mode_builds_a_prefix_tsquery() {
+        let query = SearchQuery::parse("kube deploy!", MatchMode::Prefix).unwrap();
+        assert_eq!(
+            query.prefix_tsquery(),
+            Some("kube:* & deploy:*".to_string())
+        );
+    }
+
+    #[test]
+    fn hyphenated_prefix_words_match_each_part() {
+        let query = SearchQuery::parse("kube-deploy conf", MatchMode::Prefix).unwrap();
+        assert_eq!(
+            query.prefix_tsquery(),
+            Some("kube:* & deploy:* & conf:*".to_string())
+        );
+    }
+
+    #[test]
+    fn phrases_and_exclusions_require_fulltext_mode() {
+        for mode in [MatchMode::Fuzzy, MatchMode::Prefix] {
+            assert_err!(SearchQuery::parse("\"exact phrase\"", mode));
+            assert_err!(SearchQuery::parse("meeting -draft", mode));
+        }
+    }
+
+    #[test]
+    fn prefix_words_without_letters_are_rejected() {
+        assert_err!(SearchQuery::parse("kube &&", MatchMode::Prefix));
+    }
+
+    #[test]
+    fn unknown_match_mode_is_rejected() {
+        assert_err!(MatchMode::parse("exact"));
+    }
+}
@@ -0,0 +1,74 @@
+/// Markers wrapped around search matches in `ts_headline` snippets.
+#[derive(Debug, PartialEq)]
+pub struct HighlightMarkers {
+    start: String,
+    stop: String,
+}
+
+impl Default for HighlightMarkers {
+    fn default() -> Self {
+        Self {
+            start: "<mark>".to_string(),
+            stop: "</mark>".to_string(),
+        }
+    }
+}
+
+impl HighlightMarkers {
+    pub fn parse(start: Option<String>, stop: Option<String>) -> Result<HighlightMarkers, String> {
+        let default = Self::default();
+        let start = start.unwrap_or(default.start);
+        let stop = stop.unwrap_or(default.stop);
+
+        for marker in [&start, &stop] {
+            if marker.is_empty() || marker.chars().count() > 32 {
+                return Err("Highlight markers must be between 1 and 32 characters".to_string());
+            }
+            // Markers are embedded as quoted values in the ts_headline options
+            if marker.contains(['"', '\\']) {
+                return Err("Highlight markers cannot contain quotes or backslashes".to_string());
+            }
+        }
+
+        Ok(Self { start, stop })
+    }
+
+    /// The options argument of `ts_headline`.
+    pub fn headline_options(&self) -> String {
+        format!(
+            "StartSel=\"{}\", StopSel=\"{}\", MaxWords=35, MinWords=15, MaxFragments=2",
+            self.start, self.stop
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HighlightMarkers;
+    use claims::assert_err;
+
+    #[test]
+    fn markers_default_to_mark_elements() {
+        let markers = HighlightMarkers::parse(None, None).unwrap();
+        assert!(markers
+            .headline_options()
+            .starts_with("StartSel=\"<mark>\", StopSel=\"</mark>\""));
+    }
+
+    #[test]
+    fn custom_markers_are_quoted() {
+        let markers =
+            HighlightMarkers::parse(Some("**".to_string()), Some("**".to_string())).unwrap();
+        assert!(markers
+            .headline_options()
+            .starts_with("StartSel=\"**\", StopSel=\"**\""));
+    }
+
+    #[test]
+    fn markers_that_would_break_the_options_are_rejected() {
+        assert_err!(HighlightMarkers::parse(Some("\"".to_string()), None));
+        assert_err!(HighlightMarkers::parse(None, Some("\\".to_string())));
+        assert_err!(HighlightMarkers::parse(Some("".to_string()), None));
+        assert_err!(HighlightMarkers::parse(Some("x".repeat(33)), None));
+    }
+}
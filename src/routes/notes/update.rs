@@ -1,13 +1,27 @@
+use super::etag::{if_match_satisfied, note_etag};
+use super::links::save_links;
+use super::revisions::save_revision;
 use crate::authentication::AuthenticatedUser;
-use crate::domain::UpdateNote;
-use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
-use sqlx::PgPool;
+use crate::domain::{NoteTagsUpdate, UpdateNote};
+use crate::routes::notebooks::notebook_belongs_to_user;
+use crate::routes::tags::{note_tag_names, set_note_tags};
+use actix_web::http::header::{ETag, IfMatch};
+use actix_web::{http::StatusCode, web, HttpMessage, HttpRequest, HttpResponse, ResponseError};
+use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
 #[derive(serde::Deserialize)]
 pub struct UpdateNoteRequest {
     pub title: Option<String>,
     pub content: Option<String>,
+    /// `null` takes the note out of its notebook, leaving the field out keeps it.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub notebook_id: Option<Option<String>>,
+    /// Tag names. Tags that don't exist yet are created.
+    pub tags: Option<Vec<String>>,
+    /// Either `replace` (the default), so the note ends up with exactly
+    /// `tags`, or `merge` to add them to the note's current tags.
+    pub tags_mode: Option<String>,
 }
 
 #[derive(serde::Serialize)]
@@ -15,17 +29,36 @@ pub struct UpdateNoteResponse {
     pub note_id: String,
     pub title: String,
     pub content: String,
+    pub notebook_id: Option<String>,
+    pub tags: Vec<String>,
     pub updated_at: String,
 }
 
+/// Distinguishes an explicit `null` from a missing field.
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: serde::Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    serde::Deserialize::deserialize(deserializer).map(Some)
+}
+
 #[derive(thiserror::Error)]
 pub enum UpdateNoteError {
     #[error("Invalid input: {0}")]
     ValidationError(String),
     #[error("Note not found")]
     NotFound,
+    #[error("Notebook not found")]
+    NotebookNotFound,
     #[error("Invalid note ID")]
     InvalidId,
+    #[error("Note has been modified since it was last fetched")]
+    PreconditionFailed,
+    #[error("Unsupported patch format. Use either `application/merge-patch+json` or `application/json-patch+json`")]
+    UnsupportedPatchFormat,
+    #[error("Patch cannot be applied: {0}")]
+    UnprocessablePatch(String),
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -41,7 +74,11 @@ impl ResponseError for UpdateNoteError {
         match self {
             UpdateNoteError::ValidationError(_) => StatusCode::BAD_REQUEST,
             UpdateNoteError::NotFound => StatusCode::NOT_FOUND,
+            UpdateNoteError::NotebookNotFound => StatusCode::NOT_FOUND,
             UpdateNoteError::InvalidId => StatusCode::BAD_REQUEST,
+            UpdateNoteError::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            UpdateNoteError::UnsupportedPatchFormat => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            UpdateNoteError::UnprocessablePatch(_) => StatusCode::UNPROCESSABLE_ENTITY,
             UpdateNoteError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -49,46 +86,123 @@ impl ResponseError for UpdateNoteError {
 
 #[tracing::instrument(
     name = "Update note",
-    skip(user, request, pool),
+    skip(user, http_request, request, pool),
     fields(user_id = %user.user_id)
 )]
 pub async fn update_note(
     user: AuthenticatedUser,
+    http_request: HttpRequest,
     note_id: web::Path<String>,
     request: web::Json<UpdateNoteRequest>,
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, UpdateNoteError> {
     let note_id = Uuid::parse_str(&note_id).map_err(|_| UpdateNoteError::InvalidId)?;
 
-    let update = UpdateNote::parse(request.0.title, request.0.content)
-        .map_err(UpdateNoteError::ValidationError)?;
+    let request = request.into_inner();
+    let update = UpdateNote::parse(
+        request.title,
+        request.content,
+        request.notebook_id,
+        request.tags,
+        request.tags_mode,
+    )
+    .map_err(UpdateNoteError::ValidationError)?;
 
-    let updated_note = update_note_in_db(&pool, note_id, user.user_id, &update).await?;
+    let if_match = http_request.get_header::<IfMatch>();
+    let (updated_note, version) =
+        update_note_in_db(&pool, note_id, user.user_id, &update, if_match.as_ref()).await?;
 
-    Ok(HttpResponse::Ok().json(updated_note))
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(note_etag(version)))
+        .json(updated_note))
 }
 
-#[tracing::instrument(name = "Update note in database", skip(pool, update))]
+#[tracing::instrument(name = "Update note in database", skip(pool, update, if_match))]
 async fn update_note_in_db(
     pool: &PgPool,
     note_id: Uuid,
     user_id: Uuid,
     update: &UpdateNote,
-) -> Result<UpdateNoteResponse, UpdateNoteError> {
-    // First, check if the note exists and belongs to the user
-    let existing = sqlx::query!(
+    if_match: Option<&IfMatch>,
+) -> Result<(UpdateNoteResponse, i32), UpdateNoteError> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .map_err(|e| UpdateNoteError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    let existing = lock_note(&mut transaction, note_id, user_id, if_match).await?;
+    let updated = apply_update(&mut transaction, note_id, user_id, &existing, update).await?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| UpdateNoteError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    Ok(updated)
+}
+
+/// A note as it was before an update, locked until the transaction ends.
+pub(super) struct LockedNote {
+    pub title: String,
+    pub content: String,
+    pub notebook_id: Option<Uuid>,
+    pub version: i32,
+}
+
+/// Locks the note for the update, checking it exists, belongs to the user
+/// and still matches `if_match`.
+pub(super) async fn lock_note(
+    transaction: &mut Transaction<'_, Postgres>,
+    note_id: Uuid,
+    user_id: Uuid,
+    if_match: Option<&IfMatch>,
+) -> Result<LockedNote, UpdateNoteError> {
+    let existing = sqlx::query_as!(
+        LockedNote,
         r#"
-        SELECT note_id, title, content
+        SELECT title, content, notebook_id, version
         FROM notes
-        WHERE note_id = $1 AND user_id = $2
+        WHERE note_id = $1 AND user_id = $2 AND deleted_at IS NULL
+        FOR UPDATE
         "#,
         note_id,
         user_id
     )
-    .fetch_optional(pool)
-    .await?
+    .fetch_optional(&mut **transaction)
+    .await
+    .map_err(|e| UpdateNoteError::UnexpectedError(anyhow::anyhow!(e)))?
     .ok_or(UpdateNoteError::NotFound)?;
 
+    if !if_match_satisfied(if_match, existing.version) {
+        return Err(UpdateNoteError::PreconditionFailed);
+    }
+
+    Ok(existing)
+}
+
+/// Writes `update` over the locked note. The caller commits.
+pub(super) async fn apply_update(
+    transaction: &mut Transaction<'_, Postgres>,
+    note_id: Uuid,
+    user_id: Uuid,
+    existing: &LockedNote,
+    update: &UpdateNote,
+) -> Result<(UpdateNoteResponse, i32), UpdateNoteError> {
+    if let Some(Some(notebook_id)) = update.notebook_id {
+        let owned = notebook_belongs_to_user(&mut **transaction, notebook_id, user_id)
+            .await
+            .map_err(|e| UpdateNoteError::UnexpectedError(anyhow::anyhow!(e)))?;
+        if !owned {
+            return Err(UpdateNoteError::NotebookNotFound);
+        }
+    }
+
+    // Keep the previous text so the edit can be undone. Moving a note between
+    // notebooks leaves the text alone and needs no revision.
+    if update.title.is_some() || update.content.is_some() {
+        save_revision(transaction, note_id, &existing.title, &existing.content).await?;
+    }
+
     // Determine what to update
     let new_title = update
         .title
@@ -100,29 +214,53 @@ async fn update_note_in_db(
         .as_ref()
         .map(|c| c.as_ref())
         .unwrap_or(&existing.content);
+    let new_notebook_id = update.notebook_id.unwrap_or(existing.notebook_id);
 
     // Update the note
     let row = sqlx::query!(
         r#"
         UPDATE notes
-        SET title = $1, content = $2, updated_at = NOW()
-        WHERE note_id = $3 AND user_id = $4
-        RETURNING note_id, title, content, updated_at
+        SET title = $1, content = $2, notebook_id = $3, updated_at = NOW(),
+            version = version + 1
+        WHERE note_id = $4 AND user_id = $5
+        RETURNING note_id, title, content, notebook_id, updated_at, version
         "#,
         new_title,
         new_content,
+        new_notebook_id,
         note_id,
         user_id
     )
-    .fetch_one(pool)
-    .await?;
-
-    Ok(UpdateNoteResponse {
-        note_id: row.note_id.to_string(),
-        title: row.title,
-        content: row.content,
-        updated_at: row.updated_at.to_rfc3339(),
-    })
+    .fetch_one(&mut **transaction)
+    .await
+    .map_err(|e| UpdateNoteError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    if let Some(content) = &update.content {
+        save_links(transaction, note_id, &content.links()).await?;
+    }
+
+    let tags = match &update.tags {
+        Some(NoteTagsUpdate::Replace(names)) => {
+            set_note_tags(transaction, user_id, note_id, names, true).await
+        }
+        Some(NoteTagsUpdate::Merge(names)) => {
+            set_note_tags(transaction, user_id, note_id, names, false).await
+        }
+        None => note_tag_names(transaction, note_id).await,
+    }
+    .map_err(|e| UpdateNoteError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    Ok((
+        UpdateNoteResponse {
+            note_id: row.note_id.to_string(),
+            title: row.title,
+            content: row.content,
+            notebook_id: row.notebook_id.map(|id| id.to_string()),
+            tags,
+            updated_at: row.updated_at.to_rfc3339(),
+        },
+        row.version,
+    ))
 }
 
 fn error_chain_fmt(
@@ -0,0 +1,347 @@
+use crate::authentication::AuthenticatedUser;
+use crate::domain::SavedSearchName;
+use crate::routes::{query_notes, NoteQueryParams};
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// The filters and ordering of a saved search, with the same meaning as the
+/// matching `GET /notes` query parameters.
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SavedSearchQuery {
+    pub search: Option<String>,
+    #[serde(rename = "match")]
+    pub match_mode: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub sort: Option<String>,
+    pub order: Option<String>,
+    pub tag: Option<String>,
+    pub tags: Option<String>,
+    pub tag_mode: Option<String>,
+    pub exclude_tags: Option<String>,
+    pub untagged: Option<bool>,
+    pub notebook: Option<String>,
+    pub archived: Option<String>,
+}
+
+/// Pagination and presentation options for running a saved search.
+#[derive(Default, serde::Deserialize)]
+pub struct SavedSearchPageParams {
+    pub page: Option<i64>,
+    pub page_size: Option<i64>,
+    pub cursor: Option<String>,
+    pub highlight_start: Option<String>,
+    pub highlight_end: Option<String>,
+}
+
+impl SavedSearchQuery {
+    fn into_params(self, paging: SavedSearchPageParams) -> NoteQueryParams {
+        let defaults = NoteQueryParams::default();
+        NoteQueryParams {
+            page: paging.page.unwrap_or(defaults.page),
+            page_size: paging.page_size.unwrap_or(defaults.page_size),
+            search: self.search,
+            match_mode: self.match_mode,
+            from: self.from,
+            to: self.to,
+            sort: self.sort.unwrap_or(defaults.sort),
+            order: self.order.unwrap_or(defaults.order),
+            tag: self.tag,
+            tags: self.tags,
+            tag_mode: self.tag_mode,
+            exclude_tags: self.exclude_tags,
+            untagged: self.untagged,
+            notebook: self.notebook,
+            archived: self.archived,
+            highlight_start: paging.highlight_start,
+            highlight_end: paging.highlight_end,
+            cursor: paging.cursor,
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct SavedSearchRequest {
+    pub name: String,
+    #[serde(default)]
+    pub query: SavedSearchQuery,
+}
+
+#[derive(serde::Serialize)]
+pub struct SavedSearchResponse {
+    pub search_id: String,
+    pub name: String,
+    pub query: SavedSearchQuery,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(thiserror::Error)]
+pub enum SavedSearchError {
+    #[error("Invalid input: {0}")]
+    ValidationError(String),
+    #[error("A saved search with this name already exists")]
+    Duplicate,
+    #[error("Saved search not found")]
+    NotFound,
+    #[error("Invalid saved search ID")]
+    InvalidId,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for SavedSearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for SavedSearchError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            SavedSearchError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            SavedSearchError::Duplicate => StatusCode::CONFLICT,
+            SavedSearchError::NotFound => StatusCode::NOT_FOUND,
+            SavedSearchError::InvalidId => StatusCode::BAD_REQUEST,
+            SavedSearchError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(name = "Create saved search", skip(user, request, pool), fields(user_id = %user.user_id))]
+pub async fn create_saved_search(
+    user: AuthenticatedUser,
+    request: web::Json<SavedSearchRequest>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, SavedSearchError> {
+    let (name, query) = parse_request(request.into_inner())?;
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO saved_searches (search_id, user_id, name, query)
+        VALUES ($1, $2, $3, $4)
+        RETURNING search_id, name, query, created_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        user.user_id,
+        name.as_ref(),
+        query,
+    )
+    .fetch_one(pool.as_ref())
+    .await
+    .map_err(map_write_error)?;
+
+    let response = to_response(
+        row.search_id,
+        row.name,
+        row.query,
+        row.created_at,
+        row.updated_at,
+    )?;
+    Ok(HttpResponse::Created().json(response))
+}
+
+#[tracing::instrument(name = "List saved searches", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn list_saved_searches(
+    user: AuthenticatedUser,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, SavedSearchError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT search_id, name, query, created_at, updated_at
+        FROM saved_searches
+        WHERE user_id = $1
+        ORDER BY name
+        "#,
+        user.user_id
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| SavedSearchError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    let searches = rows
+        .into_iter()
+        .map(|r| to_response(r.search_id, r.name, r.query, r.created_at, r.updated_at))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(HttpResponse::Ok().json(searches))
+}
+
+#[tracing::instrument(name = "Get saved search", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn get_saved_search(
+    user: AuthenticatedUser,
+    search_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, SavedSearchError> {
+    let search_id = Uuid::parse_str(&search_id).map_err(|_| SavedSearchError::InvalidId)?;
+    let search = fetch_saved_search(&pool, search_id, user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(search))
+}
+
+#[tracing::instrument(name = "Update saved search", skip(user, request, pool), fields(user_id = %user.user_id))]
+pub async fn update_saved_search(
+    user: AuthenticatedUser,
+    search_id: web::Path<String>,
+    request: web::Json<SavedSearchRequest>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, SavedSearchError> {
+    let search_id = Uuid::parse_str(&search_id).map_err(|_| SavedSearchError::InvalidId)?;
+    let (name, query) = parse_request(request.into_inner())?;
+
+    let row = sqlx::query!(
+        r#"
+        UPDATE saved_searches
+        SET name = $1, query = $2, updated_at = NOW()
+        WHERE search_id = $3 AND user_id = $4
+        RETURNING search_id, name, query, created_at, updated_at
+        "#,
+        name.as_ref(),
+        query,
+        search_id,
+        user.user_id
+    )
+    .fetch_optional(pool.as_ref())
+    .await
+    .map_err(map_write_error)?
+    .ok_or(SavedSearchError::NotFound)?;
+
+    let response = to_response(
+        row.search_id,
+        row.name,
+        row.query,
+        row.created_at,
+        row.updated_at,
+    )?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[tracing::instrument(name = "Delete saved search", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn delete_saved_search(
+    user: AuthenticatedUser,
+    search_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, SavedSearchError> {
+    let search_id = Uuid::parse_str(&search_id).map_err(|_| SavedSearchError::InvalidId)?;
+
+    let result = sqlx::query!(
+        "DELETE FROM saved_searches WHERE search_id = $1 AND user_id = $2",
+        search_id,
+        user.user_id
+    )
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| SavedSearchError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(SavedSearchError::NotFound);
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[tracing::instrument(name = "Run saved search", skip(user, paging, pool), fields(user_id = %user.user_id))]
+pub async fn list_saved_search_notes(
+    user: AuthenticatedUser,
+    search_id: web::Path<String>,
+    paging: web::Query<SavedSearchPageParams>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let search_id = Uuid::parse_str(&search_id).map_err(|_| SavedSearchError::InvalidId)?;
+    let search = fetch_saved_search(&pool, search_id, user.user_id).await?;
+
+    let params = search.query.into_params(paging.into_inner());
+    query_notes(&pool, user.user_id, &params).await
+}
+
+fn parse_request(
+    request: SavedSearchRequest,
+) -> Result<(SavedSearchName, serde_json::Value), SavedSearchError> {
+    let name = SavedSearchName::parse(request.name).map_err(SavedSearchError::ValidationError)?;
+
+    let query = serde_json::to_value(&request.query)
+        .map_err(|e| SavedSearchError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    // Reject filters GET /notes would reject, so a saved search always runs
+    request
+        .query
+        .into_params(SavedSearchPageParams::default())
+        .validate_filters()
+        .map_err(SavedSearchError::ValidationError)?;
+
+    Ok((name, query))
+}
+
+#[tracing::instrument(name = "Fetch saved search from database", skip(pool))]
+async fn fetch_saved_search(
+    pool: &PgPool,
+    search_id: Uuid,
+    user_id: Uuid,
+) -> Result<SavedSearchResponse, SavedSearchError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT search_id, name, query, created_at, updated_at
+        FROM saved_searches
+        WHERE search_id = $1 AND user_id = $2
+        "#,
+        search_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| SavedSearchError::UnexpectedError(anyhow::anyhow!(e)))?
+    .ok_or(SavedSearchError::NotFound)?;
+
+    to_response(
+        row.search_id,
+        row.name,
+        row.query,
+        row.created_at,
+        row.updated_at,
+    )
+}
+
+fn to_response(
+    search_id: Uuid,
+    name: String,
+    query: serde_json::Value,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+) -> Result<SavedSearchResponse, SavedSearchError> {
+    let query = serde_json::from_value(query)
+        .map_err(|e| SavedSearchError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    Ok(SavedSearchResponse {
+        search_id: search_id.to_string(),
+        name,
+        query,
+        created_at: created_at.to_rfc3339(),
+        updated_at: updated_at.to_rfc3339(),
+    })
+}
+
+fn map_write_error(e: sqlx::Error) -> SavedSearchError {
+    if let Some(db) = e.as_database_error() {
+        if db.is_unique_violation() {
+            return SavedSearchError::Duplicate;
+        }
+    }
+
+    SavedSearchError::UnexpectedError(anyhow::anyhow!(e))
+}
+
+fn error_chain_fmt(
+    e: &impl std::error::Error,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    writeln!(f, "{}\n", e)?;
+    let mut current = e.source();
+    while let Some(cause) = current {
+        writeln!(f, "Caused by:\n\t{}", cause)?;
+        current = cause.source();
+    }
+    Ok(())
+}
@@ -1,8 +1,9 @@
 use crate::authentication::AuthenticatedUser;
-use crate::domain::NewTag;
+use crate::domain::{NewTag, TagName};
 use crate::errors::user_error::TagError;
 use actix_web::{web, HttpResponse};
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::{BTreeSet, HashMap};
 use uuid::Uuid;
 
 #[derive(serde::Deserialize)]
@@ -10,10 +11,21 @@ pub struct CreateTagRequest {
     pub name: String,
 }
 
+#[derive(serde::Deserialize)]
+pub struct RenameTagRequest {
+    pub name: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct MergeTagRequest {
+    pub target_tag_id: String,
+}
+
 #[derive(serde::Serialize)]
 pub struct TagResponse {
     pub tag_id: String,
     pub name: String,
+    pub note_count: i64,
 }
 #[tracing::instrument(name = "Create tag", skip(user, request, pool), fields(user_id = %user.user_id))]
 pub async fn create_tag(
@@ -28,6 +40,7 @@ pub async fn create_tag(
     Ok(HttpResponse::Created().json(TagResponse {
         tag_id: tag_id.to_string(),
         name: new_tag.name.to_string(),
+        note_count: 0,
     }))
 }
 #[tracing::instrument(name = "Insert tag into database", skip(pool, new_tag))]
@@ -59,7 +72,15 @@ pub async fn list_tags(
     pool: web::Data<PgPool>,
 ) -> Result<HttpResponse, TagError> {
     let rows = sqlx::query!(
-        "SELECT tag_id, name FROM tags WHERE user_id = $1 ORDER BY name",
+        r#"
+        SELECT t.tag_id, t.name, COUNT(n.note_id) AS "note_count!"
+        FROM tags t
+        LEFT JOIN note_tags nt ON t.tag_id = nt.tag_id
+        LEFT JOIN notes n ON nt.note_id = n.note_id AND n.deleted_at IS NULL
+        WHERE t.user_id = $1
+        GROUP BY t.tag_id, t.name
+        ORDER BY t.name
+        "#,
         user.user_id
     )
     .fetch_all(pool.as_ref())
@@ -71,12 +92,157 @@ pub async fn list_tags(
         .map(|r| TagResponse {
             tag_id: r.tag_id.to_string(),
             name: r.name,
+            note_count: r.note_count,
         })
         .collect();
 
     Ok(HttpResponse::Ok().json(tags))
 }
 
+#[tracing::instrument(name = "Rename tag", skip(user, request, pool), fields(user_id = %user.user_id))]
+pub async fn rename_tag(
+    user: AuthenticatedUser,
+    tag_id: web::Path<String>,
+    request: web::Json<RenameTagRequest>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, TagError> {
+    let tag_id = Uuid::parse_str(&tag_id).map_err(|_| TagError::InvalidId)?;
+    let name = TagName::parse(request.0.name).map_err(TagError::Validation)?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
+
+    sqlx::query!(
+        "UPDATE tags SET name = $1 WHERE tag_id = $2 AND user_id = $3 RETURNING tag_id",
+        name.as_ref(),
+        tag_id,
+        user.user_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .map_err(|e| {
+        if let Some(db) = e.as_database_error() {
+            if db.is_unique_violation() {
+                return TagError::Duplicate;
+            }
+        }
+
+        TagError::Unexpected(anyhow::anyhow!(e))
+    })?
+    .ok_or(TagError::NotFound)?;
+
+    bump_tagged_note_versions(&mut transaction, tag_id).await?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
+
+    let tag = fetch_tag(&pool, tag_id, user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(tag))
+}
+
+#[tracing::instrument(name = "Delete tag", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn delete_tag(
+    user: AuthenticatedUser,
+    tag_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, TagError> {
+    let tag_id = Uuid::parse_str(&tag_id).map_err(|_| TagError::InvalidId)?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
+
+    // Bump the versions while the note_tags rows are still there
+    bump_tagged_note_versions(&mut transaction, tag_id).await?;
+
+    // note_tags rows go with the tag through ON DELETE CASCADE
+    let result = sqlx::query!(
+        "DELETE FROM tags WHERE tag_id = $1 AND user_id = $2",
+        tag_id,
+        user.user_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(TagError::NotFound);
+    }
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[tracing::instrument(name = "Merge tag", skip(user, request, pool), fields(user_id = %user.user_id))]
+pub async fn merge_tag(
+    user: AuthenticatedUser,
+    tag_id: web::Path<String>,
+    request: web::Json<MergeTagRequest>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, TagError> {
+    let source_tag_id = Uuid::parse_str(&tag_id).map_err(|_| TagError::InvalidId)?;
+    let target_tag_id =
+        Uuid::parse_str(&request.0.target_tag_id).map_err(|_| TagError::InvalidId)?;
+
+    if source_tag_id == target_tag_id {
+        return Err(TagError::Validation(
+            "A tag cannot be merged into itself".to_string(),
+        ));
+    }
+
+    verify_tag_ownership(&pool, source_tag_id, user.user_id).await?;
+    verify_tag_ownership(&pool, target_tag_id, user.user_id).await?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
+
+    // Every note carrying the source tag ends up with a different tag list
+    bump_tagged_note_versions(&mut transaction, source_tag_id).await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO note_tags (note_id, tag_id)
+        SELECT note_id, $2 FROM note_tags WHERE tag_id = $1
+        ON CONFLICT DO NOTHING
+        "#,
+        source_tag_id,
+        target_tag_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
+
+    sqlx::query!(
+        "DELETE FROM tags WHERE tag_id = $1 AND user_id = $2",
+        source_tag_id,
+        user.user_id
+    )
+    .execute(&mut *transaction)
+    .await
+    .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
+
+    let tag = fetch_tag(&pool, target_tag_id, user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(tag))
+}
+
 #[tracing::instrument(name = "Add tag to note", skip(user, pool), fields(user_id = %user.user_id))]
 pub async fn add_tag_to_note(
     user: AuthenticatedUser,
@@ -85,20 +251,34 @@ pub async fn add_tag_to_note(
 ) -> Result<HttpResponse, TagError> {
     let (note_id_str, tag_id_str) = path.into_inner();
     let note_id = Uuid::parse_str(&note_id_str).map_err(|_| TagError::InvalidId)?;
-    let tag_id = Uuid::parse_str(&note_id_str).map_err(|_| TagError::InvalidId)?;
+    let tag_id = Uuid::parse_str(&tag_id_str).map_err(|_| TagError::InvalidId)?;
 
-    verify_note_ownerhip(&pool, note_id, user.user_id).await?;
-    verify_tag_ownerhip(&pool, tag_id, user.user_id).await?;
+    verify_note_ownership(&pool, note_id, user.user_id).await?;
+    verify_tag_ownership(&pool, tag_id, user.user_id).await?;
 
-    sqlx::query!(
+    let mut transaction = pool
+        .begin()
+        .await
+        .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
+
+    let result = sqlx::query!(
         "INSERT INTO note_tags (note_id, tag_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
         note_id,
         tag_id
     )
-    .execute(pool.as_ref())
+    .execute(&mut *transaction)
     .await
     .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
 
+    if result.rows_affected() > 0 {
+        bump_note_version(&mut transaction, note_id).await?;
+    }
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
+
     Ok(HttpResponse::Created().finish())
 }
 
@@ -115,21 +295,179 @@ pub async fn remove_tag_from_note(
     verify_note_ownership(&pool, note_id, user.user_id).await?;
     verify_tag_ownership(&pool, tag_id, user.user_id).await?;
 
-    sqlx::query!(
+    let mut transaction = pool
+        .begin()
+        .await
+        .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
+
+    let result = sqlx::query!(
         "DELETE FROM note_tags WHERE note_id = $1 AND tag_id = $2",
         note_id,
         tag_id
     )
-    .execute(pool.as_ref())
+    .execute(&mut *transaction)
     .await
     .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
 
+    if result.rows_affected() > 0 {
+        bump_note_version(&mut transaction, note_id).await?;
+    }
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
+
     Ok(HttpResponse::NoContent().finish())
 }
 
-async fn verify_note_ownerhip(pool: &PgPool, note_id: Uuid, user_id: Uuid) -> Result<(), TagError> {
+/// The tags are part of the note representation, so changing them has to
+/// change the note's ETag.
+async fn bump_note_version(
+    transaction: &mut Transaction<'_, Postgres>,
+    note_id: Uuid,
+) -> Result<(), TagError> {
+    sqlx::query!(
+        "UPDATE notes SET version = version + 1, updated_at = NOW() WHERE note_id = $1",
+        note_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
+    Ok(())
+}
+
+/// Renaming, deleting or merging a tag changes the representation of every
+/// note carrying it.
+async fn bump_tagged_note_versions(
+    transaction: &mut Transaction<'_, Postgres>,
+    tag_id: Uuid,
+) -> Result<(), TagError> {
+    sqlx::query!(
+        r#"
+        UPDATE notes
+        SET version = version + 1, updated_at = NOW()
+        WHERE note_id IN (SELECT note_id FROM note_tags WHERE tag_id = $1)
+        "#,
+        tag_id
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?;
+    Ok(())
+}
+
+/// The IDs of the user's tags with these names, by name. Tags that don't
+/// exist yet are created.
+#[tracing::instrument(name = "Get or create tags", skip(transaction, names))]
+pub(crate) async fn get_or_create_tags(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    names: &[TagName],
+) -> Result<HashMap<String, Uuid>, sqlx::Error> {
+    let names: Vec<String> = names
+        .iter()
+        .map(|name| name.as_ref().to_string())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    if names.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let new_tag_ids: Vec<Uuid> = names.iter().map(|_| Uuid::new_v4()).collect();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO tags (tag_id, user_id, name)
+        SELECT t.tag_id, $1, t.name
+        FROM UNNEST($2::uuid[], $3::text[]) AS t(tag_id, name)
+        ON CONFLICT (user_id, name) DO NOTHING
+        "#,
+        user_id,
+        &new_tag_ids,
+        &names
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    let rows = sqlx::query!(
+        "SELECT tag_id, name FROM tags WHERE user_id = $1 AND name = ANY($2)",
+        user_id,
+        &names
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| (r.name, r.tag_id)).collect())
+}
+
+/// Tags a note with `names`, creating the tags the user doesn't have yet.
+/// With `replace`, any other tag is taken off the note. Returns the names of
+/// the note's tags afterwards, sorted.
+#[tracing::instrument(name = "Set note tags", skip(transaction, names))]
+pub(crate) async fn set_note_tags(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    note_id: Uuid,
+    names: &[TagName],
+    replace: bool,
+) -> Result<Vec<String>, sqlx::Error> {
+    let tag_ids: Vec<Uuid> = get_or_create_tags(transaction, user_id, names)
+        .await?
+        .into_values()
+        .collect();
+
+    if replace {
+        sqlx::query!(
+            "DELETE FROM note_tags WHERE note_id = $1 AND NOT (tag_id = ANY($2))",
+            note_id,
+            &tag_ids
+        )
+        .execute(&mut **transaction)
+        .await?;
+    }
+
     sqlx::query!(
-        "SELECT note_id FROM notes WHERE note_id = $1 AND user_id = $2",
+        r#"
+        INSERT INTO note_tags (note_id, tag_id)
+        SELECT $1, UNNEST($2::uuid[])
+        ON CONFLICT DO NOTHING
+        "#,
+        note_id,
+        &tag_ids
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    note_tag_names(transaction, note_id).await
+}
+
+/// The names of the note's tags, sorted.
+pub(crate) async fn note_tag_names(
+    transaction: &mut Transaction<'_, Postgres>,
+    note_id: Uuid,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT t.name
+        FROM note_tags nt
+        JOIN tags t ON nt.tag_id = t.tag_id
+        WHERE nt.note_id = $1
+        ORDER BY t.name
+        "#,
+        note_id
+    )
+    .fetch_all(&mut **transaction)
+    .await
+}
+
+async fn verify_note_ownership(
+    pool: &PgPool,
+    note_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), TagError> {
+    sqlx::query!(
+        "SELECT note_id FROM notes WHERE note_id = $1 AND user_id = $2 AND deleted_at IS NULL",
         note_id,
         user_id
     )
@@ -152,3 +490,29 @@ async fn verify_tag_ownership(pool: &PgPool, tag_id: Uuid, user_id: Uuid) -> Res
     .ok_or(TagError::NotFound)?;
     Ok(())
 }
+
+#[tracing::instrument(name = "Fetch tag from database", skip(pool))]
+async fn fetch_tag(pool: &PgPool, tag_id: Uuid, user_id: Uuid) -> Result<TagResponse, TagError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT t.tag_id, t.name, COUNT(n.note_id) AS "note_count!"
+        FROM tags t
+        LEFT JOIN note_tags nt ON t.tag_id = nt.tag_id
+        LEFT JOIN notes n ON nt.note_id = n.note_id AND n.deleted_at IS NULL
+        WHERE t.tag_id = $1 AND t.user_id = $2
+        GROUP BY t.tag_id, t.name
+        "#,
+        tag_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| TagError::Unexpected(anyhow::anyhow!(e)))?
+    .ok_or(TagError::NotFound)?;
+
+    Ok(TagResponse {
+        tag_id: row.tag_id.to_string(),
+        name: row.name,
+        note_count: row.note_count,
+    })
+}
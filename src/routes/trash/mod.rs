@@ -0,0 +1,171 @@
+use crate::authentication::AuthenticatedUser;
+use crate::storage::{delete_stored_files, FileStorage};
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(serde::Serialize)]
+pub struct TrashItem {
+    pub note_id: String,
+    pub title: String,
+    pub content: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub deleted_at: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct EmptyTrashResponse {
+    pub purged_count: u64,
+}
+
+#[derive(thiserror::Error)]
+pub enum TrashError {
+    #[error("Note not found in trash")]
+    NotFound,
+    #[error("Invalid note ID")]
+    InvalidId,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for TrashError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for TrashError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            TrashError::NotFound => StatusCode::NOT_FOUND,
+            TrashError::InvalidId => StatusCode::BAD_REQUEST,
+            TrashError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(name = "List trash", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn list_trash(
+    user: AuthenticatedUser,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, TrashError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT note_id, title, content, created_at, updated_at, deleted_at as "deleted_at!"
+        FROM notes
+        WHERE user_id = $1 AND deleted_at IS NOT NULL
+        ORDER BY deleted_at DESC
+        "#,
+        user.user_id
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| TrashError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    let notes: Vec<TrashItem> = rows
+        .into_iter()
+        .map(|r| TrashItem {
+            note_id: r.note_id.to_string(),
+            title: r.title,
+            content: r.content,
+            created_at: r.created_at.to_rfc3339(),
+            updated_at: r.updated_at.to_rfc3339(),
+            deleted_at: r.deleted_at.to_rfc3339(),
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(notes))
+}
+
+#[tracing::instrument(name = "Empty trash", skip(user, pool, storage), fields(user_id = %user.user_id))]
+pub async fn empty_trash(
+    user: AuthenticatedUser,
+    pool: web::Data<PgPool>,
+    storage: web::Data<dyn FileStorage>,
+) -> Result<HttpResponse, TrashError> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .map_err(|e| TrashError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    // Lock the trashed notes first so a concurrent restore either finishes
+    // before them or waits and then finds nothing to restore
+    let note_ids = sqlx::query_scalar!(
+        r#"
+        SELECT note_id
+        FROM notes
+        WHERE user_id = $1 AND deleted_at IS NOT NULL
+        ORDER BY note_id
+        FOR UPDATE
+        "#,
+        user.user_id
+    )
+    .fetch_all(&mut *transaction)
+    .await
+    .map_err(|e| TrashError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    let storage_keys = sqlx::query_scalar!(
+        "DELETE FROM attachments WHERE note_id = ANY($1) RETURNING storage_key",
+        &note_ids
+    )
+    .fetch_all(&mut *transaction)
+    .await
+    .map_err(|e| TrashError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    let result = sqlx::query!("DELETE FROM notes WHERE note_id = ANY($1)", &note_ids)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| TrashError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    transaction
+        .commit()
+        .await
+        .map_err(|e| TrashError::UnexpectedError(anyhow::anyhow!(e)))?;
+    delete_stored_files(storage.get_ref(), &storage_keys).await;
+
+    Ok(HttpResponse::Ok().json(EmptyTrashResponse {
+        purged_count: result.rows_affected(),
+    }))
+}
+
+#[tracing::instrument(name = "Restore note from trash", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn restore_note(
+    user: AuthenticatedUser,
+    note_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, TrashError> {
+    let note_id = Uuid::parse_str(&note_id).map_err(|_| TrashError::InvalidId)?;
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE notes
+        SET deleted_at = NULL
+        WHERE note_id = $1 AND user_id = $2 AND deleted_at IS NOT NULL
+        "#,
+        note_id,
+        user.user_id
+    )
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| TrashError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(TrashError::NotFound);
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+fn error_chain_fmt(
+    e: &impl std::error::Error,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    writeln!(f, "{}\n", e)?;
+    let mut current = e.source();
+    while let Some(cause) = current {
+        writeln!(f, "Caused by:\n\t{}", cause)?;
+        current = cause.source();
+    }
+    Ok(())
+}
@@ -1,4 +1,6 @@
 use crate::authentication::AuthenticatedUser;
+use crate::domain::SearchLanguage;
+use crate::utils::{e400, e500};
 use actix_web::{web, HttpResponse};
 use sqlx::PgPool;
 
@@ -6,6 +8,14 @@ use sqlx::PgPool;
 pub struct UserResponse {
     pub user_id: String,
     pub email: String,
+    pub search_language: String,
+    /// Total size of the user's attachments, trashed notes included.
+    pub storage_used_bytes: i64,
+}
+
+#[derive(serde::Deserialize)]
+pub struct UpdateUserRequest {
+    pub search_language: Option<String>,
 }
 
 #[tracing::instrument(name = "Get current user", skip(user, pool))]
@@ -20,6 +30,58 @@ pub async fn me(
     Ok(HttpResponse::Ok().json(user_details))
 }
 
+#[tracing::instrument(name = "Update current user", skip(user, request, pool))]
+pub async fn update_me(
+    user: AuthenticatedUser,
+    request: web::Json<UpdateUserRequest>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    if let Some(language) = &request.search_language {
+        let language = SearchLanguage::parse(language).map_err(e400)?;
+        update_search_language(&pool, user.user_id, language)
+            .await
+            .map_err(e500)?;
+    }
+
+    let user_details = get_user_details(&pool, user.user_id).await.map_err(e500)?;
+
+    Ok(HttpResponse::Ok().json(user_details))
+}
+
+/// Changes the user's search language and re-indexes their notes with it, so
+/// stored vectors and queries always use the same configuration.
+#[tracing::instrument(name = "Update user search language", skip(pool))]
+async fn update_search_language(
+    pool: &PgPool,
+    user_id: uuid::Uuid,
+    language: SearchLanguage,
+) -> Result<(), anyhow::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        "UPDATE users SET search_language = $1, updated_at = NOW() WHERE user_id = $2",
+        language.as_str(),
+        user_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE notes
+        SET search_vector = note_search_vector($1::text::regconfig, title, content)
+        WHERE user_id = $2
+        "#,
+        language.as_str(),
+        user_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+    Ok(())
+}
+
 #[tracing::instrument(name = "Get user details from database", skip(pool))]
 async fn get_user_details(
     pool: &PgPool,
@@ -27,9 +89,11 @@ async fn get_user_details(
 ) -> Result<UserResponse, anyhow::Error> {
     let row = sqlx::query!(
         r#"
-        SELECT user_id, email
-        FROM users
-        WHERE user_id = $1"#,
+        SELECT u.user_id, u.email, u.search_language,
+            (SELECT COALESCE(SUM(a.size_bytes), 0)::BIGINT FROM attachments a WHERE a.user_id = u.user_id)
+                AS "storage_used_bytes!"
+        FROM users u
+        WHERE u.user_id = $1"#,
         user_id
     )
     .fetch_one(pool)
@@ -38,5 +102,7 @@ async fn get_user_details(
     Ok(UserResponse {
         user_id: row.user_id.to_string(),
         email: row.email,
+        search_language: row.search_language,
+        storage_used_bytes: row.storage_used_bytes,
     })
 }
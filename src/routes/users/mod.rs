@@ -1,5 +1,7 @@
 mod me;
 mod register;
+mod tokens;
 
 pub use me::*;
 pub use register::*;
+pub use tokens::*;
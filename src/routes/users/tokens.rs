@@ -0,0 +1,197 @@
+use crate::authentication::{generate_api_token, hash_api_token, SessionUser};
+use crate::domain::NewApiToken;
+use actix_web::{http::StatusCode, web, HttpResponse, ResponseError};
+use chrono::{DateTime, Utc};
+use secrecy::ExposeSecret;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(serde::Deserialize)]
+pub struct CreateTokenRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(serde::Serialize)]
+pub struct TokenResponse {
+    pub token_id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+    pub expires_at: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct CreateTokenResponse {
+    #[serde(flatten)]
+    pub details: TokenResponse,
+    /// The plaintext token. It is only ever returned once, at creation time.
+    pub token: String,
+}
+
+#[derive(thiserror::Error)]
+pub enum ApiTokenError {
+    #[error("Invalid input: {0}")]
+    ValidationError(String),
+    #[error("Token not found")]
+    NotFound,
+    #[error("Invalid token ID")]
+    InvalidId,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for ApiTokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for ApiTokenError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiTokenError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            ApiTokenError::NotFound => StatusCode::NOT_FOUND,
+            ApiTokenError::InvalidId => StatusCode::BAD_REQUEST,
+            ApiTokenError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[tracing::instrument(name = "Create API token", skip(user, request, pool), fields(user_id = %user.user_id))]
+pub async fn create_token(
+    user: SessionUser,
+    request: web::Json<CreateTokenRequest>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiTokenError> {
+    let request = request.into_inner();
+    let new_token = NewApiToken::parse(
+        user.user_id,
+        request.name,
+        request.scopes,
+        request.expires_at,
+    )
+    .map_err(ApiTokenError::ValidationError)?;
+
+    let token = generate_api_token();
+    let details = insert_token(&pool, &new_token, &hash_api_token(&token)).await?;
+
+    Ok(HttpResponse::Created().json(CreateTokenResponse {
+        details,
+        token: token.expose_secret().to_string(),
+    }))
+}
+
+#[tracing::instrument(name = "List API tokens", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn list_tokens(
+    user: SessionUser,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiTokenError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT token_id, name, scopes, created_at, last_used_at, expires_at
+        FROM api_tokens
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        "#,
+        user.user_id
+    )
+    .fetch_all(pool.as_ref())
+    .await
+    .map_err(|e| ApiTokenError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    let tokens: Vec<TokenResponse> = rows
+        .into_iter()
+        .map(|r| TokenResponse {
+            token_id: r.token_id.to_string(),
+            name: r.name,
+            scopes: r.scopes,
+            created_at: r.created_at.to_rfc3339(),
+            last_used_at: r.last_used_at.map(|t| t.to_rfc3339()),
+            expires_at: r.expires_at.map(|t| t.to_rfc3339()),
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(tokens))
+}
+
+#[tracing::instrument(name = "Revoke API token", skip(user, pool), fields(user_id = %user.user_id))]
+pub async fn delete_token(
+    user: SessionUser,
+    token_id: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, ApiTokenError> {
+    let token_id = Uuid::parse_str(&token_id).map_err(|_| ApiTokenError::InvalidId)?;
+
+    let result = sqlx::query!(
+        "DELETE FROM api_tokens WHERE token_id = $1 AND user_id = $2",
+        token_id,
+        user.user_id
+    )
+    .execute(pool.as_ref())
+    .await
+    .map_err(|e| ApiTokenError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiTokenError::NotFound);
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[tracing::instrument(
+    name = "Saving new API token to database",
+    skip(pool, new_token, token_hash)
+)]
+async fn insert_token(
+    pool: &PgPool,
+    new_token: &NewApiToken,
+    token_hash: &str,
+) -> Result<TokenResponse, ApiTokenError> {
+    let scopes: Vec<String> = new_token
+        .scopes
+        .iter()
+        .map(|s| s.as_str().to_string())
+        .collect();
+
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO api_tokens (token_id, user_id, name, token_hash, scopes, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING token_id, name, scopes, created_at, expires_at
+        "#,
+        Uuid::new_v4(),
+        new_token.user_id,
+        new_token.name.as_ref(),
+        token_hash,
+        &scopes,
+        new_token.expires_at,
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| ApiTokenError::UnexpectedError(anyhow::anyhow!(e)))?;
+
+    Ok(TokenResponse {
+        token_id: row.token_id.to_string(),
+        name: row.name,
+        scopes: row.scopes,
+        created_at: row.created_at.to_rfc3339(),
+        last_used_at: None,
+        expires_at: row.expires_at.map(|t| t.to_rfc3339()),
+    })
+}
+
+fn error_chain_fmt(
+    e: &impl std::error::Error,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    writeln!(f, "{}\n", e)?;
+    let mut current = e.source();
+    while let Some(cause) = current {
+        writeln!(f, "Caused by:\n\t{}", cause)?;
+        current = cause.source();
+    }
+    Ok(())
+}
@@ -2,21 +2,61 @@ use crate::configuration::DatabaseSettings;
 use crate::configuration::Settings;
 use crate::middleware::{configure_cors, RateLimiter, RequestId};
 use crate::routes::add_tag_to_note;
+use crate::routes::archive_note;
+use crate::routes::bulk_notes;
 use crate::routes::create_note;
+use crate::routes::create_notebook;
+use crate::routes::create_saved_search;
 use crate::routes::create_tag;
+use crate::routes::create_token;
+use crate::routes::delete_attachment;
 use crate::routes::delete_note;
+use crate::routes::delete_notebook;
+use crate::routes::delete_saved_search;
+use crate::routes::delete_tag;
+use crate::routes::delete_token;
+use crate::routes::download_attachment;
+use crate::routes::empty_trash;
+use crate::routes::export_notes;
+use crate::routes::get_graph;
 use crate::routes::get_note;
+use crate::routes::get_notebook;
+use crate::routes::get_revision;
+use crate::routes::get_saved_search;
 use crate::routes::health_check;
 use crate::routes::home;
+use crate::routes::import_notes;
+use crate::routes::list_attachments;
+use crate::routes::list_backlinks;
+use crate::routes::list_note_links;
+use crate::routes::list_notebooks;
 use crate::routes::list_notes;
+use crate::routes::list_revisions;
+use crate::routes::list_saved_search_notes;
+use crate::routes::list_saved_searches;
 use crate::routes::list_tags;
+use crate::routes::list_tokens;
+use crate::routes::list_trash;
 use crate::routes::login;
 use crate::routes::logout;
 use crate::routes::me;
+use crate::routes::merge_tag;
+use crate::routes::patch_note;
+use crate::routes::pin_note;
 use crate::routes::register;
 use crate::routes::remove_tag_from_note;
+use crate::routes::rename_tag;
+use crate::routes::restore_note;
+use crate::routes::restore_revision;
+use crate::routes::unarchive_note;
+use crate::routes::unpin_note;
+use crate::routes::update_me;
 use crate::routes::update_note;
+use crate::routes::update_notebook;
+use crate::routes::update_saved_search;
+use crate::routes::upload_attachment;
 use crate::session_state::session_middleware;
+use crate::storage::{get_file_storage, FileStorage};
 
 use actix_web::cookie::Key;
 use actix_web::dev::Server;
@@ -30,6 +70,7 @@ use serde::Deserialize;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 use std::net::TcpListener;
+use std::sync::Arc;
 use tracing_actix_web::TracingLogger;
 
 pub struct Application {
@@ -40,7 +81,14 @@ pub struct Application {
 impl Application {
     pub async fn build(configuration: Settings) -> Result<Self, anyhow::Error> {
         let connection_pool = get_connection_pool(&configuration.database);
+        Self::build_with_pool(configuration, connection_pool).await
+    }
 
+    /// Like [`Application::build`], but serves requests from a caller-provided pool.
+    pub async fn build_with_pool(
+        configuration: Settings,
+        connection_pool: PgPool,
+    ) -> Result<Self, anyhow::Error> {
         let address = format!(
             "{}:{}",
             configuration.application.host, configuration.application.port
@@ -48,9 +96,11 @@ impl Application {
 
         let listener = TcpListener::bind(address)?;
         let port = listener.local_addr().unwrap().port();
+        let storage = get_file_storage(&configuration.storage)?;
         let server = run(
             listener,
             connection_pool,
+            storage,
             configuration.application.url().expect("Invalid host url"),
             configuration.application.hmac_secret,
             configuration.redis_uri,
@@ -77,14 +127,19 @@ pub fn get_connection_pool(configuration: &DatabaseSettings) -> PgPool {
 
 pub struct ApplicationBaseUrl(pub Url);
 
+/// Largest request body accepted, attachment uploads included.
+pub const MAX_PAYLOAD_BYTES: usize = 10_485_760;
+
 async fn run(
     listener: TcpListener,
     db_pool: PgPool,
+    storage: Arc<dyn FileStorage>,
     base_url: Url,
     hmac_secret: SecretString,
     redis_uri: SecretString,
 ) -> Result<Server, anyhow::Error> {
     let db_pool = web::Data::new(db_pool);
+    let storage: web::Data<dyn FileStorage> = web::Data::from(storage);
     let base_url = web::Data::new(ApplicationBaseUrl(base_url));
 
     let secret_key = Key::from(hmac_secret.expose_secret().as_bytes());
@@ -103,20 +158,89 @@ async fn run(
             .wrap(message_framework.clone())
             .wrap(TracingLogger::default())
             .app_data(web::JsonConfig::default().limit(262_144)) // 256KB JSON limit
-            .app_data(web::PayloadConfig::default().limit(10_485_760)) // 10MB payload limit
+            .app_data(web::PayloadConfig::default().limit(MAX_PAYLOAD_BYTES)) // 10MB payload limit
             .route("/", web::get().to(home))
             .route("/health", web::get().to(health_check))
             .route("/login", web::post().to(login))
             .route("/logout", web::post().to(logout))
             .route("/users", web::post().to(register))
             .route("/users/me", web::get().to(me))
+            .route("/users/me", web::patch().to(update_me))
+            .route("/users/me/tokens", web::post().to(create_token))
+            .route("/users/me/tokens", web::get().to(list_tokens))
+            .route(
+                "/users/me/tokens/{token_id}",
+                web::delete().to(delete_token),
+            )
             .route("/notes", web::post().to(create_note))
             .route("/notes", web::get().to(list_notes))
+            .route("/notes/bulk", web::post().to(bulk_notes))
             .route("/notes/{note_id}", web::get().to(get_note))
             .route("/notes/{note_id}", web::put().to(update_note))
+            .route("/notes/{note_id}", web::patch().to(patch_note))
             .route("/notes/{note_id}", web::delete().to(delete_note))
+            .route("/notes/{note_id}/restore", web::post().to(restore_note))
+            .route("/notes/{note_id}/links", web::get().to(list_note_links))
+            .route("/notes/{note_id}/backlinks", web::get().to(list_backlinks))
+            .route(
+                "/notes/{note_id}/attachments",
+                web::post().to(upload_attachment),
+            )
+            .route(
+                "/notes/{note_id}/attachments",
+                web::get().to(list_attachments),
+            )
+            .route(
+                "/notes/{note_id}/attachments/{attachment_id}",
+                web::get().to(download_attachment),
+            )
+            .route(
+                "/notes/{note_id}/attachments/{attachment_id}",
+                web::delete().to(delete_attachment),
+            )
+            .route("/notes/{note_id}/pin", web::put().to(pin_note))
+            .route("/notes/{note_id}/pin", web::delete().to(unpin_note))
+            .route("/notes/{note_id}/archive", web::put().to(archive_note))
+            .route("/notes/{note_id}/archive", web::delete().to(unarchive_note))
+            .route("/notes/{note_id}/revisions", web::get().to(list_revisions))
+            .route(
+                "/notes/{note_id}/revisions/{rev}",
+                web::get().to(get_revision),
+            )
+            .route(
+                "/notes/{note_id}/revisions/{rev}/restore",
+                web::post().to(restore_revision),
+            )
+            .route("/trash", web::get().to(list_trash))
+            .route("/trash", web::delete().to(empty_trash))
+            .route("/graph", web::get().to(get_graph))
+            .route("/export", web::get().to(export_notes))
+            .route("/import", web::post().to(import_notes))
+            .route("/notebooks", web::post().to(create_notebook))
+            .route("/notebooks", web::get().to(list_notebooks))
+            .route("/notebooks/{notebook_id}", web::get().to(get_notebook))
+            .route("/notebooks/{notebook_id}", web::put().to(update_notebook))
+            .route(
+                "/notebooks/{notebook_id}",
+                web::delete().to(delete_notebook),
+            )
+            .route("/searches", web::post().to(create_saved_search))
+            .route("/searches", web::get().to(list_saved_searches))
+            .route("/searches/{search_id}", web::get().to(get_saved_search))
+            .route("/searches/{search_id}", web::put().to(update_saved_search))
+            .route(
+                "/searches/{search_id}",
+                web::delete().to(delete_saved_search),
+            )
+            .route(
+                "/searches/{search_id}/notes",
+                web::get().to(list_saved_search_notes),
+            )
             .route("/tags", web::post().to(create_tag))
             .route("/tags", web::get().to(list_tags))
+            .route("/tags/{tag_id}", web::patch().to(rename_tag))
+            .route("/tags/{tag_id}", web::delete().to(delete_tag))
+            .route("/tags/{tag_id}/merge", web::post().to(merge_tag))
             .route(
                 "/notes/{note_id}/tags/{tag_id}",
                 web::post().to(add_tag_to_note),
@@ -126,6 +250,7 @@ async fn run(
                 web::delete().to(remove_tag_from_note),
             )
             .app_data(db_pool.clone())
+            .app_data(storage.clone())
             .app_data(base_url.clone())
             .app_data(web::Data::new(HmacSecret(hmac_secret.clone())))
     })
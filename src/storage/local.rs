@@ -0,0 +1,96 @@
+use super::FileStorage;
+use anyhow::Context;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// Stores each key as a file below `root`, using `/` in keys as directories.
+pub struct LocalFileStorage {
+    root: PathBuf,
+}
+
+impl LocalFileStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+}
+
+#[async_trait::async_trait]
+impl FileStorage for LocalFileStorage {
+    async fn put(
+        &self,
+        key: &str,
+        contents: Vec<u8>,
+        _content_type: &str,
+    ) -> Result<(), anyhow::Error> {
+        let path = self.path(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .context("Failed to create storage directory")?;
+        }
+
+        // Readers never see a partially written file
+        let partial_path = path.with_extension("partial");
+        tokio::fs::write(&partial_path, contents)
+            .await
+            .context("Failed to write stored file")?;
+        tokio::fs::rename(&partial_path, &path)
+            .await
+            .context("Failed to move stored file into place")?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        match tokio::fs::read(self.path(key)).await {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(anyhow::Error::new(e).context("Failed to read stored file")),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), anyhow::Error> {
+        match tokio::fs::remove_file(self.path(key)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(anyhow::Error::new(e).context("Failed to delete stored file")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_none, assert_ok};
+
+    fn storage() -> LocalFileStorage {
+        LocalFileStorage::new(std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()))
+    }
+
+    #[tokio::test]
+    async fn stored_files_can_be_read_back_and_deleted() {
+        let storage = storage();
+        assert_ok!(
+            storage
+                .put("user/file", b"hello".to_vec(), "text/plain")
+                .await
+        );
+        assert_eq!(
+            storage.get("user/file").await.unwrap(),
+            Some(b"hello".to_vec())
+        );
+
+        assert_ok!(storage.delete("user/file").await);
+        assert_none!(storage.get("user/file").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn missing_files_are_not_errors() {
+        let storage = storage();
+        assert_none!(storage.get("user/missing").await.unwrap());
+        assert_ok!(storage.delete("user/missing").await);
+    }
+}
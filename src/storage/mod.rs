@@ -0,0 +1,47 @@
+mod local;
+mod s3;
+
+pub use local::*;
+pub use s3::*;
+
+use crate::configuration::StorageSettings;
+use std::sync::Arc;
+
+/// A flat key-value store for attachment contents.
+#[async_trait::async_trait]
+pub trait FileStorage: Send + Sync {
+    async fn put(
+        &self,
+        key: &str,
+        contents: Vec<u8>,
+        content_type: &str,
+    ) -> Result<(), anyhow::Error>;
+
+    /// Returns `None` when nothing is stored under `key`.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, anyhow::Error>;
+
+    /// Deleting a key that does not exist is not an error.
+    async fn delete(&self, key: &str) -> Result<(), anyhow::Error>;
+}
+
+pub fn get_file_storage(settings: &StorageSettings) -> Result<Arc<dyn FileStorage>, anyhow::Error> {
+    Ok(match settings {
+        StorageSettings::Local { path } => Arc::new(LocalFileStorage::new(path)),
+        StorageSettings::S3(settings) => Arc::new(S3FileStorage::new(settings)?),
+    })
+}
+
+/// Deletes stored files whose database rows are already gone. Failures are
+/// logged rather than returned, since the rows cannot be brought back.
+pub async fn delete_stored_files(storage: &dyn FileStorage, keys: &[String]) {
+    for key in keys {
+        if let Err(e) = storage.delete(key).await {
+            tracing::warn!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                storage_key = %key,
+                "Failed to delete stored file"
+            );
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use super::FileStorage;
+use crate::configuration::S3Settings;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+use rusty_s3::{Bucket, Credentials, S3Action, UrlStyle};
+use secrecy::ExposeSecret;
+use std::time::Duration;
+
+/// Requests are signed just before they are sent, so a short expiry is enough.
+const SIGNATURE_EXPIRY: Duration = Duration::from_secs(60);
+
+/// Stores each key as an object in a bucket of an S3-compatible service,
+/// using presigned URLs over plain HTTP.
+pub struct S3FileStorage {
+    http_client: reqwest::Client,
+    bucket: Bucket,
+    credentials: Credentials,
+}
+
+impl S3FileStorage {
+    pub fn new(settings: &S3Settings) -> Result<Self, anyhow::Error> {
+        let endpoint = settings
+            .endpoint
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid S3 endpoint: {}", e))?;
+        let url_style = if settings.path_style {
+            UrlStyle::Path
+        } else {
+            UrlStyle::VirtualHost
+        };
+        let bucket = Bucket::new(
+            endpoint,
+            url_style,
+            settings.bucket.clone(),
+            settings.region.clone(),
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid S3 bucket: {}", e))?;
+        let credentials = Credentials::new(
+            settings.access_key_id.clone(),
+            settings.secret_access_key.expose_secret().to_string(),
+        );
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?;
+
+        Ok(Self {
+            http_client,
+            bucket,
+            credentials,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl FileStorage for S3FileStorage {
+    async fn put(
+        &self,
+        key: &str,
+        contents: Vec<u8>,
+        content_type: &str,
+    ) -> Result<(), anyhow::Error> {
+        let url = self
+            .bucket
+            .put_object(Some(&self.credentials), key)
+            .sign(SIGNATURE_EXPIRY);
+        self.http_client
+            .put(url)
+            .header(CONTENT_TYPE, content_type)
+            .body(contents)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        let url = self
+            .bucket
+            .get_object(Some(&self.credentials), key)
+            .sign(SIGNATURE_EXPIRY);
+        let response = self.http_client.get(url).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let contents = response.error_for_status()?.bytes().await?;
+        Ok(Some(contents.to_vec()))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), anyhow::Error> {
+        let url = self
+            .bucket
+            .delete_object(Some(&self.credentials), key)
+            .sign(SIGNATURE_EXPIRY);
+        let response = self.http_client.delete(url).send().await?;
+        if response.status() != StatusCode::NOT_FOUND {
+            response.error_for_status()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_none, assert_ok};
+    use wiremock::matchers::{body_bytes, header, method, path, query_param_contains};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn storage(server: &MockServer) -> S3FileStorage {
+        S3FileStorage::new(&S3Settings {
+            endpoint: server.uri(),
+            bucket: "attachments".to_string(),
+            region: "us-east-1".to_string(),
+            access_key_id: "minio".to_string(),
+            secret_access_key: secrecy::SecretString::new("minio-secret".into()),
+            path_style: true,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn put_uploads_a_signed_object() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/attachments/user/file"))
+            .and(query_param_contains("X-Amz-Credential", "minio/"))
+            .and(header("Content-Type", "image/png"))
+            .and(body_bytes(b"png".to_vec()))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        assert_ok!(
+            storage(&server)
+                .put("user/file", b"png".to_vec(), "image/png")
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn get_returns_the_object_or_none_when_missing() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/attachments/user/file"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"png".to_vec()))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/attachments/user/missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let storage = storage(&server);
+        assert_eq!(
+            storage.get("user/file").await.unwrap(),
+            Some(b"png".to_vec())
+        );
+        assert_none!(storage.get("user/missing").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn delete_removes_the_object() {
+        let server = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path("/attachments/user/file"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        assert_ok!(storage(&server).delete("user/file").await);
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_reported() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        assert_err!(
+            storage(&server)
+                .put("user/file", b"png".to_vec(), "image/png")
+                .await
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use crate::configuration::{Settings, TrashSettings};
+use crate::startup::get_connection_pool;
+use crate::storage::{delete_stored_files, get_file_storage, FileStorage};
+use sqlx::PgPool;
+
+pub async fn run_worker_until_stopped(configuration: Settings) -> Result<(), anyhow::Error> {
+    let connection_pool = get_connection_pool(&configuration.database);
+    let storage = get_file_storage(&configuration.storage)?;
+    worker_loop(connection_pool, storage.as_ref(), configuration.trash).await
+}
+
+async fn worker_loop(
+    pool: PgPool,
+    storage: &dyn FileStorage,
+    settings: TrashSettings,
+) -> Result<(), anyhow::Error> {
+    let mut interval = tokio::time::interval(settings.purge_interval());
+    loop {
+        interval.tick().await;
+        match purge_trashed_notes(&pool, storage, settings.retention()).await {
+            Ok(purged) => {
+                if purged > 0 {
+                    tracing::info!(purged, "Purged trashed notes");
+                }
+            }
+            Err(e) => {
+                tracing::error!(
+                    error.cause_chain = ?e,
+                    error.message = %e,
+                    "Failed to purge trashed notes"
+                );
+            }
+        }
+    }
+}
+
+/// Permanently deletes notes that have been in the trash for longer than
+/// `retention`, along with their attachments.
+#[tracing::instrument(name = "Purge trashed notes", skip(pool, storage))]
+pub async fn purge_trashed_notes(
+    pool: &PgPool,
+    storage: &dyn FileStorage,
+    retention: chrono::Duration,
+) -> Result<u64, anyhow::Error> {
+    let cutoff = chrono::Utc::now() - retention;
+    let mut transaction = pool.begin().await?;
+
+    // Lock the notes first so a concurrent restore can't take back a note
+    // whose attachments are already gone
+    let note_ids = sqlx::query_scalar!(
+        r#"
+        SELECT note_id
+        FROM notes
+        WHERE deleted_at IS NOT NULL AND deleted_at < $1
+        ORDER BY note_id
+        FOR UPDATE
+        "#,
+        cutoff
+    )
+    .fetch_all(&mut *transaction)
+    .await?;
+
+    let storage_keys = sqlx::query_scalar!(
+        "DELETE FROM attachments WHERE note_id = ANY($1) RETURNING storage_key",
+        &note_ids
+    )
+    .fetch_all(&mut *transaction)
+    .await?;
+
+    let result = sqlx::query!("DELETE FROM notes WHERE note_id = ANY($1)", &note_ids)
+        .execute(&mut *transaction)
+        .await?;
+
+    transaction.commit().await?;
+    delete_stored_files(storage, &storage_keys).await;
+
+    Ok(result.rows_affected())
+}
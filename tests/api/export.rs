@@ -0,0 +1,111 @@
+use crate::helpers::{spawn_app, TestApp};
+use std::io::{Cursor, Read};
+
+async fn create_note(app: &TestApp, title: &str, content: &str) -> String {
+    let response = app
+        .post_note(&serde_json::json!({"title": title, "content": content}))
+        .await;
+    let created: serde_json::Value = response.json().await.unwrap();
+    created["note_id"].as_str().unwrap().to_string()
+}
+
+async fn tag_note(app: &TestApp, note_id: &str, name: &str) {
+    let response = app.post_tag(&serde_json::json!({"name": name})).await;
+    let tag: serde_json::Value = response.json().await.unwrap();
+    app.add_tag_to_note(note_id, tag["tag_id"].as_str().unwrap())
+        .await;
+}
+
+/// File names and contents of a zip archive, in archive order.
+fn unzip(bytes: &[u8]) -> Vec<(String, String)> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+    (0..archive.len())
+        .map(|i| {
+            let mut file = archive.by_index(i).unwrap();
+            let mut contents = String::new();
+            file.read_to_string(&mut contents).unwrap();
+            (file.name().to_string(), contents)
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn markdown_export_is_a_zip_with_one_file_per_note() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let plan = create_note(&app, "Plan", "# Plan\n\nShip it").await;
+    tag_note(&app, &plan, "work").await;
+    create_note(&app, "Plan", "A second plan").await;
+    let trashed = create_note(&app, "Trashed", "Gone").await;
+    app.delete_note(&trashed).await;
+
+    let response = app.get_export("format=markdown").await;
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(response.headers()["content-type"], "application/zip");
+    assert!(response.headers()["content-disposition"]
+        .to_str()
+        .unwrap()
+        .starts_with("attachment; filename=\"jot-export-"));
+
+    let files = unzip(&response.bytes().await.unwrap());
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["Plan.md", "Plan (2).md"]);
+
+    let (_, markdown) = &files[0];
+    assert!(markdown.starts_with(&format!(
+        "---\nnote_id: {}\ntitle: \"Plan\"\ntags:\n  - \"work\"\ncreated_at: ",
+        plan
+    )));
+    assert!(markdown.contains("\nupdated_at: "));
+    assert!(markdown.ends_with("---\n\n# Plan\n\nShip it\n"));
+}
+
+#[tokio::test]
+async fn json_export_is_a_versioned_document() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let note_id = create_note(&app, "Plan", "Ship it").await;
+    tag_note(&app, &note_id, "work").await;
+    app.archive_note(&note_id).await;
+
+    let response = app.get_export("format=json").await;
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(response.headers()["content-type"], "application/json");
+
+    let export: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(export["version"], 1);
+    assert!(export["exported_at"].is_string());
+    let notes = export["notes"].as_array().unwrap();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0]["note_id"], note_id);
+    assert_eq!(notes[0]["title"], "Plan");
+    assert_eq!(notes[0]["content"], "Ship it");
+    assert_eq!(notes[0]["tags"], serde_json::json!(["work"]));
+    assert_eq!(notes[0]["archived"], true);
+}
+
+#[tokio::test]
+async fn export_only_includes_the_users_own_notes() {
+    let app = spawn_app().await;
+    let _alice = app.test_user_with_email("alice@example.com").await;
+    create_note(&app, "Private", "Secret").await;
+
+    let _bob = app.test_user_with_email("bob@example.com").await;
+    let export: serde_json::Value = app.get_export("format=json").await.json().await.unwrap();
+    assert_eq!(export["notes"], serde_json::json!([]));
+
+    let response = app.get_export("format=markdown").await;
+    assert!(unzip(&response.bytes().await.unwrap()).is_empty());
+}
+
+#[tokio::test]
+async fn export_rejects_unknown_formats_and_anonymous_users() {
+    let app = spawn_app().await;
+
+    let response = app.get_export("format=json").await;
+    assert_eq!(401, response.status().as_u16());
+
+    let _user = app.test_user().await;
+    let response = app.get_export("format=pdf").await;
+    assert_eq!(400, response.status().as_u16());
+}
@@ -0,0 +1,170 @@
+use crate::helpers::{spawn_app, TestApp};
+
+async fn create_note(app: &TestApp, title: &str, content: &str) -> String {
+    let response = app
+        .post_note(&serde_json::json!({"title": title, "content": content}))
+        .await;
+    let created: serde_json::Value = response.json().await.unwrap();
+    created["note_id"].as_str().unwrap().to_string()
+}
+
+async fn tag_note(app: &TestApp, note_id: &str, name: &str) {
+    let response = app.post_tag(&serde_json::json!({"name": name})).await;
+    let tag: serde_json::Value = response.json().await.unwrap();
+    app.add_tag_to_note(note_id, tag["tag_id"].as_str().unwrap())
+        .await;
+}
+
+fn node_labels(graph: &serde_json::Value) -> Vec<&str> {
+    graph["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|node| node["label"].as_str().unwrap())
+        .collect()
+}
+
+/// Alpha -> Bravo -> Charlie, with Alpha tagged `work`.
+async fn create_chain(app: &TestApp) -> (String, String, String) {
+    let charlie = create_note(app, "Charlie", "End of the chain").await;
+    let bravo = create_note(app, "Bravo", "Next is [[Charlie]]").await;
+    let alpha = create_note(app, "Alpha", &format!("Next is [[note:{}]]", bravo)).await;
+    tag_note(app, &alpha, "work").await;
+    (alpha, bravo, charlie)
+}
+
+#[tokio::test]
+async fn graph_has_notes_tags_references_and_memberships() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let (alpha, bravo, charlie) = create_chain(&app).await;
+
+    let response = app.get_graph("").await;
+    assert_eq!(200, response.status().as_u16());
+    let graph: serde_json::Value = response.json().await.unwrap();
+
+    assert_eq!(
+        node_labels(&graph),
+        vec!["Alpha", "Bravo", "Charlie", "work"]
+    );
+    let edges = graph["edges"].as_array().unwrap();
+    assert!(edges.contains(&serde_json::json!({
+        "source": format!("note:{}", alpha),
+        "target": format!("note:{}", bravo),
+        "kind": "reference"
+    })));
+    assert!(edges.contains(&serde_json::json!({
+        "source": format!("note:{}", bravo),
+        "target": format!("note:{}", charlie),
+        "kind": "reference"
+    })));
+    assert!(edges.contains(&serde_json::json!({
+        "source": format!("note:{}", alpha),
+        "target": "tag:work",
+        "kind": "tag"
+    })));
+    assert_eq!(edges.len(), 3);
+}
+
+#[tokio::test]
+async fn mentioned_ids_and_titles_are_references() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let roadmap = create_note(&app, "Roadmap", "Long term goals").await;
+    let budget = create_note(&app, "Q3 budget", "Numbers").await;
+    let plan = create_note(
+        &app,
+        "Plan",
+        &format!("Based on {} and the q3 budget", roadmap),
+    )
+    .await;
+
+    let graph: serde_json::Value = app.get_graph("").await.json().await.unwrap();
+    let edges = graph["edges"].as_array().unwrap();
+    for target in [&roadmap, &budget] {
+        assert!(edges.contains(&serde_json::json!({
+            "source": format!("note:{}", plan),
+            "target": format!("note:{}", target),
+            "kind": "reference"
+        })));
+    }
+    assert_eq!(edges.len(), 2);
+}
+
+#[tokio::test]
+async fn graph_can_be_filtered_by_tag() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    create_chain(&app).await;
+
+    let graph: serde_json::Value = app.get_graph("tags=work").await.json().await.unwrap();
+    assert_eq!(node_labels(&graph), vec!["Alpha", "work"]);
+    assert_eq!(graph["edges"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn depth_limits_the_graph_around_the_start_note() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let (_, _, charlie) = create_chain(&app).await;
+
+    let graph: serde_json::Value = app
+        .get_graph(&format!("start={}&depth=1", charlie))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(node_labels(&graph), vec!["Bravo", "Charlie"]);
+
+    let graph: serde_json::Value = app
+        .get_graph(&format!("start={}", charlie))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        node_labels(&graph),
+        vec!["Alpha", "Bravo", "Charlie", "work"]
+    );
+}
+
+#[tokio::test]
+async fn graph_can_be_rendered_as_dot() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let (alpha, bravo, _) = create_chain(&app).await;
+
+    let response = app.get_graph("format=dot").await;
+    assert_eq!(200, response.status().as_u16());
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/vnd.graphviz"));
+
+    let dot = response.text().await.unwrap();
+    assert!(dot.starts_with("digraph notes {"));
+    assert!(dot.contains(&format!("\"note:{}\" -> \"note:{}\";", alpha, bravo)));
+    assert!(dot.contains("\"tag:work\" [label=\"work\", shape=ellipse];"));
+}
+
+#[tokio::test]
+async fn invalid_graph_parameters_are_rejected() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    for query in [
+        "format=svg",
+        "tags=not valid!",
+        "depth=2",
+        "start=nope",
+        "depth=-1",
+    ] {
+        let response = app.get_graph(query).await;
+        assert_eq!(400, response.status().as_u16(), "Accepted {}", query);
+    }
+
+    let response = app
+        .get_graph(&format!("start={}", uuid::Uuid::new_v4()))
+        .await;
+    assert_eq!(404, response.status().as_u16());
+}
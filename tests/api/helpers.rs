@@ -1,8 +1,12 @@
-use jot::configuration::{get_configuration, DatabaseSettings};
+use jot::configuration::{get_configuration, DatabaseSettings, StorageSettings};
 use jot::startup::{get_connection_pool, Application};
+use jot::storage::{get_file_storage, FileStorage};
 use jot::telemetry::{get_subscriber, init_subscriber};
 use once_cell::sync::Lazy;
+use sqlx::postgres::PgPoolOptions;
 use sqlx::{Connection, Executor, PgConnection, PgPool};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use uuid::Uuid;
 
 // Re-export for convenience
@@ -26,9 +30,18 @@ pub struct TestApp {
     pub port: u16,
     pub db_pool: PgPool,
     pub api_client: reqwest::Client,
+    /// Connections handed out by the application's pool. Every standalone
+    /// query checks out a connection, so this tracks round trips to Postgres.
+    pub db_checkouts: Arc<AtomicUsize>,
+    /// The storage backend the application keeps attachments in.
+    pub storage: Arc<dyn FileStorage>,
 }
 
 impl TestApp {
+    pub fn db_checkout_count(&self) -> usize {
+        self.db_checkouts.load(Ordering::SeqCst)
+    }
+
     pub async fn test_user(&self) -> TestUser {
         self.test_user_with_email("test@example.com").await
     }
@@ -98,6 +111,58 @@ impl TestApp {
             .expect("Failed to execute request")
     }
 
+    pub async fn patch_current_user<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .patch(&format!("{}/users/me", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_token<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(&format!("{}/users/me/tokens", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_tokens(&self) -> reqwest::Response {
+        self.api_client
+            .get(&format!("{}/users/me/tokens", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn delete_token(&self, token_id: &str) -> reqwest::Response {
+        self.api_client
+            .delete(&format!("{}/users/me/tokens/{}", &self.address, token_id))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    /// Builds a request that authenticates with an API token instead of the session cookie.
+    pub fn with_bearer(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        token: &str,
+    ) -> reqwest::RequestBuilder {
+        reqwest::Client::new()
+            .request(method, format!("{}{}", &self.address, path))
+            .bearer_auth(token)
+    }
+
     pub async fn post_note<Body>(&self, body: &Body) -> reqwest::Response
     where
         Body: serde::Serialize,
@@ -110,6 +175,18 @@ impl TestApp {
             .expect("Failed to execute request")
     }
 
+    pub async fn post_bulk_notes<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(&format!("{}/notes/bulk", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
     pub async fn get_notes(&self, page: Option<i64>, page_size: Option<i64>) -> reqwest::Response {
         let mut url = format!("{}/notes", &self.address);
         let mut params = vec![];
@@ -133,6 +210,17 @@ impl TestApp {
             .expect("Failed to execute request")
     }
 
+    pub async fn get_notes_with_cursor(&self, query: &str, cursor: &str) -> reqwest::Response {
+        self.api_client
+            .get(&format!(
+                "{}/notes?{}&cursor={}",
+                &self.address, query, cursor
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
     pub async fn get_note_by_id(&self, note_id: &str) -> reqwest::Response {
         self.api_client
             .get(&format!("{}/notes/{}", &self.address, note_id))
@@ -141,6 +229,15 @@ impl TestApp {
             .expect("Failed to execute request")
     }
 
+    pub async fn get_note_with_accept(&self, note_id: &str, accept: &str) -> reqwest::Response {
+        self.api_client
+            .get(&format!("{}/notes/{}", &self.address, note_id))
+            .header("Accept", accept)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
     pub async fn put_note<Body>(&self, note_id: &str, body: &Body) -> reqwest::Response
     where
         Body: serde::Serialize,
@@ -161,6 +258,207 @@ impl TestApp {
             .expect("Failed to execute request")
     }
 
+    // Conditional request helpers
+    pub async fn get_note_if_none_match(&self, note_id: &str, etag: &str) -> reqwest::Response {
+        self.api_client
+            .get(&format!("{}/notes/{}", &self.address, note_id))
+            .header("If-None-Match", etag)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn patch_note(
+        &self,
+        note_id: &str,
+        content_type: &str,
+        body: &serde_json::Value,
+    ) -> reqwest::Response {
+        self.api_client
+            .patch(&format!("{}/notes/{}", &self.address, note_id))
+            .header("Content-Type", content_type)
+            .body(body.to_string())
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn put_note_if_match<Body>(
+        &self,
+        note_id: &str,
+        etag: &str,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .put(&format!("{}/notes/{}", &self.address, note_id))
+            .header("If-Match", etag)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn delete_note_if_match(&self, note_id: &str, etag: &str) -> reqwest::Response {
+        self.api_client
+            .delete(&format!("{}/notes/{}", &self.address, note_id))
+            .header("If-Match", etag)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    // Trash helpers
+    pub async fn get_trash(&self) -> reqwest::Response {
+        self.api_client
+            .get(&format!("{}/trash", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn empty_trash(&self) -> reqwest::Response {
+        self.api_client
+            .delete(&format!("{}/trash", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn restore_note(&self, note_id: &str) -> reqwest::Response {
+        self.api_client
+            .post(&format!("{}/notes/{}/restore", &self.address, note_id))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    // Link helpers
+    pub async fn get_note_links(&self, note_id: &str) -> reqwest::Response {
+        self.api_client
+            .get(&format!("{}/notes/{}/links", &self.address, note_id))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_note_backlinks(&self, note_id: &str) -> reqwest::Response {
+        self.api_client
+            .get(&format!("{}/notes/{}/backlinks", &self.address, note_id))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    // Pin and archive helpers
+    pub async fn pin_note(&self, note_id: &str) -> reqwest::Response {
+        self.api_client
+            .put(&format!("{}/notes/{}/pin", &self.address, note_id))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn unpin_note(&self, note_id: &str) -> reqwest::Response {
+        self.api_client
+            .delete(&format!("{}/notes/{}/pin", &self.address, note_id))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn archive_note(&self, note_id: &str) -> reqwest::Response {
+        self.api_client
+            .put(&format!("{}/notes/{}/archive", &self.address, note_id))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn unarchive_note(&self, note_id: &str) -> reqwest::Response {
+        self.api_client
+            .delete(&format!("{}/notes/{}/archive", &self.address, note_id))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    // Revision helpers
+    pub async fn post_attachment(
+        &self,
+        note_id: &str,
+        form: reqwest::multipart::Form,
+    ) -> reqwest::Response {
+        self.api_client
+            .post(&format!("{}/notes/{}/attachments", &self.address, note_id))
+            .multipart(form)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_attachments(&self, note_id: &str) -> reqwest::Response {
+        self.api_client
+            .get(&format!("{}/notes/{}/attachments", &self.address, note_id))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_attachment(&self, note_id: &str, attachment_id: &str) -> reqwest::Response {
+        self.api_client
+            .get(&format!(
+                "{}/notes/{}/attachments/{}",
+                &self.address, note_id, attachment_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn delete_attachment(&self, note_id: &str, attachment_id: &str) -> reqwest::Response {
+        self.api_client
+            .delete(&format!(
+                "{}/notes/{}/attachments/{}",
+                &self.address, note_id, attachment_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_note_revisions(&self, note_id: &str) -> reqwest::Response {
+        self.api_client
+            .get(&format!("{}/notes/{}/revisions", &self.address, note_id))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_note_revision(&self, note_id: &str, rev: &str) -> reqwest::Response {
+        self.api_client
+            .get(&format!(
+                "{}/notes/{}/revisions/{}",
+                &self.address, note_id, rev
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn restore_note_revision(&self, note_id: &str, rev: &str) -> reqwest::Response {
+        self.api_client
+            .post(&format!(
+                "{}/notes/{}/revisions/{}/restore",
+                &self.address, note_id, rev
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
     // Search and filter helpers
     pub async fn search_notes(&self, query: &str) -> reqwest::Response {
         self.api_client
@@ -230,6 +528,14 @@ impl TestApp {
             .expect("Failed to execute request")
     }
 
+    pub async fn get_notes_with_query(&self, query: &str) -> reqwest::Response {
+        self.api_client
+            .get(&format!("{}/notes?{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
     pub async fn filter_notes_by_tag(&self, tag: &str) -> reqwest::Response {
         self.api_client
             .get(&format!("{}/notes?tag={}", &self.address, tag))
@@ -238,6 +544,141 @@ impl TestApp {
             .expect("Failed to execute request")
     }
 
+    // Saved search helpers
+    pub async fn post_search<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(&format!("{}/searches", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_searches(&self) -> reqwest::Response {
+        self.api_client
+            .get(&format!("{}/searches", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_search(&self, search_id: &str) -> reqwest::Response {
+        self.api_client
+            .get(&format!("{}/searches/{}", &self.address, search_id))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn put_search<Body>(&self, search_id: &str, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .put(&format!("{}/searches/{}", &self.address, search_id))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn delete_search(&self, search_id: &str) -> reqwest::Response {
+        self.api_client
+            .delete(&format!("{}/searches/{}", &self.address, search_id))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_search_notes(&self, search_id: &str, query: &str) -> reqwest::Response {
+        self.api_client
+            .get(&format!(
+                "{}/searches/{}/notes?{}",
+                &self.address, search_id, query
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    // Graph helpers
+    pub async fn get_graph(&self, query: &str) -> reqwest::Response {
+        self.api_client
+            .get(&format!("{}/graph?{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    // Notebook helpers
+    pub async fn get_export(&self, query: &str) -> reqwest::Response {
+        self.api_client
+            .get(&format!("{}/export?{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_import(&self, form: reqwest::multipart::Form) -> reqwest::Response {
+        self.api_client
+            .post(&format!("{}/import", &self.address))
+            .multipart(form)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_notebook<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(&format!("{}/notebooks", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_notebooks(&self) -> reqwest::Response {
+        self.api_client
+            .get(&format!("{}/notebooks", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn get_notebook(&self, notebook_id: &str) -> reqwest::Response {
+        self.api_client
+            .get(&format!("{}/notebooks/{}", &self.address, notebook_id))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn put_notebook<Body>(&self, notebook_id: &str, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .put(&format!("{}/notebooks/{}", &self.address, notebook_id))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn delete_notebook(&self, notebook_id: &str) -> reqwest::Response {
+        self.api_client
+            .delete(&format!("{}/notebooks/{}", &self.address, notebook_id))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
     // Tag helpers
     pub async fn post_tag<Body>(&self, body: &Body) -> reqwest::Response
     where
@@ -259,6 +700,35 @@ impl TestApp {
             .expect("Failed to execute request")
     }
 
+    pub async fn patch_tag<Body>(&self, tag_id: &str, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .patch(&format!("{}/tags/{}", &self.address, tag_id))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn delete_tag(&self, tag_id: &str) -> reqwest::Response {
+        self.api_client
+            .delete(&format!("{}/tags/{}", &self.address, tag_id))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn merge_tags(&self, source_tag_id: &str, target_tag_id: &str) -> reqwest::Response {
+        self.api_client
+            .post(&format!("{}/tags/{}/merge", &self.address, source_tag_id))
+            .json(&serde_json::json!({ "target_tag_id": target_tag_id }))
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
     pub async fn add_tag_to_note(&self, note_id: &str, tag_id: &str) -> reqwest::Response {
         self.api_client
             .post(&format!(
@@ -296,12 +766,36 @@ pub async fn spawn_app() -> TestApp {
         c.application.port = 0;
 
         c.redis_uri = secrecy::SecretString::new("redis://127.0.0.1:6379".into());
+        // Each test stores attachments in a directory of its own
+        c.storage = StorageSettings::Local {
+            path: std::env::temp_dir()
+                .join(Uuid::new_v4().to_string())
+                .to_string_lossy()
+                .into_owned(),
+        };
         c
     };
 
     configure_database(&configuration.database).await;
 
-    let application = Application::build(configuration.clone())
+    let db_checkouts = Arc::new(AtomicUsize::new(0));
+    let application_pool = {
+        let on_connect = db_checkouts.clone();
+        let on_acquire = db_checkouts.clone();
+        PgPoolOptions::new()
+            .acquire_timeout(std::time::Duration::from_secs(2))
+            .after_connect(move |_, _| {
+                on_connect.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Ok(()) })
+            })
+            .before_acquire(move |_, _| {
+                on_acquire.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Ok(true) })
+            })
+            .connect_lazy_with(configuration.database.with_db())
+    };
+
+    let application = Application::build_with_pool(configuration.clone(), application_pool)
         .await
         .expect("Failed to build application");
     let application_port = application.port();
@@ -319,6 +813,8 @@ pub async fn spawn_app() -> TestApp {
         port: application_port,
         db_pool: get_connection_pool(&configuration.database),
         api_client: client,
+        db_checkouts,
+        storage: get_file_storage(&configuration.storage).expect("Failed to build storage"),
     };
 
     test_app
@@ -0,0 +1,212 @@
+use crate::helpers::{spawn_app, TestApp};
+use reqwest::multipart::{Form, Part};
+use std::io::{Cursor, Write};
+
+fn file_form(filename: &str, contents: Vec<u8>) -> Form {
+    Form::new().part(
+        "file",
+        Part::bytes(contents).file_name(filename.to_string()),
+    )
+}
+
+fn zip(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, contents) in files {
+        zip.start_file(*name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+async fn get_note(app: &TestApp, note_id: &serde_json::Value) -> serde_json::Value {
+    app.get_note_by_id(note_id.as_str().unwrap())
+        .await
+        .json()
+        .await
+        .unwrap()
+}
+
+async fn tag_names(app: &TestApp) -> Vec<String> {
+    let tags: Vec<serde_json::Value> = app.get_tags().await.json().await.unwrap();
+    let mut names: Vec<String> = tags
+        .iter()
+        .map(|tag| tag["name"].as_str().unwrap().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+fn timestamp(value: &serde_json::Value) -> chrono::DateTime<chrono::FixedOffset> {
+    chrono::DateTime::parse_from_rfc3339(value.as_str().unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn markdown_archive_is_imported_with_front_matter() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let archive = zip(&[
+        (
+            "plans/Q3.md",
+            "---\ntitle: Q3 plan\ntags: [work, ideas]\ncreated: 2023-04-01T09:30:00Z\n---\n# Plan\n",
+        ),
+        ("Grocery list.md", "- milk\n"),
+        ("notes/image.png", "not markdown"),
+        ("Bad tag.md", "---\ntags: [\"not valid!\"]\n---\nBody"),
+    ]);
+
+    let response = app.post_import(file_form("notes.zip", archive)).await;
+    assert_eq!(200, response.status().as_u16());
+    let report: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(report["imported_count"], 2);
+    assert_eq!(report["failed_count"], 1);
+
+    let items = report["items"].as_array().unwrap();
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0]["source"], "plans/Q3.md");
+    assert_eq!(items[0]["status"], "imported");
+    assert_eq!(items[2]["source"], "Bad tag.md");
+    assert_eq!(items[2]["status"], "failed");
+    assert!(items[2]["error"].as_str().unwrap().contains("not valid!"));
+
+    let plan = get_note(&app, &items[0]["note_id"]).await;
+    assert_eq!(plan["title"], "Q3 plan");
+    assert_eq!(plan["content"], "# Plan\n");
+    assert_eq!(plan["tags"], serde_json::json!(["ideas", "work"]));
+    let created_at = timestamp(&plan["created_at"]);
+    assert_eq!(created_at.to_rfc3339(), "2023-04-01T09:30:00+00:00");
+    assert_eq!(timestamp(&plan["updated_at"]), created_at);
+
+    let groceries = get_note(&app, &items[1]["note_id"]).await;
+    assert_eq!(groceries["title"], "Grocery list");
+    assert!(groceries.get("tags").is_none());
+
+    assert_eq!(tag_names(&app).await, vec!["ideas", "work"]);
+}
+
+#[tokio::test]
+async fn enex_export_is_imported() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let enex = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE en-export SYSTEM "http://xml.evernote.com/pub/evernote-export3.dtd">
+<en-export>
+  <note>
+    <title>Packing list</title>
+    <content><![CDATA[<en-note><div>Bring:</div><ul><li>Passport</li><li><b>Charger</b></li></ul></en-note>]]></content>
+    <created>20220115T083000Z</created>
+    <tag>travel</tag>
+  </note>
+  <note>
+    <title></title>
+    <content><![CDATA[<en-note>Untitled</en-note>]]></content>
+  </note>
+</en-export>"#;
+
+    let response = app
+        .post_import(file_form("My Notes.enex", enex.as_bytes().to_vec()))
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let report: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(report["imported_count"], 1);
+    assert_eq!(report["failed_count"], 1);
+
+    let items = report["items"].as_array().unwrap();
+    assert_eq!(items[0]["source"], "Packing list");
+    assert_eq!(items[1]["source"], "Note 2");
+    assert_eq!(items[1]["status"], "failed");
+
+    let note = get_note(&app, &items[0]["note_id"]).await;
+    assert_eq!(note["title"], "Packing list");
+    assert_eq!(note["content"], "Bring:\n- Passport\n- **Charger**");
+    assert_eq!(note["tags"], serde_json::json!(["travel"]));
+    assert_eq!(
+        timestamp(&note["created_at"]).to_rfc3339(),
+        "2022-01-15T08:30:00+00:00"
+    );
+}
+
+#[tokio::test]
+async fn imported_tags_reuse_existing_tags() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    app.post_tag(&serde_json::json!({"name": "work"})).await;
+
+    let archive = zip(&[
+        ("a.md", "---\ntags: [work]\n---\nA"),
+        ("b.md", "---\ntags: work, later\n---\nB"),
+    ]);
+    let response = app.post_import(file_form("notes.zip", archive)).await;
+    assert_eq!(200, response.status().as_u16());
+
+    assert_eq!(tag_names(&app).await, vec!["later", "work"]);
+}
+
+#[tokio::test]
+async fn export_archive_can_be_imported_again() {
+    let app = spawn_app().await;
+    let _alice = app.test_user_with_email("alice@example.com").await;
+    let response = app
+        .post_note(&serde_json::json!({"title": "Plan", "content": "Ship it\n"}))
+        .await;
+    let note: serde_json::Value = response.json().await.unwrap();
+    let tag: serde_json::Value = app
+        .post_tag(&serde_json::json!({"name": "work"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    app.add_tag_to_note(
+        note["note_id"].as_str().unwrap(),
+        tag["tag_id"].as_str().unwrap(),
+    )
+    .await;
+    let archive = app
+        .get_export("format=markdown")
+        .await
+        .bytes()
+        .await
+        .unwrap();
+
+    let _bob = app.test_user_with_email("bob@example.com").await;
+    let report: serde_json::Value = app
+        .post_import(file_form("export.zip", archive.to_vec()))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(report["imported_count"], 1);
+
+    let imported = get_note(&app, &report["items"][0]["note_id"]).await;
+    assert_eq!(imported["title"], "Plan");
+    assert_eq!(imported["content"], "Ship it\n");
+    assert_eq!(imported["tags"], serde_json::json!(["work"]));
+    assert_eq!(imported["created_at"], note["created_at"]);
+}
+
+#[tokio::test]
+async fn unsupported_uploads_are_rejected() {
+    let app = spawn_app().await;
+
+    let response = app
+        .post_import(file_form("notes.zip", zip(&[("a.md", "A")])))
+        .await;
+    assert_eq!(401, response.status().as_u16());
+
+    let _user = app.test_user().await;
+    for (form, description) in [
+        (file_form("notes.txt", b"just text".to_vec()), "plain text"),
+        (
+            file_form("notes.enex", b"<rss><note/></rss>".to_vec()),
+            "not an Evernote export",
+        ),
+        (
+            file_form("notes.zip", b"PK\x03\x04truncated".to_vec()),
+            "corrupt zip",
+        ),
+        (Form::new().text("title", "no file"), "missing file field"),
+    ] {
+        let response = app.post_import(form).await;
+        assert_eq!(400, response.status().as_u16(), "Accepted {}", description);
+    }
+}
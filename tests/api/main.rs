@@ -1,7 +1,14 @@
+mod export;
+mod graph;
 mod health_check;
 mod helpers;
+mod import;
 mod login;
+mod notebooks;
 mod notes;
+mod searches;
 
 mod tag;
+mod tokens;
+mod trash;
 mod users;
@@ -0,0 +1,223 @@
+use crate::helpers::{spawn_app, TestApp};
+
+async fn create_notebook(app: &TestApp, name: &str, parent_id: Option<&str>) -> String {
+    let response = app
+        .post_notebook(&serde_json::json!({"name": name, "parent_id": parent_id}))
+        .await;
+    assert_eq!(201, response.status().as_u16());
+    let created: serde_json::Value = response.json().await.unwrap();
+    created["notebook_id"].as_str().unwrap().to_string()
+}
+
+async fn create_note(app: &TestApp, title: &str, notebook_id: Option<&str>) -> String {
+    let response = app
+        .post_note(&serde_json::json!({
+            "title": title,
+            "content": "Content",
+            "notebook_id": notebook_id
+        }))
+        .await;
+    assert_eq!(201, response.status().as_u16());
+    let created: serde_json::Value = response.json().await.unwrap();
+    created["note_id"].as_str().unwrap().to_string()
+}
+
+async fn listed_titles(app: &TestApp, query: &str) -> Vec<String> {
+    let body: serde_json::Value = app.get_notes_with_query(query).await.json().await.unwrap();
+    body["notes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|note| note["title"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn notebook_crud_works() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let notebook_id = create_notebook(&app, "Work", None).await;
+    let response = app.get_notebook(&notebook_id).await;
+    assert_eq!(200, response.status().as_u16());
+    let notebook: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(notebook["name"], "Work");
+    assert!(notebook["parent_id"].is_null());
+    assert_eq!(notebook["note_count"], 0);
+
+    let response = app
+        .put_notebook(&notebook_id, &serde_json::json!({"name": "Office"}))
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let updated: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(updated["name"], "Office");
+
+    let notebooks: Vec<serde_json::Value> = app.get_notebooks().await.json().await.unwrap();
+    assert_eq!(notebooks.len(), 1);
+
+    let response = app.delete_notebook(&notebook_id).await;
+    assert_eq!(204, response.status().as_u16());
+    let response = app.get_notebook(&notebook_id).await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn sibling_notebooks_need_unique_names() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let work = create_notebook(&app, "Work", None).await;
+    let response = app
+        .post_notebook(&serde_json::json!({"name": "Work"}))
+        .await;
+    assert_eq!(409, response.status().as_u16());
+
+    // The same name is fine under a different parent
+    create_notebook(&app, "Work", Some(&work)).await;
+}
+
+#[tokio::test]
+async fn invalid_notebooks_are_rejected() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let response = app.post_notebook(&serde_json::json!({"name": "  "})).await;
+    assert_eq!(400, response.status().as_u16());
+
+    let response = app
+        .post_notebook(&serde_json::json!({"name": "Work", "parent_id": "not-a-uuid"}))
+        .await;
+    assert_eq!(400, response.status().as_u16());
+
+    let response = app
+        .post_notebook(&serde_json::json!({
+            "name": "Work",
+            "parent_id": uuid::Uuid::new_v4().to_string()
+        }))
+        .await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn notebooks_cannot_be_moved_into_their_own_subtree() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let work = create_notebook(&app, "Work", None).await;
+    let projects = create_notebook(&app, "Projects", Some(&work)).await;
+
+    let response = app
+        .put_notebook(
+            &work,
+            &serde_json::json!({"name": "Work", "parent_id": projects}),
+        )
+        .await;
+    assert_eq!(400, response.status().as_u16());
+
+    let response = app
+        .put_notebook(
+            &work,
+            &serde_json::json!({"name": "Work", "parent_id": work}),
+        )
+        .await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn notes_can_be_filed_moved_and_unfiled() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let work = create_notebook(&app, "Work", None).await;
+    let home = create_notebook(&app, "Home", None).await;
+    let note_id = create_note(&app, "Plan", Some(&work)).await;
+
+    let note: serde_json::Value = app.get_note_by_id(&note_id).await.json().await.unwrap();
+    assert_eq!(note["notebook_id"], work.as_str());
+
+    let response = app
+        .put_note(&note_id, &serde_json::json!({"notebook_id": home}))
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let updated: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(updated["notebook_id"], home.as_str());
+    assert_eq!(updated["title"], "Plan");
+
+    let response = app
+        .put_note(&note_id, &serde_json::json!({"notebook_id": null}))
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let updated: serde_json::Value = response.json().await.unwrap();
+    assert!(updated["notebook_id"].is_null());
+
+    // Moving a note does not touch its text, so it leaves no revision
+    let revisions: Vec<serde_json::Value> =
+        app.get_note_revisions(&note_id).await.json().await.unwrap();
+    assert!(revisions.is_empty());
+}
+
+#[tokio::test]
+async fn notes_cannot_be_filed_in_another_users_notebook() {
+    let app = spawn_app().await;
+    let _alice = app.test_user_with_email("alice@example.com").await;
+    let notebook_id = create_notebook(&app, "Private", None).await;
+
+    let _bob = app.test_user_with_email("bob@example.com").await;
+    let response = app
+        .post_note(&serde_json::json!({
+            "title": "Sneaky",
+            "content": "Content",
+            "notebook_id": notebook_id
+        }))
+        .await;
+    assert_eq!(404, response.status().as_u16());
+
+    let note_id = create_note(&app, "Mine", None).await;
+    let response = app
+        .put_note(&note_id, &serde_json::json!({"notebook_id": notebook_id}))
+        .await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn notebook_filter_includes_sub_notebooks() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let work = create_notebook(&app, "Work", None).await;
+    let projects = create_notebook(&app, "Projects", Some(&work)).await;
+    create_note(&app, "Roadmap", Some(&work)).await;
+    create_note(&app, "Launch", Some(&projects)).await;
+    create_note(&app, "Groceries", None).await;
+
+    let titles = listed_titles(&app, &format!("notebook={}&sort=title&order=asc", work)).await;
+    assert_eq!(titles, vec!["Launch", "Roadmap"]);
+
+    let titles = listed_titles(&app, &format!("notebook={}", projects)).await;
+    assert_eq!(titles, vec!["Launch"]);
+
+    let titles = listed_titles(&app, "notebook=none").await;
+    assert_eq!(titles, vec!["Groceries"]);
+
+    let response = app.get_notes_with_query("notebook=inbox").await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn deleting_a_notebook_unfiles_its_notes() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let work = create_notebook(&app, "Work", None).await;
+    let projects = create_notebook(&app, "Projects", Some(&work)).await;
+    let note_id = create_note(&app, "Launch", Some(&projects)).await;
+
+    let response = app.delete_notebook(&work).await;
+    assert_eq!(204, response.status().as_u16());
+
+    let response = app.get_notebook(&projects).await;
+    assert_eq!(404, response.status().as_u16());
+
+    let note: serde_json::Value = app.get_note_by_id(&note_id).await.json().await.unwrap();
+    assert!(note["notebook_id"].is_null());
+}
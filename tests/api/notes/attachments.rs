@@ -0,0 +1,211 @@
+use crate::helpers::{spawn_app, TestApp};
+use reqwest::multipart::{Form, Part};
+
+async fn create_note(app: &TestApp) -> String {
+    let response = app
+        .post_note(&serde_json::json!({"title": "Trip", "content": "Photos"}))
+        .await;
+    let created: serde_json::Value = response.json().await.unwrap();
+    created["note_id"].as_str().unwrap().to_string()
+}
+
+fn file_form(filename: &str, content_type: &str, contents: Vec<u8>) -> Form {
+    Form::new().part(
+        "file",
+        Part::bytes(contents)
+            .file_name(filename.to_string())
+            .mime_str(content_type)
+            .unwrap(),
+    )
+}
+
+async fn upload(
+    app: &TestApp,
+    note_id: &str,
+    filename: &str,
+    contents: &[u8],
+) -> serde_json::Value {
+    let response = app
+        .post_attachment(note_id, file_form(filename, "image/png", contents.to_vec()))
+        .await;
+    assert_eq!(201, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+#[tokio::test]
+async fn uploaded_attachments_can_be_listed_and_downloaded() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let note_id = create_note(&app).await;
+
+    let attachment = upload(&app, &note_id, "beach.png", b"not really a png").await;
+    assert_eq!(attachment["filename"], "beach.png");
+    assert_eq!(attachment["content_type"], "image/png");
+    assert_eq!(attachment["size_bytes"], 16);
+    let attachment_id = attachment["attachment_id"].as_str().unwrap();
+
+    let listed: serde_json::Value = app.get_attachments(&note_id).await.json().await.unwrap();
+    assert_eq!(listed, serde_json::json!([attachment]));
+
+    let response = app.get_attachment(&note_id, attachment_id).await;
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(response.headers()["content-type"], "image/png");
+    assert_eq!(
+        response.headers()["content-disposition"],
+        "attachment; filename=\"beach.png\""
+    );
+    assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+    assert_eq!(
+        response.bytes().await.unwrap().as_ref(),
+        b"not really a png"
+    );
+}
+
+#[tokio::test]
+async fn deleting_an_attachment_removes_the_stored_file() {
+    let app = spawn_app().await;
+    let user = app.test_user().await;
+    let note_id = create_note(&app).await;
+    let attachment = upload(&app, &note_id, "beach.png", b"png").await;
+    let attachment_id = attachment["attachment_id"].as_str().unwrap();
+
+    let response = app.delete_attachment(&note_id, attachment_id).await;
+    assert_eq!(204, response.status().as_u16());
+
+    let response = app.get_attachment(&note_id, attachment_id).await;
+    assert_eq!(404, response.status().as_u16());
+    let listed: serde_json::Value = app.get_attachments(&note_id).await.json().await.unwrap();
+    assert_eq!(listed, serde_json::json!([]));
+
+    let storage_key = format!("{}/{}", user.user_id, attachment_id);
+    assert!(app.storage.get(&storage_key).await.unwrap().is_none());
+    let response = app.delete_attachment(&note_id, attachment_id).await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn storage_usage_is_accounted_per_user() {
+    let app = spawn_app().await;
+    let _alice = app.test_user_with_email("alice@example.com").await;
+    let note_id = create_note(&app).await;
+    upload(&app, &note_id, "a.png", &[0; 100]).await;
+    let attachment = upload(&app, &note_id, "b.png", &[0; 50]).await;
+
+    let me: serde_json::Value = app.get_current_user().await.json().await.unwrap();
+    assert_eq!(me["storage_used_bytes"], 150);
+
+    app.delete_attachment(&note_id, attachment["attachment_id"].as_str().unwrap())
+        .await;
+    let me: serde_json::Value = app.get_current_user().await.json().await.unwrap();
+    assert_eq!(me["storage_used_bytes"], 100);
+
+    let _bob = app.test_user_with_email("bob@example.com").await;
+    let me: serde_json::Value = app.get_current_user().await.json().await.unwrap();
+    assert_eq!(me["storage_used_bytes"], 0);
+}
+
+#[tokio::test]
+async fn emptying_the_trash_deletes_attachments() {
+    let app = spawn_app().await;
+    let user = app.test_user().await;
+    let note_id = create_note(&app).await;
+    let attachment = upload(&app, &note_id, "beach.png", b"png").await;
+    let storage_key = format!(
+        "{}/{}",
+        user.user_id,
+        attachment["attachment_id"].as_str().unwrap()
+    );
+
+    app.delete_note(&note_id).await;
+    // Trashed notes keep their attachments, but hide them
+    let response = app.get_attachments(&note_id).await;
+    assert_eq!(404, response.status().as_u16());
+    let me: serde_json::Value = app.get_current_user().await.json().await.unwrap();
+    assert_eq!(me["storage_used_bytes"], 3);
+
+    app.empty_trash().await;
+    let me: serde_json::Value = app.get_current_user().await.json().await.unwrap();
+    assert_eq!(me["storage_used_bytes"], 0);
+    assert!(app.storage.get(&storage_key).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn attachments_larger_than_the_payload_limit_are_rejected() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let note_id = create_note(&app).await;
+
+    let form = file_form("huge.bin", "application/octet-stream", vec![0; 10_485_761]);
+    let response = app.post_attachment(&note_id, form).await;
+    assert_eq!(413, response.status().as_u16());
+
+    let listed: serde_json::Value = app.get_attachments(&note_id).await.json().await.unwrap();
+    assert_eq!(listed, serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn invalid_uploads_are_rejected() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let note_id = create_note(&app).await;
+
+    let test_cases = vec![
+        (Form::new().text("title", "no file"), "missing file field"),
+        (
+            Form::new().part("file", Part::bytes(b"png".to_vec())),
+            "missing filename",
+        ),
+        (
+            file_form("..", "image/png", b"png".to_vec()),
+            "invalid filename",
+        ),
+    ];
+
+    for (form, description) in test_cases {
+        let response = app.post_attachment(&note_id, form).await;
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not reject an upload with {}",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn attachments_of_other_users_notes_are_not_found() {
+    let app = spawn_app().await;
+    let _alice = app.test_user_with_email("alice@example.com").await;
+    let note_id = create_note(&app).await;
+    let attachment = upload(&app, &note_id, "private.png", b"png").await;
+    let attachment_id = attachment["attachment_id"].as_str().unwrap();
+
+    let _bob = app.test_user_with_email("bob@example.com").await;
+    let form = file_form("mine.png", "image/png", b"png".to_vec());
+    assert_eq!(
+        404,
+        app.post_attachment(&note_id, form).await.status().as_u16()
+    );
+    assert_eq!(404, app.get_attachments(&note_id).await.status().as_u16());
+    assert_eq!(
+        404,
+        app.get_attachment(&note_id, attachment_id)
+            .await
+            .status()
+            .as_u16()
+    );
+    assert_eq!(
+        404,
+        app.delete_attachment(&note_id, attachment_id)
+            .await
+            .status()
+            .as_u16()
+    );
+    assert_eq!(
+        400,
+        app.get_attachment(&note_id, "not-a-uuid")
+            .await
+            .status()
+            .as_u16()
+    );
+}
@@ -0,0 +1,242 @@
+use crate::helpers::{spawn_app, TestApp};
+
+async fn create_note(app: &TestApp, title: &str) -> String {
+    let response = app
+        .post_note(&serde_json::json!({"title": title, "content": "Content"}))
+        .await;
+    let created: serde_json::Value = response.json().await.unwrap();
+    created["note_id"].as_str().unwrap().to_string()
+}
+
+/// Tag names of a note. Notes without tags leave the field out.
+async fn note_tags(app: &TestApp, note_id: &str) -> serde_json::Value {
+    let note: serde_json::Value = app.get_note_by_id(note_id).await.json().await.unwrap();
+    note.get("tags").cloned().unwrap_or(serde_json::json!([]))
+}
+
+async fn bulk(app: &TestApp, body: serde_json::Value) -> serde_json::Value {
+    let response = app.post_bulk_notes(&body).await;
+    assert_eq!(200, response.status().as_u16());
+    response.json().await.unwrap()
+}
+
+#[tokio::test]
+async fn bulk_delete_moves_the_listed_notes_to_the_trash() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let first = create_note(&app, "First").await;
+    let second = create_note(&app, "Second").await;
+    let kept = create_note(&app, "Kept").await;
+
+    let result = bulk(
+        &app,
+        serde_json::json!({"action": "delete", "note_ids": [first, second]}),
+    )
+    .await;
+    assert_eq!(result["action"], "delete");
+    assert_eq!(result["matched_count"], 2);
+    assert_eq!(result["changed_count"], 2);
+    assert_eq!(result["not_found"], serde_json::json!([]));
+
+    assert_eq!(404, app.get_note_by_id(&first).await.status().as_u16());
+    assert_eq!(404, app.get_note_by_id(&second).await.status().as_u16());
+    assert_eq!(200, app.get_note_by_id(&kept).await.status().as_u16());
+    let trash: serde_json::Value = app.get_trash().await.json().await.unwrap();
+    assert_eq!(trash.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn tags_can_be_added_and_removed_in_bulk() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let first = create_note(&app, "First").await;
+    let second = create_note(&app, "Second").await;
+
+    let result = bulk(
+        &app,
+        serde_json::json!({"action": "add_tag", "note_ids": [first], "tag": "work"}),
+    )
+    .await;
+    assert_eq!(result["changed_count"], 1);
+
+    // The tag exists now, and only the second note gains it
+    let result = bulk(
+        &app,
+        serde_json::json!({"action": "add_tag", "note_ids": [first, second], "tag": "work"}),
+    )
+    .await;
+    assert_eq!(result["matched_count"], 2);
+    assert_eq!(result["changed_count"], 1);
+    assert_eq!(note_tags(&app, &second).await, serde_json::json!(["work"]));
+
+    let result = bulk(
+        &app,
+        serde_json::json!({"action": "remove_tag", "note_ids": [first, second], "tag": "work"}),
+    )
+    .await;
+    assert_eq!(result["changed_count"], 2);
+    assert_eq!(note_tags(&app, &first).await, serde_json::json!([]));
+    assert_eq!(note_tags(&app, &second).await, serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn replace_tags_sets_the_exact_tag_set() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let first = create_note(&app, "First").await;
+    let second = create_note(&app, "Second").await;
+    bulk(
+        &app,
+        serde_json::json!({"action": "add_tag", "note_ids": [first], "tag": "old"}),
+    )
+    .await;
+    bulk(
+        &app,
+        serde_json::json!({"action": "add_tag", "note_ids": [second], "tag": "ideas"}),
+    )
+    .await;
+
+    let result = bulk(
+        &app,
+        serde_json::json!({
+            "action": "replace_tags",
+            "note_ids": [first, second],
+            "tags": ["ideas", "work"]
+        }),
+    )
+    .await;
+    assert_eq!(result["changed_count"], 2);
+    assert_eq!(
+        note_tags(&app, &first).await,
+        serde_json::json!(["ideas", "work"])
+    );
+    assert_eq!(
+        note_tags(&app, &second).await,
+        serde_json::json!(["ideas", "work"])
+    );
+
+    let result = bulk(
+        &app,
+        serde_json::json!({"action": "replace_tags", "note_ids": [first], "tags": []}),
+    )
+    .await;
+    assert_eq!(result["changed_count"], 1);
+    assert_eq!(note_tags(&app, &first).await, serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn bulk_actions_can_select_notes_with_a_filter() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let work = create_note(&app, "Work plan").await;
+    let other = create_note(&app, "Groceries").await;
+    bulk(
+        &app,
+        serde_json::json!({"action": "add_tag", "note_ids": [work], "tag": "work"}),
+    )
+    .await;
+
+    let result = bulk(
+        &app,
+        serde_json::json!({"action": "delete", "filter": {"tags": "work"}}),
+    )
+    .await;
+    assert_eq!(result["matched_count"], 1);
+    assert_eq!(404, app.get_note_by_id(&work).await.status().as_u16());
+    assert_eq!(200, app.get_note_by_id(&other).await.status().as_u16());
+}
+
+#[tokio::test]
+async fn notes_of_other_users_are_reported_and_left_alone() {
+    let app = spawn_app().await;
+    let _alice = app.test_user_with_email("alice@example.com").await;
+    let alices = create_note(&app, "Alice's note").await;
+
+    let _bob = app.test_user_with_email("bob@example.com").await;
+    let bobs = create_note(&app, "Bob's note").await;
+    let result = bulk(
+        &app,
+        serde_json::json!({"action": "delete", "note_ids": [alices, bobs]}),
+    )
+    .await;
+    assert_eq!(result["matched_count"], 1);
+    assert_eq!(result["not_found"], serde_json::json!([alices]));
+
+    // A filter only ever matches the user's own notes
+    let result = bulk(
+        &app,
+        serde_json::json!({"action": "add_tag", "filter": {}, "tag": "mine"}),
+    )
+    .await;
+    assert_eq!(result["matched_count"], 0);
+
+    let _alice = app.test_user_with_email("alice@example.com").await;
+    assert_eq!(200, app.get_note_by_id(&alices).await.status().as_u16());
+    assert_eq!(note_tags(&app, &alices).await, serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn invalid_bulk_requests_are_rejected() {
+    let app = spawn_app().await;
+    let note_id = uuid::Uuid::new_v4().to_string();
+
+    let response = app
+        .post_bulk_notes(&serde_json::json!({"action": "delete", "note_ids": [note_id]}))
+        .await;
+    assert_eq!(401, response.status().as_u16());
+
+    let _user = app.test_user().await;
+    for (body, description) in [
+        (
+            serde_json::json!({"action": "archive", "note_ids": [note_id]}),
+            "unknown action",
+        ),
+        (serde_json::json!({"action": "delete"}), "no selection"),
+        (
+            serde_json::json!({"action": "delete", "note_ids": [note_id], "filter": {}}),
+            "both selections",
+        ),
+        (
+            serde_json::json!({"action": "delete", "note_ids": ["nope"]}),
+            "invalid note ID",
+        ),
+        (
+            serde_json::json!({"action": "add_tag", "note_ids": [note_id]}),
+            "missing tag",
+        ),
+        (
+            serde_json::json!({"action": "add_tag", "note_ids": [note_id], "tag": "not valid!"}),
+            "invalid tag",
+        ),
+        (
+            serde_json::json!({"action": "replace_tags", "note_ids": [note_id]}),
+            "missing tags",
+        ),
+        (
+            serde_json::json!({"action": "delete", "filter": {"tag_mode": "some"}}),
+            "invalid filter",
+        ),
+    ] {
+        let response = app.post_bulk_notes(&body).await;
+        assert_eq!(400, response.status().as_u16(), "Accepted {}", description);
+    }
+}
+
+#[tokio::test]
+async fn bulk_retagging_changes_the_etag_of_changed_notes() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let note_id = create_note(&app, "First").await;
+    let etag = app.get_note_by_id(&note_id).await.headers()["ETag"].clone();
+
+    bulk(
+        &app,
+        serde_json::json!({"action": "add_tag", "note_ids": [note_id], "tag": "work"}),
+    )
+    .await;
+
+    let response = app
+        .get_note_if_none_match(&note_id, etag.to_str().unwrap())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+}
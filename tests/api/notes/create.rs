@@ -83,4 +83,48 @@ async fn create_note_with_missing_fields_fails() {
             description
         );
     }
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn create_note_with_tags_creates_missing_tags() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    app.post_tag(&serde_json::json!({"name": "work"})).await;
+
+    let note = serde_json::json!({
+        "title": "Title",
+        "content": "Content",
+        "tags": ["Work", "ideas", "work"]
+    });
+    let response = app.post_note(&note).await;
+    assert_eq!(201, response.status().as_u16());
+    let created: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(created["tags"], serde_json::json!(["ideas", "work"]));
+
+    let fetched: serde_json::Value = app
+        .get_note_by_id(created["note_id"].as_str().unwrap())
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(fetched["tags"], serde_json::json!(["ideas", "work"]));
+    let tags: Vec<serde_json::Value> = app.get_tags().await.json().await.unwrap();
+    assert_eq!(tags.len(), 2);
+}
+
+#[tokio::test]
+async fn create_note_with_an_invalid_tag_creates_nothing() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let note = serde_json::json!({
+        "title": "Title",
+        "content": "Content",
+        "tags": ["ideas", "not valid!"]
+    });
+    let response = app.post_note(&note).await;
+    assert_eq!(400, response.status().as_u16());
+
+    let tags: Vec<serde_json::Value> = app.get_tags().await.json().await.unwrap();
+    assert!(tags.is_empty());
+}
@@ -0,0 +1,137 @@
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn cursor_pagination_walks_every_note_once() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    for i in 1..=5 {
+        app.post_note(&serde_json::json!({
+            "title": format!("Note {}", i),
+            "content": "Content"
+        }))
+        .await;
+    }
+
+    let mut seen = Vec::new();
+    let mut cursor = String::new();
+    loop {
+        let response = app.get_notes_with_cursor("page_size=2", &cursor).await;
+        assert_eq!(200, response.status().as_u16());
+
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert!(body.get("total_count").is_none());
+        for note in body["notes"].as_array().unwrap() {
+            seen.push(note["title"].as_str().unwrap().to_string());
+        }
+
+        match body["next_cursor"].as_str() {
+            Some(next) => cursor = next.to_string(),
+            None => break,
+        }
+    }
+
+    assert_eq!(seen, vec!["Note 5", "Note 4", "Note 3", "Note 2", "Note 1"]);
+}
+
+#[tokio::test]
+async fn notes_created_while_paging_do_not_cause_duplicates() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    for title in ["Alpha", "Bravo", "Charlie", "Delta"] {
+        app.post_note(&serde_json::json!({"title": title, "content": "Content"}))
+            .await;
+    }
+
+    let query = "page_size=2&sort=title&order=asc";
+    let first: serde_json::Value = app
+        .get_notes_with_cursor(query, "")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(first["notes"][0]["title"], "Alpha");
+    assert_eq!(first["notes"][1]["title"], "Bravo");
+
+    // A note sorting before the cursor must not shift the next page
+    app.post_note(&serde_json::json!({"title": "Aardvark", "content": "Content"}))
+        .await;
+
+    let second: serde_json::Value = app
+        .get_notes_with_cursor(query, first["next_cursor"].as_str().unwrap())
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(second["notes"][0]["title"], "Charlie");
+    assert_eq!(second["notes"][1]["title"], "Delta");
+    assert!(second["next_cursor"].is_null());
+}
+
+#[tokio::test]
+async fn cursor_pagination_respects_filters() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    for i in 1..=3 {
+        app.post_note(&serde_json::json!({
+            "title": format!("Shopping {}", i),
+            "content": "Buy groceries"
+        }))
+        .await;
+    }
+    app.post_note(&serde_json::json!({"title": "Work", "content": "Meeting"}))
+        .await;
+
+    let first: serde_json::Value = app
+        .get_notes_with_cursor("page_size=2&search=groceries", "")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(first["notes"].as_array().unwrap().len(), 2);
+
+    let second: serde_json::Value = app
+        .get_notes_with_cursor(
+            "page_size=2&search=groceries",
+            first["next_cursor"].as_str().unwrap(),
+        )
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(second["notes"].as_array().unwrap().len(), 1);
+    assert!(second["next_cursor"].is_null());
+}
+
+#[tokio::test]
+async fn invalid_or_mismatched_cursor_returns_400() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    for i in 1..=3 {
+        app.post_note(&serde_json::json!({
+            "title": format!("Note {}", i),
+            "content": "Content"
+        }))
+        .await;
+    }
+
+    let response = app.get_notes_with_cursor("page_size=1", "garbage").await;
+    assert_eq!(400, response.status().as_u16());
+
+    let first: serde_json::Value = app
+        .get_notes_with_cursor("page_size=1", "")
+        .await
+        .json()
+        .await
+        .unwrap();
+    let response = app
+        .get_notes_with_cursor(
+            "page_size=1&sort=title",
+            first["next_cursor"].as_str().unwrap(),
+        )
+        .await;
+    assert_eq!(400, response.status().as_u16());
+}
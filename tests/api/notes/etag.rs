@@ -0,0 +1,144 @@
+use crate::helpers::spawn_app;
+
+fn etag_of(response: &reqwest::Response) -> String {
+    response
+        .headers()
+        .get("ETag")
+        .expect("Missing ETag header")
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[tokio::test]
+async fn create_get_and_update_return_an_etag() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let create_response = app
+        .post_note(&serde_json::json!({"title": "Note", "content": "Content"}))
+        .await;
+    let created_etag = etag_of(&create_response);
+    let created: serde_json::Value = create_response.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+
+    let get_response = app.get_note_by_id(note_id).await;
+    assert_eq!(created_etag, etag_of(&get_response));
+
+    let update_response = app
+        .put_note(note_id, &serde_json::json!({"content": "Changed"}))
+        .await;
+    assert_ne!(created_etag, etag_of(&update_response));
+}
+
+#[tokio::test]
+async fn update_with_stale_etag_returns_412() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let create_response = app
+        .post_note(&serde_json::json!({"title": "Note", "content": "Content"}))
+        .await;
+    let stale_etag = etag_of(&create_response);
+    let created: serde_json::Value = create_response.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+
+    // The first device wins
+    let response = app
+        .put_note_if_match(
+            note_id,
+            &stale_etag,
+            &serde_json::json!({"content": "First device"}),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+
+    // The second device still holds the old ETag
+    let response = app
+        .put_note_if_match(
+            note_id,
+            &stale_etag,
+            &serde_json::json!({"content": "Second device"}),
+        )
+        .await;
+    assert_eq!(412, response.status().as_u16());
+
+    let note: serde_json::Value = app.get_note_by_id(note_id).await.json().await.unwrap();
+    assert_eq!(note["content"], "First device");
+}
+
+#[tokio::test]
+async fn delete_with_stale_etag_returns_412() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let create_response = app
+        .post_note(&serde_json::json!({"title": "Note", "content": "Content"}))
+        .await;
+    let stale_etag = etag_of(&create_response);
+    let created: serde_json::Value = create_response.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+
+    let update_response = app
+        .put_note(note_id, &serde_json::json!({"content": "Changed"}))
+        .await;
+    let current_etag = etag_of(&update_response);
+
+    let response = app.delete_note_if_match(note_id, &stale_etag).await;
+    assert_eq!(412, response.status().as_u16());
+
+    let response = app.delete_note_if_match(note_id, &current_etag).await;
+    assert_eq!(204, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn get_with_current_etag_returns_304() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let create_response = app
+        .post_note(&serde_json::json!({"title": "Note", "content": "Content"}))
+        .await;
+    let etag = etag_of(&create_response);
+    let created: serde_json::Value = create_response.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+
+    let response = app.get_note_if_none_match(note_id, &etag).await;
+    assert_eq!(304, response.status().as_u16());
+
+    app.put_note(note_id, &serde_json::json!({"content": "Changed"}))
+        .await;
+
+    let response = app.get_note_if_none_match(note_id, &etag).await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn tagging_a_note_changes_its_etag() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let create_response = app
+        .post_note(&serde_json::json!({"title": "Note", "content": "Content"}))
+        .await;
+    let etag = etag_of(&create_response);
+    let created: serde_json::Value = create_response.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+    let tag: serde_json::Value = app
+        .post_tag(&serde_json::json!({"name": "work"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let tag_id = tag["tag_id"].as_str().unwrap();
+
+    app.add_tag_to_note(note_id, tag_id).await;
+    let response = app.get_note_if_none_match(note_id, &etag).await;
+    assert_eq!(200, response.status().as_u16());
+    let tagged_etag = etag_of(&response);
+    assert_ne!(etag, tagged_etag);
+
+    app.remove_tag_from_note(note_id, tag_id).await;
+    let response = app.get_note_if_none_match(note_id, &tagged_etag).await;
+    assert_eq!(200, response.status().as_u16());
+}
@@ -0,0 +1,130 @@
+use crate::helpers::{spawn_app, TestApp};
+
+async fn create_note(app: &TestApp, title: &str) -> String {
+    let response = app
+        .post_note(&serde_json::json!({"title": title, "content": "Content"}))
+        .await;
+    let created: serde_json::Value = response.json().await.unwrap();
+    created["note_id"].as_str().unwrap().to_string()
+}
+
+async fn listed_titles(app: &TestApp, query: &str) -> Vec<String> {
+    let response = app.get_notes_with_query(query).await;
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    body["notes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|note| note["title"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn pinned_notes_are_listed_first_regardless_of_sort() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    create_note(&app, "Alpha").await;
+    let bravo = create_note(&app, "Bravo").await;
+    create_note(&app, "Charlie").await;
+
+    let response = app.pin_note(&bravo).await;
+    assert_eq!(204, response.status().as_u16());
+
+    let titles = listed_titles(&app, "sort=title&order=asc").await;
+    assert_eq!(titles, vec!["Bravo", "Alpha", "Charlie"]);
+    let titles = listed_titles(&app, "sort=title&order=desc").await;
+    assert_eq!(titles, vec!["Bravo", "Charlie", "Alpha"]);
+
+    let note: serde_json::Value = app.get_note_by_id(&bravo).await.json().await.unwrap();
+    assert_eq!(note["pinned"], true);
+
+    let response = app.unpin_note(&bravo).await;
+    assert_eq!(204, response.status().as_u16());
+    let titles = listed_titles(&app, "sort=title&order=asc").await;
+    assert_eq!(titles, vec!["Alpha", "Bravo", "Charlie"]);
+}
+
+#[tokio::test]
+async fn cursor_pagination_keeps_pinned_notes_first() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    for title in ["Alpha", "Bravo", "Charlie", "Delta"] {
+        let note_id = create_note(&app, title).await;
+        if title == "Charlie" {
+            app.pin_note(&note_id).await;
+        }
+    }
+
+    let mut seen = Vec::new();
+    let mut cursor = String::new();
+    loop {
+        let body: serde_json::Value = app
+            .get_notes_with_cursor("page_size=1&sort=title&order=asc", &cursor)
+            .await
+            .json()
+            .await
+            .unwrap();
+        for note in body["notes"].as_array().unwrap() {
+            seen.push(note["title"].as_str().unwrap().to_string());
+        }
+        match body["next_cursor"].as_str() {
+            Some(next) => cursor = next.to_string(),
+            None => break,
+        }
+    }
+
+    assert_eq!(seen, vec!["Charlie", "Alpha", "Bravo", "Delta"]);
+}
+
+#[tokio::test]
+async fn archived_notes_are_hidden_unless_requested() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    create_note(&app, "Current").await;
+    let stale = create_note(&app, "Stale").await;
+
+    let response = app.archive_note(&stale).await;
+    assert_eq!(204, response.status().as_u16());
+
+    assert_eq!(listed_titles(&app, "").await, vec!["Current"]);
+    assert_eq!(
+        listed_titles(&app, "archived=include&sort=title&order=asc").await,
+        vec!["Current", "Stale"]
+    );
+    assert_eq!(listed_titles(&app, "archived=only").await, vec!["Stale"]);
+
+    let note: serde_json::Value = app.get_note_by_id(&stale).await.json().await.unwrap();
+    assert_eq!(note["archived"], true);
+
+    let response = app.unarchive_note(&stale).await;
+    assert_eq!(204, response.status().as_u16());
+    assert_eq!(
+        listed_titles(&app, "archived=only").await,
+        Vec::<String>::new()
+    );
+}
+
+#[tokio::test]
+async fn invalid_archived_filter_is_rejected() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let response = app.get_notes_with_query("archived=hidden").await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn flags_cannot_be_set_on_missing_or_foreign_notes() {
+    let app = spawn_app().await;
+    let _alice = app.test_user_with_email("alice@example.com").await;
+    let note_id = create_note(&app, "Private").await;
+
+    let _bob = app.test_user_with_email("bob@example.com").await;
+    assert_eq!(404, app.pin_note(&note_id).await.status().as_u16());
+    assert_eq!(404, app.archive_note(&note_id).await.status().as_u16());
+    assert_eq!(400, app.pin_note("not-a-uuid").await.status().as_u16());
+}
@@ -1,4 +1,4 @@
-use crate::helpers::spawn_app;
+use crate::helpers::{spawn_app, TestApp};
 
 #[tokio::test]
 async fn get_note_requires_authentication() {
@@ -96,3 +96,120 @@ async fn get_note_returns_all_fields() {
     assert!(fetched["created_at"].is_string());
     assert!(fetched["updated_at"].is_string());
 }
+
+#[tokio::test]
+async fn get_note_includes_its_tags() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let create_response = app
+        .post_note(&serde_json::json!({"title": "Tagged", "content": "Content"}))
+        .await;
+    let created: serde_json::Value = create_response.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+
+    for name in ["work", "ideas"] {
+        let tag: serde_json::Value = app
+            .post_tag(&serde_json::json!({"name": name}))
+            .await
+            .json()
+            .await
+            .unwrap();
+        app.add_tag_to_note(note_id, tag["tag_id"].as_str().unwrap())
+            .await;
+    }
+
+    let response = app.get_note_by_id(note_id).await;
+    let fetched: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(fetched["tags"], serde_json::json!(["ideas", "work"]));
+}
+
+async fn create_markdown_note(app: &TestApp) -> String {
+    let created: serde_json::Value = app
+        .post_note(&serde_json::json!({
+            "title": "Rendered",
+            "content": "# Plan\n\nShip **it**\n\n<script>alert(1)</script>\n\n<a href=\"#\" onclick=\"alert(1)\">x</a>"
+        }))
+        .await
+        .json()
+        .await
+        .unwrap();
+    created["note_id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn get_note_renders_sanitized_html() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let note_id = create_markdown_note(&app).await;
+
+    let response = app.get_note_with_accept(&note_id, "text/html").await;
+    assert_eq!(200, response.status().as_u16());
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+    assert_eq!(response.headers()["vary"], "Accept");
+
+    let html = response.text().await.unwrap();
+    assert!(html.contains("<h1>Plan</h1>"));
+    assert!(html.contains("<strong>it</strong>"));
+    assert!(!html.contains("<script"));
+    assert!(!html.contains("onclick"));
+}
+
+#[tokio::test]
+async fn get_note_returns_raw_markdown_and_plain_text() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let note_id = create_markdown_note(&app).await;
+
+    let response = app.get_note_with_accept(&note_id, "text/markdown").await;
+    assert_eq!(200, response.status().as_u16());
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .starts_with("# Plan\n\nShip **it**"));
+
+    let response = app.get_note_with_accept(&note_id, "text/plain").await;
+    assert_eq!(200, response.status().as_u16());
+    assert!(response.text().await.unwrap().starts_with("Plan\nShip it"));
+
+    let response = app
+        .get_note_with_accept(&note_id, "text/html;q=0.5, application/json")
+        .await;
+    let fetched: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(fetched["title"], "Rendered");
+}
+
+#[tokio::test]
+async fn get_note_rejects_unsupported_media_types() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let note_id = create_markdown_note(&app).await;
+
+    let response = app.get_note_with_accept(&note_id, "application/pdf").await;
+    assert_eq!(406, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn each_representation_has_its_own_etag() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let note_id = create_markdown_note(&app).await;
+
+    let mut etags = Vec::new();
+    for accept in [
+        "application/json",
+        "text/html",
+        "text/markdown",
+        "text/plain",
+    ] {
+        let response = app.get_note_with_accept(&note_id, accept).await;
+        etags.push(response.headers()["etag"].to_str().unwrap().to_string());
+    }
+    etags.sort();
+    etags.dedup();
+    assert_eq!(etags.len(), 4);
+}
@@ -0,0 +1,136 @@
+use crate::helpers::{spawn_app, TestApp};
+
+async fn create_note(app: &TestApp, title: &str, content: &str) -> String {
+    let response = app
+        .post_note(&serde_json::json!({"title": title, "content": content}))
+        .await;
+    assert_eq!(201, response.status().as_u16());
+    let created: serde_json::Value = response.json().await.unwrap();
+    created["note_id"].as_str().unwrap().to_string()
+}
+
+fn titles(links: &serde_json::Value) -> Vec<&str> {
+    links
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|link| link["title"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn title_and_id_links_resolve_to_notes() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let roadmap = create_note(&app, "Roadmap", "Plans").await;
+    let budget = create_note(&app, "Budget", "Numbers").await;
+    let source = create_note(
+        &app,
+        "Index",
+        &format!("See [[roadmap]] and [[note:{}]]", budget),
+    )
+    .await;
+
+    let response = app.get_note_links(&source).await;
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(titles(&body["links"]), vec!["Budget", "Roadmap"]);
+    assert_eq!(body["dangling"], serde_json::json!([]));
+
+    let backlinks: serde_json::Value = app.get_note_backlinks(&roadmap).await.json().await.unwrap();
+    assert_eq!(titles(&backlinks), vec!["Index"]);
+    let backlinks: serde_json::Value = app.get_note_backlinks(&budget).await.json().await.unwrap();
+    assert_eq!(titles(&backlinks), vec!["Index"]);
+}
+
+#[tokio::test]
+async fn links_to_missing_notes_are_reported_as_dangling() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let missing_id = uuid::Uuid::new_v4();
+    let source = create_note(
+        &app,
+        "Index",
+        &format!("[[Someday]] and [[note:{}]]", missing_id),
+    )
+    .await;
+
+    let body: serde_json::Value = app.get_note_links(&source).await.json().await.unwrap();
+    assert_eq!(body["links"], serde_json::json!([]));
+    let mut dangling: Vec<&str> = body["dangling"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|link| link.as_str().unwrap())
+        .collect();
+    dangling.sort();
+    let expected_id = format!("note:{}", missing_id);
+    assert_eq!(dangling, vec!["Someday", expected_id.as_str()]);
+
+    // Creating the target resolves the link
+    create_note(&app, "Someday", "Later").await;
+    let body: serde_json::Value = app.get_note_links(&source).await.json().await.unwrap();
+    assert_eq!(titles(&body["links"]), vec!["Someday"]);
+    assert_eq!(body["dangling"], serde_json::json!([expected_id]));
+}
+
+#[tokio::test]
+async fn updating_content_replaces_links() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let first = create_note(&app, "First", "Content").await;
+    create_note(&app, "Second", "Content").await;
+    let source = create_note(&app, "Index", "[[First]]").await;
+
+    app.put_note(&source, &serde_json::json!({"content": "[[Second]]"}))
+        .await;
+
+    let body: serde_json::Value = app.get_note_links(&source).await.json().await.unwrap();
+    assert_eq!(titles(&body["links"]), vec!["Second"]);
+    let backlinks: serde_json::Value = app.get_note_backlinks(&first).await.json().await.unwrap();
+    assert_eq!(backlinks, serde_json::json!([]));
+
+    // Restoring a revision brings its links back
+    app.restore_note_revision(&source, "1").await;
+    let body: serde_json::Value = app.get_note_links(&source).await.json().await.unwrap();
+    assert_eq!(titles(&body["links"]), vec!["First"]);
+}
+
+#[tokio::test]
+async fn trashed_notes_do_not_resolve_or_link_back() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let target = create_note(&app, "Target", "Content").await;
+    let source = create_note(&app, "Index", "[[Target]]").await;
+
+    app.delete_note(&source).await;
+    let backlinks: serde_json::Value = app.get_note_backlinks(&target).await.json().await.unwrap();
+    assert_eq!(backlinks, serde_json::json!([]));
+
+    app.restore_note(&source).await;
+    app.delete_note(&target).await;
+    let body: serde_json::Value = app.get_note_links(&source).await.json().await.unwrap();
+    assert_eq!(body["dangling"], serde_json::json!(["Target"]));
+}
+
+#[tokio::test]
+async fn links_of_other_users_notes_are_not_found() {
+    let app = spawn_app().await;
+    let _alice = app.test_user_with_email("alice@example.com").await;
+    let note_id = create_note(&app, "Private", "[[Secret]]").await;
+
+    let _bob = app.test_user_with_email("bob@example.com").await;
+    assert_eq!(404, app.get_note_links(&note_id).await.status().as_u16());
+    assert_eq!(
+        404,
+        app.get_note_backlinks(&note_id).await.status().as_u16()
+    );
+    assert_eq!(
+        400,
+        app.get_note_links("not-a-uuid").await.status().as_u16()
+    );
+}
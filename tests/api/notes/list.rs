@@ -1,4 +1,4 @@
-use crate::helpers::spawn_app;
+use crate::helpers::{spawn_app, TestApp};
 
 #[tokio::test]
 async fn list_notes_requires_authentication() {
@@ -121,3 +121,44 @@ async fn pagination_handles_page_size_limits() {
     let body: serde_json::Value = response.json().await.unwrap();
     assert_eq!(body["page_size"], 1);
 }
+
+#[tokio::test]
+async fn listing_notes_uses_a_constant_number_of_queries() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let tag_response = app.post_tag(&serde_json::json!({"name": "work"})).await;
+    let tag: serde_json::Value = tag_response.json().await.unwrap();
+    let tag_id = tag["tag_id"].as_str().unwrap().to_string();
+
+    create_tagged_notes(&app, &tag_id, 2).await;
+    let before = app.db_checkout_count();
+    let response = app.get_notes(Some(1), Some(100)).await;
+    let small_page_queries = app.db_checkout_count() - before;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["notes"].as_array().unwrap().len(), 2);
+
+    create_tagged_notes(&app, &tag_id, 30).await;
+    let before = app.db_checkout_count();
+    let response = app.get_notes(Some(1), Some(100)).await;
+    let large_page_queries = app.db_checkout_count() - before;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["notes"].as_array().unwrap().len(), 32);
+    assert_eq!(body["notes"][0]["tags"][0], "work");
+
+    assert_eq!(small_page_queries, large_page_queries);
+}
+
+async fn create_tagged_notes(app: &TestApp, tag_id: &str, count: usize) {
+    for i in 0..count {
+        let response = app
+            .post_note(&serde_json::json!({
+                "title": format!("Note {}", i),
+                "content": "Content"
+            }))
+            .await;
+        let note: serde_json::Value = response.json().await.unwrap();
+        app.add_tag_to_note(note["note_id"].as_str().unwrap(), tag_id)
+            .await;
+    }
+}
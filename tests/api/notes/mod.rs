@@ -1,6 +1,15 @@
+mod attachments;
+mod bulk;
 mod create;
+mod cursor;
 mod delete;
+mod etag;
+mod flags;
 mod get;
+mod links;
 mod list;
+mod patch;
+mod revisions;
 mod search;
+mod tag_filter;
 mod update;
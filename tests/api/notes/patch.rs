@@ -0,0 +1,180 @@
+use crate::helpers::{spawn_app, TestApp};
+
+const MERGE_PATCH: &str = "application/merge-patch+json";
+const JSON_PATCH: &str = "application/json-patch+json";
+
+async fn create_note(app: &TestApp) -> String {
+    let note = serde_json::json!({
+        "title": "Plan",
+        "content": "Ship it",
+        "tags": ["work"]
+    });
+    let created: serde_json::Value = app.post_note(&note).await.json().await.unwrap();
+    created["note_id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn merge_patch_changes_only_the_given_members() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let note_id = create_note(&app).await;
+
+    let response = app
+        .patch_note(
+            &note_id,
+            MERGE_PATCH,
+            &serde_json::json!({"content": "Ship it on Friday"}),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    assert!(response.headers().contains_key("etag"));
+    let patched: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(patched["title"], "Plan");
+    assert_eq!(patched["content"], "Ship it on Friday");
+    assert_eq!(patched["tags"], serde_json::json!(["work"]));
+
+    // `null` removes the tags
+    let patched: serde_json::Value = app
+        .patch_note(&note_id, MERGE_PATCH, &serde_json::json!({"tags": null}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(patched["tags"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn json_patch_operations_are_applied_in_order() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let note_id = create_note(&app).await;
+
+    let operations = serde_json::json!([
+        {"op": "test", "path": "/title", "value": "Plan"},
+        {"op": "replace", "path": "/title", "value": "Q3 plan"},
+        {"op": "add", "path": "/tags/-", "value": "ideas"},
+        {"op": "copy", "from": "/title", "path": "/content"}
+    ]);
+    let response = app.patch_note(&note_id, JSON_PATCH, &operations).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let note: serde_json::Value = app.get_note_by_id(&note_id).await.json().await.unwrap();
+    assert_eq!(note["title"], "Q3 plan");
+    assert_eq!(note["content"], "Q3 plan");
+    assert_eq!(note["tags"], serde_json::json!(["ideas", "work"]));
+}
+
+#[tokio::test]
+async fn failed_json_patch_changes_nothing() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let note_id = create_note(&app).await;
+
+    let operations = serde_json::json!([
+        {"op": "replace", "path": "/title", "value": "Q3 plan"},
+        {"op": "test", "path": "/content", "value": "Something else"}
+    ]);
+    let response = app.patch_note(&note_id, JSON_PATCH, &operations).await;
+    assert_eq!(422, response.status().as_u16());
+
+    let operations = serde_json::json!([{"op": "remove", "path": "/tags/5"}]);
+    let response = app.patch_note(&note_id, JSON_PATCH, &operations).await;
+    assert_eq!(422, response.status().as_u16());
+
+    let note: serde_json::Value = app.get_note_by_id(&note_id).await.json().await.unwrap();
+    assert_eq!(note["title"], "Plan");
+    assert_eq!(note["tags"], serde_json::json!(["work"]));
+}
+
+#[tokio::test]
+async fn patched_values_are_validated_like_updates() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let note_id = create_note(&app).await;
+
+    for (content_type, patch, description) in [
+        (MERGE_PATCH, serde_json::json!({"title": ""}), "empty title"),
+        (
+            MERGE_PATCH,
+            serde_json::json!({"title": null}),
+            "removed title",
+        ),
+        (
+            MERGE_PATCH,
+            serde_json::json!({"content": 42}),
+            "content not a string",
+        ),
+        (
+            MERGE_PATCH,
+            serde_json::json!({"tags": ["not valid!"]}),
+            "invalid tag",
+        ),
+        (
+            MERGE_PATCH,
+            serde_json::json!({"notebook_id": null}),
+            "unknown member",
+        ),
+        (MERGE_PATCH, serde_json::json!({}), "empty patch"),
+        (
+            JSON_PATCH,
+            serde_json::json!([{"op": "remove", "path": "/content"}]),
+            "removed content",
+        ),
+        (
+            JSON_PATCH,
+            serde_json::json!([{"op": "add", "path": "/version", "value": 1}]),
+            "unknown path",
+        ),
+        (
+            JSON_PATCH,
+            serde_json::json!({"title": "Q3 plan"}),
+            "not a list",
+        ),
+    ] {
+        let response = app.patch_note(&note_id, content_type, &patch).await;
+        assert_eq!(400, response.status().as_u16(), "Accepted {}", description);
+    }
+
+    let note: serde_json::Value = app.get_note_by_id(&note_id).await.json().await.unwrap();
+    assert_eq!(note["title"], "Plan");
+    assert_eq!(note["content"], "Ship it");
+}
+
+#[tokio::test]
+async fn patch_requires_a_patch_content_type() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    let note_id = create_note(&app).await;
+
+    let response = app
+        .patch_note(
+            &note_id,
+            "application/json",
+            &serde_json::json!({"title": "Q3 plan"}),
+        )
+        .await;
+    assert_eq!(415, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn patch_respects_if_match_and_ownership() {
+    let app = spawn_app().await;
+    let _alice = app.test_user_with_email("alice@example.com").await;
+    let note_id = create_note(&app).await;
+    let patch = serde_json::json!({"title": "Q3 plan"});
+
+    let response = app
+        .api_client
+        .patch(&format!("{}/notes/{}", &app.address, note_id))
+        .header("Content-Type", MERGE_PATCH)
+        .header("If-Match", "\"999\"")
+        .body(patch.to_string())
+        .send()
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(412, response.status().as_u16());
+
+    let _bob = app.test_user_with_email("bob@example.com").await;
+    let response = app.patch_note(&note_id, MERGE_PATCH, &patch).await;
+    assert_eq!(404, response.status().as_u16());
+}
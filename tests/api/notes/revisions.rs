@@ -0,0 +1,157 @@
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn list_revisions_requires_authentication() {
+    let app = spawn_app().await;
+
+    let response = app
+        .get_note_revisions("550e8400-e29b-41d4-a716-446655440000")
+        .await;
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn updating_a_note_records_the_previous_version() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let create_response = app
+        .post_note(&serde_json::json!({
+            "title": "Original Title",
+            "content": "Original Content"
+        }))
+        .await;
+    let created: serde_json::Value = create_response.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+
+    app.put_note(note_id, &serde_json::json!({"content": "Second Content"}))
+        .await;
+    app.put_note(note_id, &serde_json::json!({"content": "Third Content"}))
+        .await;
+
+    let response = app.get_note_revisions(note_id).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let revisions: Vec<serde_json::Value> = response.json().await.unwrap();
+    assert_eq!(revisions.len(), 2);
+    assert_eq!(revisions[0]["revision_number"], 2);
+    assert_eq!(revisions[1]["revision_number"], 1);
+
+    let response = app.get_note_revision(note_id, "1").await;
+    assert_eq!(200, response.status().as_u16());
+
+    let revision: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(revision["title"], "Original Title");
+    assert_eq!(revision["content"], "Original Content");
+}
+
+#[tokio::test]
+async fn restoring_a_revision_brings_back_its_content() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let create_response = app
+        .post_note(&serde_json::json!({
+            "title": "Original Title",
+            "content": "Original Content"
+        }))
+        .await;
+    let created: serde_json::Value = create_response.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+
+    app.put_note(
+        note_id,
+        &serde_json::json!({"title": "Bad Title", "content": "Bad Content"}),
+    )
+    .await;
+
+    let response = app.restore_note_revision(note_id, "1").await;
+    assert_eq!(200, response.status().as_u16());
+
+    let restored: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(restored["title"], "Original Title");
+    assert_eq!(restored["content"], "Original Content");
+
+    // The overwritten edit is kept as a revision of its own
+    let response = app.get_note_revision(note_id, "2").await;
+    let revision: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(revision["content"], "Bad Content");
+}
+
+#[tokio::test]
+async fn restoring_a_revision_keeps_the_current_tags() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let create_response = app
+        .post_note(&serde_json::json!({
+            "title": "Original Title",
+            "content": "Original Content",
+            "tags": ["work"]
+        }))
+        .await;
+    let created: serde_json::Value = create_response.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+
+    app.put_note(
+        note_id,
+        &serde_json::json!({"content": "Bad Content", "tags": ["ideas", "work"]}),
+    )
+    .await;
+
+    let response = app.restore_note_revision(note_id, "1").await;
+    assert_eq!(200, response.status().as_u16());
+    let restored: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(restored["content"], "Original Content");
+    assert_eq!(restored["tags"], serde_json::json!(["ideas", "work"]));
+}
+
+#[tokio::test]
+async fn users_cannot_see_revisions_of_other_users_notes() {
+    let app = spawn_app().await;
+
+    let _user1 = app.test_user().await;
+    let create_response = app
+        .post_note(&serde_json::json!({
+            "title": "User 1 Note",
+            "content": "Private content"
+        }))
+        .await;
+    let created: serde_json::Value = create_response.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+    app.put_note(note_id, &serde_json::json!({"content": "Edited"}))
+        .await;
+
+    app.post_logout().await;
+    let _user2 = app.test_user_with_email("user2@example.com").await;
+
+    let response = app.get_note_revisions(note_id).await;
+    assert_eq!(404, response.status().as_u16());
+
+    let response = app.get_note_revision(note_id, "1").await;
+    assert_eq!(404, response.status().as_u16());
+
+    let response = app.restore_note_revision(note_id, "1").await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn get_revision_with_invalid_number_returns_400() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let create_response = app
+        .post_note(&serde_json::json!({
+            "title": "Note",
+            "content": "Content"
+        }))
+        .await;
+    let created: serde_json::Value = create_response.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+
+    let response = app.get_note_revision(note_id, "latest").await;
+    assert_eq!(400, response.status().as_u16());
+
+    let response = app.get_note_revision(note_id, "1").await;
+    assert_eq!(404, response.status().as_u16());
+}
@@ -158,3 +158,275 @@ async fn search_with_no_results() {
     assert_eq!(body["total_count"], 0);
     assert_eq!(body["notes"].as_array().unwrap().len(), 0);
 }
+
+#[tokio::test]
+async fn relevance_sort_puts_the_best_match_first() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    app.post_note(&serde_json::json!({
+        "title": "Passing mention",
+        "content": "We talked about the garden once, then moved on to other topics entirely"
+    }))
+    .await;
+    app.post_note(&serde_json::json!({
+        "title": "Garden plans",
+        "content": "Garden layout, garden tools and garden beds"
+    }))
+    .await;
+    app.post_note(&serde_json::json!({
+        "title": "Unrelated",
+        "content": "Nothing to see here"
+    }))
+    .await;
+
+    let response = app
+        .get_notes_with_query("search=garden&sort=relevance")
+        .await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["total_count"], 2);
+    assert_eq!(body["notes"][0]["title"], "Garden plans");
+    assert_eq!(body["notes"][1]["title"], "Passing mention");
+}
+
+#[tokio::test]
+async fn search_results_include_highlighted_snippets() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    app.post_note(&serde_json::json!({
+        "title": "Groceries",
+        "content": "Remember to buy apples and pears"
+    }))
+    .await;
+
+    let response = app.search_notes("apples").await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    let snippet = body["notes"][0]["snippet"].as_str().unwrap();
+    assert!(snippet.contains("<mark>apples</mark>"));
+
+    let response = app
+        .get_notes_with_query("search=apples&highlight_start=**&highlight_end=**")
+        .await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    let snippet = body["notes"][0]["snippet"].as_str().unwrap();
+    assert!(snippet.contains("**apples**"));
+}
+
+#[tokio::test]
+async fn snippets_are_omitted_without_a_search() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    app.post_note(&serde_json::json!({"title": "Note", "content": "Content"}))
+        .await;
+
+    let response = app.get_notes(None, None).await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["notes"][0].get("snippet").is_none());
+}
+
+#[tokio::test]
+async fn invalid_highlight_markers_are_rejected() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let response = app
+        .get_notes_with_query("search=apples&highlight_start=%22")
+        .await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn relevance_sort_rejects_cursor_pagination() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let response = app
+        .get_notes_with_cursor("search=apples&sort=relevance", "")
+        .await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn advanced_search_syntax_combines_operators() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let note: serde_json::Value = app
+        .post_note(&serde_json::json!({
+            "title": "Team meeting",
+            "content": "Discussed the release plan in detail"
+        }))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let tag: serde_json::Value = app
+        .post_tag(&serde_json::json!({"name": "work"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    app.add_tag_to_note(
+        note["note_id"].as_str().unwrap(),
+        tag["tag_id"].as_str().unwrap(),
+    )
+    .await;
+
+    app.post_note(&serde_json::json!({
+        "title": "Team meeting draft",
+        "content": "Discussed the release plan, draft version"
+    }))
+    .await;
+    app.post_note(&serde_json::json!({
+        "title": "Release notes",
+        "content": "The release plan in detail"
+    }))
+    .await;
+
+    let query = "title:meeting tag:work -draft \"release plan\" before:2999-01-01";
+    let response = app.search_notes(query).await;
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["total_count"], 1);
+    assert_eq!(body["notes"][0]["title"], "Team meeting");
+
+    let response = app.search_notes("\"release plan\" -draft").await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["total_count"], 2);
+
+    let response = app.search_notes("meeting before:2000-01-01").await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["total_count"], 0);
+}
+
+#[tokio::test]
+async fn invalid_search_syntax_is_rejected_with_the_offending_token() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let test_cases = vec![
+        "after:someday",
+        "before:someday",
+        "\"unterminated",
+        "-tag:work",
+    ];
+
+    for query in test_cases {
+        let response = app.search_notes(query).await;
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not reject the search '{}'",
+            query
+        );
+        let body = response.text().await.unwrap();
+        assert!(body.contains(query), "'{}' does not name '{}'", body, query);
+    }
+}
+
+#[tokio::test]
+async fn fuzzy_match_tolerates_typos_and_reports_scores() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    app.post_note(&serde_json::json!({
+        "title": "Weekly meeting",
+        "content": "Agenda for the weekly sync"
+    }))
+    .await;
+    app.post_note(&serde_json::json!({
+        "title": "Groceries",
+        "content": "Apples and pears"
+    }))
+    .await;
+
+    let response = app.search_notes("meetng").await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["total_count"], 0);
+
+    let response = app
+        .get_notes_with_query("search=meetng&match=fuzzy&sort=relevance")
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["total_count"], 1);
+    assert_eq!(body["notes"][0]["title"], "Weekly meeting");
+    let score = body["notes"][0]["score"].as_f64().unwrap();
+    assert!(score > 0.0 && score <= 1.0);
+}
+
+#[tokio::test]
+async fn prefix_match_finds_partial_words() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    app.post_note(&serde_json::json!({
+        "title": "Cluster setup",
+        "content": "Notes on kubernetes deployments"
+    }))
+    .await;
+
+    let response = app.search_notes("kube").await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["total_count"], 0);
+
+    let response = app.get_notes_with_query("search=kube&match=prefix").await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["total_count"], 1);
+    assert!(body["notes"][0].get("score").is_none());
+}
+
+#[tokio::test]
+async fn invalid_match_modes_are_rejected() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let test_cases = vec![
+        ("search=kube&match=exact", "an unknown match mode"),
+        (
+            "search=meeting%20-draft&match=fuzzy",
+            "an exclusion in fuzzy mode",
+        ),
+        (
+            "search=%22weekly%20sync%22&match=prefix",
+            "a phrase in prefix mode",
+        ),
+    ];
+
+    for (query, description) in test_cases {
+        let response = app.get_notes_with_query(query).await;
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not reject a search with {}",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn title_matches_rank_above_content_matches() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    app.post_note(&serde_json::json!({
+        "title": "Weekend",
+        "content": "Spent the afternoon in the garden"
+    }))
+    .await;
+    app.post_note(&serde_json::json!({
+        "title": "Garden",
+        "content": "Spent the afternoon outside"
+    }))
+    .await;
+
+    let response = app
+        .get_notes_with_query("search=garden&sort=relevance")
+        .await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["total_count"], 2);
+    assert_eq!(body["notes"][0]["title"], "Garden");
+    assert_eq!(body["notes"][1]["title"], "Weekend");
+}
@@ -0,0 +1,139 @@
+use crate::helpers::{spawn_app, TestApp};
+
+/// Creates a note carrying `tags`, creating any tag that does not exist yet.
+async fn create_note_with_tags(app: &TestApp, title: &str, tags: &[&str]) {
+    let note: serde_json::Value = app
+        .post_note(&serde_json::json!({"title": title, "content": "Content"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let note_id = note["note_id"].as_str().unwrap();
+
+    let existing: Vec<serde_json::Value> = app.get_tags().await.json().await.unwrap();
+    for tag in tags {
+        let tag_id = match existing.iter().find(|t| t["name"] == *tag) {
+            Some(t) => t["tag_id"].as_str().unwrap().to_string(),
+            None => {
+                let created: serde_json::Value = app
+                    .post_tag(&serde_json::json!({"name": tag}))
+                    .await
+                    .json()
+                    .await
+                    .unwrap();
+                created["tag_id"].as_str().unwrap().to_string()
+            }
+        };
+        app.add_tag_to_note(note_id, &tag_id).await;
+    }
+}
+
+async fn titles_for(app: &TestApp, query: &str) -> Vec<String> {
+    let response = app
+        .get_notes_with_query(&format!("sort=title&order=asc&{}", query))
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(
+        body["total_count"].as_u64().unwrap() as usize,
+        body["notes"].as_array().unwrap().len()
+    );
+    body["notes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n["title"].as_str().unwrap().to_string())
+        .collect()
+}
+
+async fn seed(app: &TestApp) {
+    create_note_with_tags(app, "Both", &["work", "urgent"]).await;
+    create_note_with_tags(app, "Home", &["home"]).await;
+    create_note_with_tags(app, "Plain", &[]).await;
+    create_note_with_tags(app, "Work", &["work"]).await;
+}
+
+#[tokio::test]
+async fn tags_in_all_mode_require_every_tag() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    seed(&app).await;
+
+    assert_eq!(titles_for(&app, "tags=work,urgent").await, vec!["Both"]);
+    assert_eq!(
+        titles_for(&app, "tags=work,urgent&tag_mode=all").await,
+        vec!["Both"]
+    );
+}
+
+#[tokio::test]
+async fn tags_in_any_mode_require_one_tag() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    seed(&app).await;
+
+    assert_eq!(
+        titles_for(&app, "tags=urgent,home&tag_mode=any").await,
+        vec!["Both", "Home"]
+    );
+}
+
+#[tokio::test]
+async fn exclude_tags_removes_matching_notes() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    seed(&app).await;
+
+    assert_eq!(
+        titles_for(&app, "exclude_tags=urgent,home").await,
+        vec!["Plain", "Work"]
+    );
+    assert_eq!(
+        titles_for(&app, "tags=work&exclude_tags=urgent").await,
+        vec!["Work"]
+    );
+}
+
+#[tokio::test]
+async fn untagged_returns_notes_without_tags() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    seed(&app).await;
+
+    assert_eq!(titles_for(&app, "untagged=true").await, vec!["Plain"]);
+}
+
+#[tokio::test]
+async fn single_tag_filter_still_works() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    seed(&app).await;
+
+    assert_eq!(titles_for(&app, "tag=work").await, vec!["Both", "Work"]);
+}
+
+#[tokio::test]
+async fn invalid_tag_filters_are_rejected() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let test_cases = vec![
+        ("tags=work&tag_mode=some", "unknown tag mode"),
+        ("tags=work&untagged=true", "untagged combined with tags"),
+        (
+            "tags=work&exclude_tags=work",
+            "tag both included and excluded",
+        ),
+        ("tags=not%20valid!", "invalid tag name"),
+    ];
+
+    for (query, description) in test_cases {
+        let response = app.get_notes_with_query(query).await;
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not reject the request when the query had {}",
+            description
+        );
+    }
+}
@@ -188,3 +188,74 @@ async fn updated_at_changes_after_update() {
 
     assert_ne!(original_updated_at, new_updated_at);
 }
+
+#[tokio::test]
+async fn update_replaces_or_merges_tags() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let note = serde_json::json!({
+        "title": "Title",
+        "content": "Content",
+        "tags": ["work", "old"]
+    });
+    let created: serde_json::Value = app.post_note(&note).await.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+
+    let update = serde_json::json!({"tags": ["work", "ideas"]});
+    let response = app.put_note(note_id, &update).await;
+    assert_eq!(200, response.status().as_u16());
+    let updated: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(updated["tags"], serde_json::json!(["ideas", "work"]));
+
+    let update = serde_json::json!({"tags": ["urgent"], "tags_mode": "merge"});
+    let updated: serde_json::Value = app.put_note(note_id, &update).await.json().await.unwrap();
+    assert_eq!(
+        updated["tags"],
+        serde_json::json!(["ideas", "urgent", "work"])
+    );
+
+    // Leaving `tags` out keeps them
+    let update = serde_json::json!({"title": "New Title"});
+    let updated: serde_json::Value = app.put_note(note_id, &update).await.json().await.unwrap();
+    assert_eq!(
+        updated["tags"],
+        serde_json::json!(["ideas", "urgent", "work"])
+    );
+
+    let update = serde_json::json!({"tags": []});
+    let updated: serde_json::Value = app.put_note(note_id, &update).await.json().await.unwrap();
+    assert_eq!(updated["tags"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn update_with_invalid_tags_changes_nothing() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let note = serde_json::json!({"title": "Title", "content": "Content", "tags": ["work"]});
+    let created: serde_json::Value = app.post_note(&note).await.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+
+    for (update, description) in [
+        (
+            serde_json::json!({"title": "New Title", "tags": ["not valid!"]}),
+            "invalid tag",
+        ),
+        (
+            serde_json::json!({"tags": ["ideas"], "tags_mode": "append"}),
+            "unknown mode",
+        ),
+        (
+            serde_json::json!({"tags_mode": "merge"}),
+            "mode without tags",
+        ),
+    ] {
+        let response = app.put_note(note_id, &update).await;
+        assert_eq!(400, response.status().as_u16(), "Accepted {}", description);
+    }
+
+    let fetched: serde_json::Value = app.get_note_by_id(note_id).await.json().await.unwrap();
+    assert_eq!(fetched["title"], "Title");
+    assert_eq!(fetched["tags"], serde_json::json!(["work"]));
+}
@@ -0,0 +1,182 @@
+use crate::helpers::{spawn_app, TestApp};
+
+async fn create_notes(app: &TestApp) {
+    for (title, content) in [
+        ("Meeting notes", "Quarterly planning meeting"),
+        ("Meeting agenda", "Agenda for the planning meeting"),
+        ("Shopping", "Milk and bread"),
+    ] {
+        app.post_note(&serde_json::json!({"title": title, "content": content}))
+            .await;
+    }
+}
+
+#[tokio::test]
+async fn saved_search_crud_works() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let response = app
+        .post_search(&serde_json::json!({
+            "name": "Meetings",
+            "query": {"search": "meeting", "sort": "title", "order": "asc"}
+        }))
+        .await;
+    assert_eq!(201, response.status().as_u16());
+    let created: serde_json::Value = response.json().await.unwrap();
+    let search_id = created["search_id"].as_str().unwrap();
+    assert_eq!(created["query"]["search"], "meeting");
+
+    let response = app.get_search(search_id).await;
+    assert_eq!(200, response.status().as_u16());
+
+    let response = app
+        .put_search(
+            search_id,
+            &serde_json::json!({"name": "Planning", "query": {"search": "planning"}}),
+        )
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let updated: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(updated["name"], "Planning");
+    assert_eq!(updated["query"]["search"], "planning");
+    assert!(updated["query"]["sort"].is_null());
+
+    let searches: Vec<serde_json::Value> = app.get_searches().await.json().await.unwrap();
+    assert_eq!(searches.len(), 1);
+
+    let response = app.delete_search(search_id).await;
+    assert_eq!(204, response.status().as_u16());
+    let response = app.get_search(search_id).await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn running_a_saved_search_matches_the_notes_list() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    create_notes(&app).await;
+
+    let created: serde_json::Value = app
+        .post_search(&serde_json::json!({
+            "name": "Meetings",
+            "query": {"search": "meeting", "sort": "title", "order": "asc"}
+        }))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let search_id = created["search_id"].as_str().unwrap();
+
+    let saved: serde_json::Value = app
+        .get_search_notes(search_id, "page_size=1&page=2")
+        .await
+        .json()
+        .await
+        .unwrap();
+    let listed: serde_json::Value = app
+        .get_notes_with_query("search=meeting&sort=title&order=asc&page_size=1&page=2")
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!(saved, listed);
+    assert_eq!(saved["total_count"], 2);
+    assert_eq!(saved["notes"][0]["title"], "Meeting notes");
+}
+
+#[tokio::test]
+async fn saved_search_can_filter_by_a_single_tag() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    create_notes(&app).await;
+    app.post_note(&serde_json::json!({
+        "title": "Roadmap",
+        "content": "Next quarter",
+        "tags": ["work"]
+    }))
+    .await;
+
+    let created: serde_json::Value = app
+        .post_search(&serde_json::json!({"name": "Work", "query": {"tag": "work"}}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(created["query"]["tag"], "work");
+    let search_id = created["search_id"].as_str().unwrap();
+
+    let saved: serde_json::Value = app
+        .get_search_notes(search_id, "")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(saved["total_count"], 1);
+    assert_eq!(saved["notes"][0]["title"], "Roadmap");
+}
+
+#[tokio::test]
+async fn saved_searches_with_invalid_filters_are_rejected() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let test_cases = vec![
+        (serde_json::json!({"name": "", "query": {}}), "empty name"),
+        (
+            serde_json::json!({"name": "Bad", "query": {"search": "before:someday"}}),
+            "invalid search syntax",
+        ),
+        (
+            serde_json::json!({"name": "Bad", "query": {"tags": "a", "tag_mode": "some"}}),
+            "invalid tag mode",
+        ),
+        (
+            serde_json::json!({"name": "Bad", "query": {"tgas": "work"}}),
+            "misspelled filter",
+        ),
+    ];
+
+    for (body, description) in test_cases {
+        let response = app.post_search(&body).await;
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "The API did not reject a saved search with {}",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn duplicate_saved_search_names_are_rejected() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let body = serde_json::json!({"name": "Meetings", "query": {"search": "meeting"}});
+    app.post_search(&body).await;
+    let response = app.post_search(&body).await;
+
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn saved_searches_are_private_to_their_owner() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let created: serde_json::Value = app
+        .post_search(&serde_json::json!({"name": "Mine", "query": {}}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let search_id = created["search_id"].as_str().unwrap();
+
+    app.post_logout().await;
+    let _other = app.test_user_with_email("other@example.com").await;
+
+    let response = app.get_search_notes(search_id, "").await;
+    assert_eq!(404, response.status().as_u16());
+}
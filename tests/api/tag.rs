@@ -172,3 +172,224 @@ async fn adding_same_tag_twice_is_idempotent() {
     let tags = body["notes"][0]["tags"].as_array().unwrap();
     assert_eq!(tags.len(), 1);
 }
+
+#[tokio::test]
+async fn list_tags_reports_note_counts() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let note: serde_json::Value = app
+        .post_note(&serde_json::json!({"title": "Note", "content": "Content"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let note_id = note["note_id"].as_str().unwrap();
+
+    let work: serde_json::Value = app
+        .post_tag(&serde_json::json!({"name": "work"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(work["note_count"], 0);
+    app.post_tag(&serde_json::json!({"name": "unused"})).await;
+    app.add_tag_to_note(note_id, work["tag_id"].as_str().unwrap())
+        .await;
+
+    let tags: Vec<serde_json::Value> = app.get_tags().await.json().await.unwrap();
+    assert_eq!(tags[0]["name"], "unused");
+    assert_eq!(tags[0]["note_count"], 0);
+    assert_eq!(tags[1]["name"], "work");
+    assert_eq!(tags[1]["note_count"], 1);
+}
+
+#[tokio::test]
+async fn rename_tag_succeeds() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let tag: serde_json::Value = app
+        .post_tag(&serde_json::json!({"name": "work"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let tag_id = tag["tag_id"].as_str().unwrap();
+
+    let response = app
+        .patch_tag(tag_id, &serde_json::json!({"name": "Office"}))
+        .await;
+
+    assert_eq!(200, response.status().as_u16());
+    let renamed: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(renamed["tag_id"], tag_id);
+    assert_eq!(renamed["name"], "office");
+}
+
+#[tokio::test]
+async fn rename_tag_rejects_invalid_and_duplicate_names() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let tag: serde_json::Value = app
+        .post_tag(&serde_json::json!({"name": "work"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let tag_id = tag["tag_id"].as_str().unwrap();
+    app.post_tag(&serde_json::json!({"name": "home"})).await;
+
+    let response = app
+        .patch_tag(tag_id, &serde_json::json!({"name": "not valid!"}))
+        .await;
+    assert_eq!(400, response.status().as_u16());
+
+    let response = app
+        .patch_tag(tag_id, &serde_json::json!({"name": "home"}))
+        .await;
+    assert_eq!(409, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn delete_tag_removes_it_from_notes() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let note: serde_json::Value = app
+        .post_note(&serde_json::json!({"title": "Note", "content": "Content"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let note_id = note["note_id"].as_str().unwrap();
+    let tag: serde_json::Value = app
+        .post_tag(&serde_json::json!({"name": "work"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let tag_id = tag["tag_id"].as_str().unwrap();
+    app.add_tag_to_note(note_id, tag_id).await;
+
+    let response = app.delete_tag(tag_id).await;
+    assert_eq!(204, response.status().as_u16());
+
+    let tags: Vec<serde_json::Value> = app.get_tags().await.json().await.unwrap();
+    assert!(tags.is_empty());
+    let note: serde_json::Value = app.get_note_by_id(note_id).await.json().await.unwrap();
+    assert_eq!(note["tags"].as_array().unwrap().len(), 0);
+
+    let response = app.delete_tag(tag_id).await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn merge_tags_repoints_notes_to_the_target() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let mut note_ids = Vec::new();
+    for title in ["First", "Second"] {
+        let note: serde_json::Value = app
+            .post_note(&serde_json::json!({"title": title, "content": "Content"}))
+            .await
+            .json()
+            .await
+            .unwrap();
+        note_ids.push(note["note_id"].as_str().unwrap().to_string());
+    }
+    let source: serde_json::Value = app
+        .post_tag(&serde_json::json!({"name": "todo"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let target: serde_json::Value = app
+        .post_tag(&serde_json::json!({"name": "tasks"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let source_id = source["tag_id"].as_str().unwrap();
+    let target_id = target["tag_id"].as_str().unwrap();
+
+    // The first note carries both tags, so the merge must not duplicate it
+    app.add_tag_to_note(&note_ids[0], source_id).await;
+    app.add_tag_to_note(&note_ids[0], target_id).await;
+    app.add_tag_to_note(&note_ids[1], source_id).await;
+
+    let response = app.merge_tags(source_id, target_id).await;
+
+    assert_eq!(200, response.status().as_u16());
+    let merged: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(merged["tag_id"], target_id);
+    assert_eq!(merged["note_count"], 2);
+
+    let tags: Vec<serde_json::Value> = app.get_tags().await.json().await.unwrap();
+    assert_eq!(tags.len(), 1);
+    assert_eq!(tags[0]["name"], "tasks");
+}
+
+#[tokio::test]
+async fn merging_a_tag_into_itself_is_rejected() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let tag: serde_json::Value = app
+        .post_tag(&serde_json::json!({"name": "work"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let tag_id = tag["tag_id"].as_str().unwrap();
+
+    let response = app.merge_tags(tag_id, tag_id).await;
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn renaming_or_deleting_a_tag_changes_the_etag_of_tagged_notes() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let note: serde_json::Value = app
+        .post_note(&serde_json::json!({"title": "Note", "content": "Content"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let note_id = note["note_id"].as_str().unwrap();
+    let tag: serde_json::Value = app
+        .post_tag(&serde_json::json!({"name": "work"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let tag_id = tag["tag_id"].as_str().unwrap();
+    app.add_tag_to_note(note_id, tag_id).await;
+
+    let etag_of = |response: &reqwest::Response| response.headers()["ETag"].clone();
+    let response = app.get_note_by_id(note_id).await;
+    let etag = etag_of(&response);
+    let tagged: serde_json::Value = response.json().await.unwrap();
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+    app.patch_tag(tag_id, &serde_json::json!({"name": "office"}))
+        .await;
+    let response = app
+        .get_note_if_none_match(note_id, etag.to_str().unwrap())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let renamed_etag = etag_of(&response);
+    let renamed: serde_json::Value = response.json().await.unwrap();
+    assert_ne!(tagged["updated_at"], renamed["updated_at"]);
+
+    app.delete_tag(tag_id).await;
+    let response = app
+        .get_note_if_none_match(note_id, renamed_etag.to_str().unwrap())
+        .await;
+    assert_eq!(200, response.status().as_u16());
+}
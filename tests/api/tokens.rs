@@ -0,0 +1,216 @@
+use crate::helpers::spawn_app;
+use reqwest::Method;
+
+#[tokio::test]
+async fn creating_a_token_returns_the_plaintext_once() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let response = app
+        .post_token(&serde_json::json!({"name": "cli", "scopes": ["read", "write"]}))
+        .await;
+    assert_eq!(201, response.status().as_u16());
+
+    let created: serde_json::Value = response.json().await.unwrap();
+    assert!(created["token"].as_str().unwrap().starts_with("jot_"));
+    assert_eq!(created["name"], "cli");
+
+    let tokens: Vec<serde_json::Value> = app.get_tokens().await.json().await.unwrap();
+    assert_eq!(tokens.len(), 1);
+    assert!(tokens[0].get("token").is_none());
+}
+
+#[tokio::test]
+async fn invalid_token_requests_are_rejected() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let test_cases = vec![
+        (
+            serde_json::json!({"name": "", "scopes": ["read"]}),
+            "empty name",
+        ),
+        (
+            serde_json::json!({"name": "cli", "scopes": []}),
+            "no scopes",
+        ),
+        (
+            serde_json::json!({"name": "cli", "scopes": ["admin"]}),
+            "unknown scope",
+        ),
+        (
+            serde_json::json!({
+                "name": "cli",
+                "scopes": ["read"],
+                "expires_at": "2000-01-01T00:00:00Z"
+            }),
+            "expiry in the past",
+        ),
+    ];
+
+    for (body, description) in test_cases {
+        let response = app.post_token(&body).await;
+        assert_eq!(
+            400,
+            response.status().as_u16(),
+            "Should reject: {}",
+            description
+        );
+    }
+}
+
+#[tokio::test]
+async fn bearer_token_authenticates_requests() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+    app.post_note(&serde_json::json!({"title": "Note", "content": "Content"}))
+        .await;
+
+    let created: serde_json::Value = app
+        .post_token(&serde_json::json!({"name": "cli", "scopes": ["read"]}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let token = created["token"].as_str().unwrap();
+
+    let response = app
+        .with_bearer(Method::GET, "/notes", token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["total_count"], 1);
+
+    let tokens: Vec<serde_json::Value> = app.get_tokens().await.json().await.unwrap();
+    assert!(tokens[0]["last_used_at"].is_string());
+}
+
+#[tokio::test]
+async fn read_only_token_cannot_write() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let created: serde_json::Value = app
+        .post_token(&serde_json::json!({"name": "reader", "scopes": ["read"]}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let token = created["token"].as_str().unwrap();
+
+    let response = app
+        .with_bearer(Method::POST, "/notes", token)
+        .json(&serde_json::json!({"title": "Note", "content": "Content"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(403, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn revoked_or_unknown_tokens_are_rejected() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let created: serde_json::Value = app
+        .post_token(&serde_json::json!({"name": "cli", "scopes": ["read"]}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let token = created["token"].as_str().unwrap();
+    let token_id = created["token_id"].as_str().unwrap();
+
+    let response = app.delete_token(token_id).await;
+    assert_eq!(204, response.status().as_u16());
+
+    let response = app
+        .with_bearer(Method::GET, "/notes", token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(401, response.status().as_u16());
+
+    let response = app
+        .with_bearer(Method::GET, "/notes", "jot_not-a-real-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn expired_tokens_are_rejected() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let created: serde_json::Value = app
+        .post_token(&serde_json::json!({"name": "cli", "scopes": ["read"]}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let token = created["token"].as_str().unwrap();
+    let token_id = uuid::Uuid::parse_str(created["token_id"].as_str().unwrap()).unwrap();
+
+    sqlx::query(
+        "UPDATE api_tokens SET expires_at = NOW() - INTERVAL '1 minute' WHERE token_id = $1",
+    )
+    .bind(token_id)
+    .execute(&app.db_pool)
+    .await
+    .unwrap();
+
+    let response = app
+        .with_bearer(Method::GET, "/notes", token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn tokens_cannot_manage_tokens() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let created: serde_json::Value = app
+        .post_token(&serde_json::json!({"name": "cli", "scopes": ["read", "write"]}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let token = created["token"].as_str().unwrap();
+    let token_id = created["token_id"].as_str().unwrap();
+
+    let response = app
+        .with_bearer(Method::POST, "/users/me/tokens", token)
+        .json(&serde_json::json!({"name": "forever", "scopes": ["write"]}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(403, response.status().as_u16());
+
+    let response = app
+        .with_bearer(Method::GET, "/users/me/tokens", token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(403, response.status().as_u16());
+
+    let response = app
+        .with_bearer(
+            Method::DELETE,
+            &format!("/users/me/tokens/{}", token_id),
+            token,
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(403, response.status().as_u16());
+
+    let tokens: Vec<serde_json::Value> = app.get_tokens().await.json().await.unwrap();
+    assert_eq!(tokens.len(), 1);
+}
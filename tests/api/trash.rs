@@ -0,0 +1,198 @@
+use crate::helpers::spawn_app;
+use jot::trash_purge_worker::purge_trashed_notes;
+
+#[tokio::test]
+async fn trash_requires_authentication() {
+    let app = spawn_app().await;
+
+    let response = app.get_trash().await;
+    assert_eq!(401, response.status().as_u16());
+
+    let response = app.empty_trash().await;
+    assert_eq!(401, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn deleted_notes_are_moved_to_trash() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let create_response = app
+        .post_note(&serde_json::json!({
+            "title": "Trashed Note",
+            "content": "Searchable content"
+        }))
+        .await;
+    let created: serde_json::Value = create_response.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+
+    let response = app.delete_note(note_id).await;
+    assert_eq!(204, response.status().as_u16());
+
+    let list_response = app.get_notes(None, None).await;
+    let body: serde_json::Value = list_response.json().await.unwrap();
+    assert_eq!(body["total_count"], 0);
+
+    let search_response = app.search_notes("searchable").await;
+    let body: serde_json::Value = search_response.json().await.unwrap();
+    assert_eq!(body["total_count"], 0);
+
+    let response = app.get_trash().await;
+    assert_eq!(200, response.status().as_u16());
+    let trash: Vec<serde_json::Value> = response.json().await.unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0]["note_id"], note_id);
+    assert!(trash[0]["deleted_at"].is_string());
+}
+
+#[tokio::test]
+async fn restoring_a_note_keeps_its_tags() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let create_response = app
+        .post_note(&serde_json::json!({
+            "title": "Tagged Note",
+            "content": "Content"
+        }))
+        .await;
+    let created: serde_json::Value = create_response.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+
+    let tag_response = app.post_tag(&serde_json::json!({"name": "work"})).await;
+    let tag: serde_json::Value = tag_response.json().await.unwrap();
+    app.add_tag_to_note(note_id, tag["tag_id"].as_str().unwrap())
+        .await;
+
+    app.delete_note(note_id).await;
+    let response = app.restore_note(note_id).await;
+    assert_eq!(204, response.status().as_u16());
+
+    let get_response = app.get_note_by_id(note_id).await;
+    assert_eq!(200, get_response.status().as_u16());
+
+    let list_response = app.get_notes(None, None).await;
+    let body: serde_json::Value = list_response.json().await.unwrap();
+    assert_eq!(body["notes"][0]["tags"][0], "work");
+
+    let trash: Vec<serde_json::Value> = app.get_trash().await.json().await.unwrap();
+    assert!(trash.is_empty());
+}
+
+#[tokio::test]
+async fn restoring_a_note_that_is_not_trashed_returns_404() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let create_response = app
+        .post_note(&serde_json::json!({
+            "title": "Live Note",
+            "content": "Content"
+        }))
+        .await;
+    let created: serde_json::Value = create_response.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+
+    let response = app.restore_note(note_id).await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn trashed_notes_cannot_be_tagged_or_have_revisions_restored() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let create_response = app
+        .post_note(&serde_json::json!({
+            "title": "Trashed Note",
+            "content": "Content"
+        }))
+        .await;
+    let created: serde_json::Value = create_response.json().await.unwrap();
+    let note_id = created["note_id"].as_str().unwrap();
+    app.put_note(note_id, &serde_json::json!({"content": "Changed"}))
+        .await;
+    let tag_response = app.post_tag(&serde_json::json!({"name": "work"})).await;
+    let tag: serde_json::Value = tag_response.json().await.unwrap();
+    let tag_id = tag["tag_id"].as_str().unwrap();
+
+    app.delete_note(note_id).await;
+
+    let response = app.add_tag_to_note(note_id, tag_id).await;
+    assert_eq!(404, response.status().as_u16());
+    let response = app.remove_tag_from_note(note_id, tag_id).await;
+    assert_eq!(404, response.status().as_u16());
+    let response = app.get_note_revisions(note_id).await;
+    assert_eq!(404, response.status().as_u16());
+    let response = app.restore_note_revision(note_id, "1").await;
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn emptying_the_trash_only_removes_trashed_notes() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let create_response = app
+        .post_note(&serde_json::json!({"title": "Keep", "content": "Content"}))
+        .await;
+    let kept: serde_json::Value = create_response.json().await.unwrap();
+
+    let create_response = app
+        .post_note(&serde_json::json!({"title": "Discard", "content": "Content"}))
+        .await;
+    let discarded: serde_json::Value = create_response.json().await.unwrap();
+    let discarded_id = discarded["note_id"].as_str().unwrap();
+    app.delete_note(discarded_id).await;
+
+    let response = app.empty_trash().await;
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["purged_count"], 1);
+
+    let response = app.restore_note(discarded_id).await;
+    assert_eq!(404, response.status().as_u16());
+
+    let response = app.get_note_by_id(kept["note_id"].as_str().unwrap()).await;
+    assert_eq!(200, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn purge_removes_only_notes_past_retention() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let create_response = app
+        .post_note(&serde_json::json!({"title": "Old", "content": "Content"}))
+        .await;
+    let old: serde_json::Value = create_response.json().await.unwrap();
+    let old_id = old["note_id"].as_str().unwrap();
+
+    let create_response = app
+        .post_note(&serde_json::json!({"title": "Recent", "content": "Content"}))
+        .await;
+    let recent: serde_json::Value = create_response.json().await.unwrap();
+    let recent_id = recent["note_id"].as_str().unwrap();
+
+    app.delete_note(old_id).await;
+    app.delete_note(recent_id).await;
+
+    sqlx::query("UPDATE notes SET deleted_at = NOW() - INTERVAL '40 days' WHERE note_id = $1")
+        .bind(uuid::Uuid::parse_str(old_id).unwrap())
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    let purged = purge_trashed_notes(
+        &app.db_pool,
+        app.storage.as_ref(),
+        chrono::Duration::days(30),
+    )
+    .await
+    .unwrap();
+    assert_eq!(purged, 1);
+
+    let trash: Vec<serde_json::Value> = app.get_trash().await.json().await.unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0]["note_id"], recent_id);
+}
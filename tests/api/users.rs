@@ -161,3 +161,52 @@ async fn register_hashes_password_properly() {
 
     assert_ne!(saved_user.password_hash, "ValidPass123");
 }
+
+#[tokio::test]
+async fn search_language_defaults_to_english() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let response = app.get_current_user().await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["search_language"], "english");
+}
+
+#[tokio::test]
+async fn changing_search_language_restems_existing_notes() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    app.post_note(&serde_json::json!({
+        "title": "Einkauf",
+        "content": "Wir kaufen die Häuser"
+    }))
+    .await;
+
+    // The German stemmer reduces both "Häuser" and "Haus" to the same lexeme
+    let response = app.search_notes("Haus").await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["total_count"], 0);
+
+    let response = app
+        .patch_current_user(&serde_json::json!({"search_language": "German"}))
+        .await;
+    assert_eq!(200, response.status().as_u16());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["search_language"], "german");
+
+    let response = app.search_notes("Haus").await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["total_count"], 1);
+}
+
+#[tokio::test]
+async fn unsupported_search_language_is_rejected() {
+    let app = spawn_app().await;
+    let _user = app.test_user().await;
+
+    let response = app
+        .patch_current_user(&serde_json::json!({"search_language": "klingon"}))
+        .await;
+    assert_eq!(400, response.status().as_u16());
+}